@@ -23,14 +23,32 @@ fn main() {
         .subcommand(clap::Command::new("path").about("Create a path unit"))
         .subcommand(clap::Command::new("socket").about("Create a socket unit"))
         .subcommand(clap::Command::new("mount").about("Create a mount unit"))
+        .subcommand(clap::Command::new("automount").about("Create an automount unit"))
+        .subcommand(clap::Command::new("swap").about("Create a swap unit"))
         .subcommand(clap::Command::new("target").about("Create a target unit"))
+        .subcommand(clap::Command::new("slice").about("Create a slice unit"))
         .subcommand(clap::Command::new("edit").about("Edit an existing unit"))
         .subcommand(clap::Command::new("show").about("Show a unit file"))
         .subcommand(clap::Command::new("validate").about("Validate a unit file"))
+        .subcommand(clap::Command::new("escape").about("Escape strings for use in unit names"))
+        .subcommand(
+            clap::Command::new("calendar")
+                .about("Check calendar expressions and show when they elapse"),
+        )
         .subcommand(clap::Command::new("status").about("Show unit status"))
         .subcommand(clap::Command::new("logs").about("Show unit logs"))
         .subcommand(clap::Command::new("remove").about("Remove a unit"))
         .subcommand(clap::Command::new("list").about("List units"))
+        .subcommand(
+            clap::Command::new("timers")
+                .about("Show timers with their schedule, last and next run"),
+        )
+        .subcommand(clap::Command::new("at").about("Run a command once at a later time"))
+        .subcommand(clap::Command::new("link").about("Link an existing unit file"))
+        .subcommand(clap::Command::new("instance").about("Manage instances of a template unit"))
+        .subcommand(clap::Command::new("import").about("Convert existing configuration into units"))
+        .subcommand(clap::Command::new("history").about("List recorded operations"))
+        .subcommand(clap::Command::new("undo").about("Undo a recorded operation"))
         .subcommand(clap::Command::new("completions").about("Generate shell completions"));
 
     let man = clap_mangen::Man::new(cmd);
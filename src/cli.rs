@@ -4,6 +4,10 @@
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 
+use crate::systemd::listen::{self, ListenAddress};
+use crate::systemd::resource::{self, ByteLimit, CpuQuota, FileLimit, TasksLimit, Weight};
+use crate::systemd::timespan::Timespan;
+
 /// A CLI tool for generating systemd unit files
 #[derive(Parser, Debug)]
 #[command(name = "mkunit")]
@@ -32,6 +36,8 @@ pub struct Cli {
     pub no_color: bool,
 }
 
+// Parsed once at startup, so the size of the largest variant doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Create a service unit
@@ -49,9 +55,18 @@ pub enum Commands {
     /// Create a mount unit
     Mount(MountArgs),
 
+    /// Create an automount unit
+    Automount(AutomountArgs),
+
+    /// Create a swap unit
+    Swap(SwapArgs),
+
     /// Create a target unit
     Target(TargetArgs),
 
+    /// Create a slice unit with resource limits
+    Slice(SliceArgs),
+
     /// Edit an existing unit
     Edit(EditArgs),
 
@@ -61,6 +76,12 @@ pub enum Commands {
     /// Validate a unit file
     Validate(ValidateArgs),
 
+    /// Escape strings for use in unit names
+    Escape(EscapeArgs),
+
+    /// Check calendar expressions and show when they elapse
+    Calendar(CalendarArgs),
+
     /// Show unit status
     Status(StatusArgs),
 
@@ -73,15 +94,33 @@ pub enum Commands {
     /// List units
     List(ListArgs),
 
+    /// Show timers with their schedule, last and next run
+    Timers(TimersArgs),
+
+    /// Run a command once at a later time
+    At(AtArgs),
+
     /// Link an existing unit file
     Link(LinkArgs),
 
+    /// Manage instances of a template unit
+    Instance(InstanceArgs),
+
+    /// Convert existing configuration into units
+    Import(ImportArgs),
+
+    /// List recorded operations
+    History(HistoryArgs),
+
+    /// Undo a recorded operation
+    Undo(UndoArgs),
+
     /// Generate shell completions
     Completions(CompletionsArgs),
 }
 
 /// Service type options
-#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
 pub enum ServiceType {
     #[default]
     Simple,
@@ -129,9 +168,37 @@ pub struct ServiceArgs {
     /// Service name
     pub name: String,
 
-    /// Command to run
-    #[arg(short = 'e', long)]
-    pub exec: Option<String>,
+    /// Command to run (repeatable for oneshot services)
+    ///
+    /// Prefix the command with '-' to ignore failure, '+' to run with full
+    /// privileges, '!' to skip the User=/Group= change, or '@' to pass the
+    /// second word as argv[0].
+    #[arg(short = 'e', long, allow_hyphen_values = true)]
+    pub exec: Vec<String>,
+
+    /// Command to run before the main command (can be repeated)
+    #[arg(long, allow_hyphen_values = true)]
+    pub exec_start_pre: Vec<String>,
+
+    /// Command to run after the main command started (can be repeated)
+    #[arg(long, allow_hyphen_values = true)]
+    pub exec_start_post: Vec<String>,
+
+    /// Command to reload the service's configuration (can be repeated)
+    #[arg(long, allow_hyphen_values = true)]
+    pub exec_reload: Vec<String>,
+
+    /// Command to stop the service (can be repeated)
+    #[arg(long, allow_hyphen_values = true)]
+    pub exec_stop: Vec<String>,
+
+    /// Command to run after the service stopped (can be repeated)
+    #[arg(long, allow_hyphen_values = true)]
+    pub exec_stop_post: Vec<String>,
+
+    /// Treat % and $ in commands literally instead of as specifiers and variables
+    #[arg(long)]
+    pub literal: bool,
 
     /// Unit description
     #[arg(short, long)]
@@ -153,9 +220,17 @@ pub struct ServiceArgs {
     #[arg(short, long, value_enum, default_value_t = RestartPolicy::OnFailure)]
     pub restart: RestartPolicy,
 
-    /// Seconds between restarts
-    #[arg(long, default_value_t = 5)]
-    pub restart_sec: u32,
+    /// Time to wait between restarts (e.g., 5s, 1min)
+    #[arg(long, default_value = "5s")]
+    pub restart_sec: Timespan,
+
+    /// Time allowed for startup before the service is considered failed
+    #[arg(long)]
+    pub timeout_start_sec: Option<Timespan>,
+
+    /// Time allowed for stopping before the service is killed
+    #[arg(long)]
+    pub timeout_stop_sec: Option<Timespan>,
 
     /// Service type
     #[arg(short = 't', long = "type", value_enum, default_value_t = ServiceType::Simple)]
@@ -185,6 +260,14 @@ pub struct ServiceArgs {
     #[arg(long, default_value = "default.target")]
     pub wanted_by: String,
 
+    /// Create a template unit (name@.service) for instances
+    #[arg(long)]
+    pub template: bool,
+
+    /// Instance enabled when the template itself is enabled
+    #[arg(long, requires = "template")]
+    pub default_instance: Option<String>,
+
     /// Create as system service
     #[arg(long)]
     pub system: bool,
@@ -204,6 +287,38 @@ pub struct ServiceArgs {
     /// Apply security hardening defaults
     #[arg(long)]
     pub hardening: bool,
+
+    /// Memory limit above which the service is throttled (e.g., 768M, 50%)
+    #[arg(long)]
+    pub memory_high: Option<ByteLimit>,
+
+    /// Hard memory limit (e.g., 1G, 50%, infinity)
+    #[arg(long)]
+    pub memory_max: Option<ByteLimit>,
+
+    /// CPU time relative to one CPU (e.g., 50%, 200%)
+    #[arg(long)]
+    pub cpu_quota: Option<CpuQuota>,
+
+    /// Relative CPU share, 1-10000 (default 100)
+    #[arg(long)]
+    pub cpu_weight: Option<Weight>,
+
+    /// Relative IO share, 1-10000 (default 100)
+    #[arg(long)]
+    pub io_weight: Option<Weight>,
+
+    /// Maximum number of tasks (e.g., 512, 10%, infinity)
+    #[arg(long)]
+    pub tasks_max: Option<TasksLimit>,
+
+    /// Open file limit (e.g., 65536 or SOFT:HARD)
+    #[arg(long)]
+    pub limit_nofile: Option<FileLimit>,
+
+    /// Slice to place the service in (e.g., apps-web)
+    #[arg(long, value_parser = resource::slice_name)]
+    pub slice: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -219,42 +334,81 @@ pub struct TimerArgs {
     #[arg(short, long)]
     pub description: Option<String>,
 
-    /// Calendar expression (e.g., "daily", "*-*-* 04:00:00")
+    /// Calendar expression (e.g., "daily", "*-*-* 04:00:00"; can be repeated)
+    #[arg(long)]
+    pub on_calendar: Vec<String>,
+
+    /// Schedule in plain English (e.g., "weekday at 9am", "15 minutes")
+    #[arg(long)]
+    pub every: Option<String>,
+
+    /// Time after boot (e.g., 5min; can be repeated)
+    #[arg(long)]
+    pub on_boot: Vec<Timespan>,
+
+    /// Time after the service manager started (can be repeated)
     #[arg(long)]
-    pub on_calendar: Option<String>,
+    pub on_startup: Vec<Timespan>,
 
-    /// Seconds after boot
+    /// Time after the timer was activated (can be repeated)
     #[arg(long)]
-    pub on_boot: Option<String>,
+    pub on_active: Vec<Timespan>,
 
-    /// Seconds after systemd start
+    /// Time after the unit was last activated (can be repeated)
     #[arg(long)]
-    pub on_startup: Option<String>,
+    pub on_unit_active: Vec<Timespan>,
 
-    /// Seconds after timer activation
+    /// Time after the unit was last deactivated (can be repeated)
     #[arg(long)]
-    pub on_active: Option<String>,
+    pub on_unit_inactive: Vec<Timespan>,
 
-    /// Seconds after unit last activated
+    /// Trigger when the system clock jumps
     #[arg(long)]
-    pub on_unit_active: Option<String>,
+    pub on_clock_change: bool,
 
-    /// Seconds after unit last deactivated
+    /// Trigger when the system time zone changes
     #[arg(long)]
-    pub on_unit_inactive: Option<String>,
+    pub on_timezone_change: bool,
 
     /// Catch up missed runs
     #[arg(long)]
     pub persistent: bool,
 
-    /// Random delay up to value
+    /// Random delay up to this long (e.g., 1h)
     #[arg(long)]
-    pub randomize_delay: Option<String>,
+    pub randomize_delay: Option<Timespan>,
+
+    /// Keep the random delay the same across restarts
+    #[arg(long, requires = "randomize_delay")]
+    pub fixed_random_delay: bool,
+
+    /// How precisely the timer elapses (systemd default: 1min)
+    #[arg(long)]
+    pub accuracy: Option<Timespan>,
+
+    /// Wake the system from suspend when the timer elapses
+    #[arg(long)]
+    pub wake_system: bool,
+
+    /// Keep the timer loaded after it elapsed for the last time (systemd default: true)
+    #[arg(long, value_name = "BOOL")]
+    pub remain_after_elapse: Option<bool>,
+
+    #[command(flatten)]
+    pub job: JobArgs,
 
     /// Install target
     #[arg(long, default_value = "timers.target")]
     pub wanted_by: String,
 
+    /// Create a template unit (name@.timer) for instances
+    #[arg(long)]
+    pub template: bool,
+
+    /// Instance enabled when the template itself is enabled
+    #[arg(long, requires = "template")]
+    pub default_instance: Option<String>,
+
     /// Create as system timer
     #[arg(long)]
     pub system: bool,
@@ -268,6 +422,43 @@ pub struct TimerArgs {
     pub output: Option<String>,
 }
 
+/// Options for the service started by a timer, path or socket unit
+///
+/// With `--exec`, the service is created alongside the trigger: a oneshot
+/// service for timers and paths, and a long-running or per-connection service
+/// for sockets.
+#[derive(Args, Debug, Default)]
+pub struct JobArgs {
+    /// Command for the triggered service (repeatable), which creates it as a
+    /// oneshot service
+    #[arg(short = 'e', long, allow_hyphen_values = true)]
+    pub exec: Vec<String>,
+
+    /// Treat % and $ in commands literally instead of as specifiers and variables
+    #[arg(long, requires = "exec")]
+    pub literal: bool,
+
+    /// Working directory of the service
+    #[arg(short, long, requires = "exec")]
+    pub workdir: Option<String>,
+
+    /// Run the service as user
+    #[arg(long, requires = "exec")]
+    pub user: Option<String>,
+
+    /// Run the service as group
+    #[arg(long, requires = "exec")]
+    pub group: Option<String>,
+
+    /// Environment variable for the service (can be repeated)
+    #[arg(long = "env", value_name = "KEY=VALUE", requires = "exec")]
+    pub env: Vec<String>,
+
+    /// Path to environment file for the service
+    #[arg(long, requires = "exec")]
+    pub env_file: Option<String>,
+}
+
 #[derive(Args, Debug)]
 pub struct PathArgs {
     /// Path unit name
@@ -305,10 +496,21 @@ pub struct PathArgs {
     #[arg(long)]
     pub make_directory: bool,
 
+    #[command(flatten)]
+    pub job: JobArgs,
+
     /// Install target
     #[arg(long, default_value = "default.target")]
     pub wanted_by: String,
 
+    /// Create a template unit (name@.path) for instances
+    #[arg(long)]
+    pub template: bool,
+
+    /// Instance enabled when the template itself is enabled
+    #[arg(long, requires = "template")]
+    pub default_instance: Option<String>,
+
     /// Create as system unit
     #[arg(long)]
     pub system: bool,
@@ -322,6 +524,26 @@ pub struct PathArgs {
     pub output: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BindIpv6Only {
+    /// Use the system default (net.ipv6.bindv6only)
+    Default,
+    /// Accept IPv4 and IPv6
+    Both,
+    /// Accept IPv6 only
+    Ipv6Only,
+}
+
+impl std::fmt::Display for BindIpv6Only {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Both => write!(f, "both"),
+            Self::Ipv6Only => write!(f, "ipv6-only"),
+        }
+    }
+}
+
 #[derive(Args, Debug)]
 pub struct SocketArgs {
     /// Socket name
@@ -335,26 +557,79 @@ pub struct SocketArgs {
     #[arg(short, long)]
     pub description: Option<String>,
 
-    /// TCP or Unix stream socket
+    /// TCP or Unix stream socket (repeatable)
+    #[arg(long, value_name = "ADDRESS")]
+    pub listen_stream: Vec<ListenAddress>,
+
+    /// UDP or Unix datagram socket (repeatable)
+    #[arg(long, value_name = "ADDRESS")]
+    pub listen_datagram: Vec<ListenAddress>,
+
+    /// Unix or vsock sequential packet socket (repeatable)
+    #[arg(long, value_name = "ADDRESS")]
+    pub listen_sequential_packet: Vec<ListenAddress>,
+
+    /// Named pipe (repeatable)
+    #[arg(long, value_name = "PATH", value_parser = listen::fifo_path)]
+    pub listen_fifo: Vec<String>,
+
+    /// Netlink socket as family and optional multicast group, e.g.
+    /// "kobject-uevent 1" (repeatable)
+    #[arg(long, value_name = "FAMILY [GROUP]", value_parser = listen::netlink)]
+    pub listen_netlink: Vec<String>,
+
+    /// Owner of unix sockets and FIFOs
+    #[arg(long)]
+    pub socket_user: Option<String>,
+
+    /// Group of unix sockets and FIFOs
+    #[arg(long)]
+    pub socket_group: Option<String>,
+
+    /// Access mode of unix sockets and FIFOs, e.g. 0660
     #[arg(long)]
-    pub listen_stream: Option<String>,
+    pub socket_mode: Option<String>,
+
+    /// Name the service sees the file descriptors under (`$LISTEN_FDNAMES`)
+    #[arg(long, value_parser = listen::fd_name)]
+    pub file_descriptor_name: Option<String>,
+
+    /// Let several sockets bind the same port (`SO_REUSEPORT`)
+    #[arg(long)]
+    pub reuse_port: bool,
+
+    /// Whether IPv6 sockets also accept IPv4 connections
+    #[arg(long, value_enum)]
+    pub bind_ipv6_only: Option<BindIpv6Only>,
 
-    /// UDP or Unix datagram socket
+    /// Length of the queue of pending connections
     #[arg(long)]
-    pub listen_datagram: Option<String>,
+    pub backlog: Option<u32>,
 
-    /// Named pipe
+    /// Send data without waiting to fill packets (`TCP_NODELAY`)
     #[arg(long)]
-    pub listen_fifo: Option<String>,
+    pub no_delay: bool,
+
+    /// Send TCP keep-alive probes on idle connections
+    #[arg(long)]
+    pub keep_alive: bool,
 
     /// Spawn instance per connection
     #[arg(long)]
     pub accept: bool,
 
     /// Connection limit
-    #[arg(long)]
+    #[arg(long, requires = "accept")]
     pub max_connections: Option<u32>,
 
+    /// Create a template unit (name@.socket) for instances
+    #[arg(long)]
+    pub template: bool,
+
+    /// Instance enabled when the template itself is enabled
+    #[arg(long, requires = "template")]
+    pub default_instance: Option<String>,
+
     /// Create as system unit
     #[arg(long)]
     pub system: bool,
@@ -366,12 +641,15 @@ pub struct SocketArgs {
     /// Write to path instead of installing
     #[arg(short, long)]
     pub output: Option<String>,
+
+    #[command(flatten)]
+    pub job: JobArgs,
 }
 
 #[derive(Args, Debug)]
 pub struct MountArgs {
-    /// Mount unit name
-    pub name: String,
+    /// Mount unit name [default: derived from --where, e.g. mnt-data]
+    pub name: Option<String>,
 
     /// Source device/path
     #[arg(long)]
@@ -393,11 +671,115 @@ pub struct MountArgs {
     #[arg(short, long)]
     pub description: Option<String>,
 
+    /// Install target [default: multi-user.target, or local-fs.target/remote-fs.target with --automount]
+    #[arg(long)]
+    pub wanted_by: Option<String>,
+
+    /// Also create an automount unit that mounts on first access
+    #[arg(long)]
+    pub automount: bool,
+
+    /// Unmount after being idle this long (e.g., 10min)
+    #[arg(long, requires = "automount")]
+    pub timeout_idle_sec: Option<Timespan>,
+
+    /// Mode for created mount point directories (e.g., 0755)
+    #[arg(long, requires = "automount")]
+    pub directory_mode: Option<String>,
+
+    /// Extra mount options for the automount (systemd 250+)
+    #[arg(long, requires = "automount")]
+    pub extra_options: Option<String>,
+
+    /// Create as system unit (default for mount)
+    #[arg(long, default_value_t = true)]
+    pub system: bool,
+
+    /// Install and enable
+    #[arg(short, long)]
+    pub install: bool,
+
+    /// Write to path instead of installing
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct AutomountArgs {
+    /// Automount unit name [default: derived from --where, e.g. mnt-data]
+    pub name: Option<String>,
+
+    /// Mount point
+    #[arg(long = "where")]
+    pub mount_where: Option<String>,
+
+    /// Unmount after being idle this long (e.g., 10min)
+    #[arg(long)]
+    pub timeout_idle_sec: Option<Timespan>,
+
+    /// Mode for created mount point directories (e.g., 0755)
+    #[arg(long)]
+    pub directory_mode: Option<String>,
+
+    /// Extra mount options (systemd 250+)
+    #[arg(long)]
+    pub extra_options: Option<String>,
+
+    /// Unit description
+    #[arg(short, long)]
+    pub description: Option<String>,
+
+    /// Install target (use remote-fs.target for network filesystems)
+    #[arg(long, default_value = "local-fs.target")]
+    pub wanted_by: String,
+
+    /// Create as system unit (default for automount)
+    #[arg(long, default_value_t = true)]
+    pub system: bool,
+
+    /// Install and enable
+    #[arg(short, long)]
+    pub install: bool,
+
+    /// Write to path instead of installing
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct SwapArgs {
+    /// Swap unit name [default: derived from --what, e.g. swapfile]
+    pub name: Option<String>,
+
+    /// Swap device or file
+    #[arg(long)]
+    pub what: Option<String>,
+
+    /// Swap priority, higher is used first (-1 to 32767)
+    #[arg(long, allow_hyphen_values = true, value_parser = clap::value_parser!(i32).range(-1..=32767))]
+    pub priority: Option<i32>,
+
+    /// Options passed to swapon (e.g., discard)
+    #[arg(long)]
+    pub options: Option<String>,
+
+    /// Time to wait for the swap device (e.g., 90s)
+    #[arg(long)]
+    pub timeout_sec: Option<Timespan>,
+
+    /// Allocate a swapfile of this size at --what and run mkswap (e.g., 2G)
+    #[arg(long, value_name = "SIZE", value_parser = resource::parse_bytes)]
+    pub create_file: Option<u64>,
+
+    /// Unit description
+    #[arg(short, long)]
+    pub description: Option<String>,
+
     /// Install target
-    #[arg(long, default_value = "multi-user.target")]
+    #[arg(long, default_value = "swap.target")]
     pub wanted_by: String,
 
-    /// Create as system unit (default for mount)
+    /// Create as system unit (default for swap)
     #[arg(long, default_value_t = true)]
     pub system: bool,
 
@@ -448,6 +830,49 @@ pub struct TargetArgs {
     pub output: Option<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct SliceArgs {
+    /// Slice name; dashes nest slices (e.g., apps-workers)
+    #[arg(value_parser = resource::slice_name)]
+    pub name: String,
+
+    /// Unit description
+    #[arg(short, long)]
+    pub description: Option<String>,
+
+    /// Memory limit above which the slice is throttled (e.g., 768M, 50%)
+    #[arg(long)]
+    pub memory_high: Option<ByteLimit>,
+
+    /// Hard memory limit shared by all units in the slice (e.g., 4G, 50%)
+    #[arg(long)]
+    pub memory_max: Option<ByteLimit>,
+
+    /// CPU time relative to one CPU (e.g., 50%, 200%)
+    #[arg(long)]
+    pub cpu_quota: Option<CpuQuota>,
+
+    /// Relative CPU share, 1-10000 (default 100)
+    #[arg(long)]
+    pub cpu_weight: Option<Weight>,
+
+    /// Relative IO share, 1-10000 (default 100)
+    #[arg(long)]
+    pub io_weight: Option<Weight>,
+
+    /// Maximum number of tasks (e.g., 4096, 10%, infinity)
+    #[arg(long)]
+    pub tasks_max: Option<TasksLimit>,
+
+    /// Create as system unit
+    #[arg(long)]
+    pub system: bool,
+
+    /// Write to path instead of installing
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
 #[derive(Args, Debug)]
 pub struct EditArgs {
     /// Unit name
@@ -474,6 +899,14 @@ pub struct ShowArgs {
     /// Show system unit
     #[arg(long)]
     pub system: bool,
+
+    /// Preview the unit with specifiers (%i, %h, ...) expanded
+    #[arg(long)]
+    pub expand: bool,
+
+    /// Instance name to use when expanding a template unit
+    #[arg(long, requires = "expand")]
+    pub instance: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -539,6 +972,53 @@ pub struct ListArgs {
     pub all: bool,
 }
 
+#[derive(Args, Debug)]
+pub struct TimersArgs {
+    /// Show system timers
+    #[arg(long)]
+    pub system: bool,
+
+    /// Show both user and system timers
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// Print JSON instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AtArgs {
+    /// When to run: a delay like "2h" or "in 30 minutes", a time like "03:00"
+    /// or "tomorrow 9am", or a date like "2026-12-24 18:00"
+    #[arg(required_unless_present_any = ["list", "cancel"])]
+    pub time: Option<String>,
+
+    /// Command to run, after `--`
+    #[arg(last = true, value_name = "COMMAND")]
+    pub command: Vec<String>,
+
+    /// Job name, used as at-NAME (default: from the time)
+    #[arg(short, long)]
+    pub name: Option<String>,
+
+    /// Working directory of the command
+    #[arg(short, long)]
+    pub workdir: Option<String>,
+
+    /// Schedule a system job
+    #[arg(long)]
+    pub system: bool,
+
+    /// List pending jobs
+    #[arg(short, long, conflicts_with_all = ["time", "cancel"])]
+    pub list: bool,
+
+    /// Cancel a pending job
+    #[arg(short, long, value_name = "JOB", conflicts_with = "time")]
+    pub cancel: Option<String>,
+}
+
 #[derive(Args, Debug)]
 pub struct LinkArgs {
     /// Path to unit file
@@ -561,6 +1041,159 @@ pub struct LinkArgs {
     pub force: bool,
 }
 
+#[derive(Args, Debug)]
+pub struct InstanceArgs {
+    #[command(subcommand)]
+    pub action: InstanceAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InstanceAction {
+    /// Enable and start an instance
+    Add(InstanceTargetArgs),
+
+    /// Stop and disable an instance
+    Remove(InstanceTargetArgs),
+
+    /// List enabled instances
+    List(InstanceListArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct InstanceTargetArgs {
+    /// Template unit (e.g., myapp@ or myapp@.timer)
+    pub template: String,
+
+    /// Instance name
+    pub instance: String,
+
+    /// Manage a system unit
+    #[arg(long)]
+    pub system: bool,
+
+    /// Only enable or disable, don't start or stop
+    #[arg(long)]
+    pub no_start: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct InstanceListArgs {
+    /// Template unit (e.g., myapp@ or myapp@.timer)
+    pub template: String,
+
+    /// List system unit instances
+    #[arg(long)]
+    pub system: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EscapeArgs {
+    /// Strings to escape or unescape
+    #[arg(required = true)]
+    pub strings: Vec<String>,
+
+    /// Treat the strings as file system paths
+    #[arg(short, long)]
+    pub path: bool,
+
+    /// Undo escaping
+    #[arg(short, long, conflicts_with_all = ["suffix", "template"])]
+    pub unescape: bool,
+
+    /// Append this unit type suffix (e.g., mount)
+    #[arg(long, conflicts_with = "template")]
+    pub suffix: Option<String>,
+
+    /// Insert the escaped string as the instance of this template (e.g., foo@.service)
+    #[arg(long)]
+    pub template: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    #[command(subcommand)]
+    pub source: ImportSource,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ImportSource {
+    /// Create mount, automount and swap units from fstab entries
+    Fstab(FstabImportArgs),
+
+    /// Create timer and service pairs from crontab entries
+    Cron(CronImportArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct FstabImportArgs {
+    /// fstab file to read
+    #[arg(default_value = "/etc/fstab")]
+    pub file: String,
+
+    /// Install and enable the imported units
+    #[arg(short, long)]
+    pub install: bool,
+
+    /// Write units into this directory instead of installing them
+    #[arg(short, long, conflicts_with = "install")]
+    pub output_dir: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct CronImportArgs {
+    /// Crontab file to read, or - for stdin (default: your crontab)
+    pub file: Option<String>,
+
+    /// Read a system crontab with a user column and create system units
+    /// (implied for /etc/crontab and /etc/cron.d)
+    #[arg(long)]
+    pub system: bool,
+
+    /// Prefix for the generated unit names
+    #[arg(long, default_value = "cron")]
+    pub prefix: String,
+
+    /// Install, enable and start the imported timers
+    #[arg(short, long)]
+    pub install: bool,
+
+    /// Write units into this directory instead of installing them
+    #[arg(short, long, conflicts_with = "install")]
+    pub output_dir: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct CalendarArgs {
+    /// Calendar expressions (e.g., "Mon..Fri 09:00")
+    #[arg(required = true)]
+    pub expressions: Vec<String>,
+
+    /// Number of upcoming elapse times to show
+    #[arg(short = 'n', long, default_value_t = 5)]
+    pub iterations: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Number of operations to show
+    #[arg(short = 'n', long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Show individual changes for each operation
+    #[arg(short, long)]
+    pub changes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct UndoArgs {
+    /// Operation ID (defaults to the most recent operation)
+    pub id: Option<u64>,
+
+    /// Skip confirmation, and undo even if later operations changed the same files
+    #[arg(short, long)]
+    pub force: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct CompletionsArgs {
     /// Shell to generate completions for
@@ -0,0 +1,351 @@
+use crate::cli::{AtArgs, RemoveArgs};
+use crate::commands::{install_or_rollback, print_warnings, remove, validate_and_warn, write_unit};
+use crate::error::{MkunitError, Result, ValidationWarning};
+use crate::systemd::calendar::CalendarSpec;
+use crate::systemd::exec::ExecCommand;
+use crate::systemd::quote::{self, Expansion};
+use crate::systemd::{is_mkunit_created, list_units, schedule, unit_dir, unit_path, UnitType};
+use crate::templates::{ServiceData, Templates, TimerData};
+use crate::util::{color, prompt};
+use jiff::Zoned;
+use std::fs;
+use std::path::Path;
+
+/// Prefix of the units that make up a job, so they can be listed and cancelled
+const JOB_PREFIX: &str = "at-";
+
+/// Target the job timers are installed into
+const TIMERS_TARGET: &str = "timers.target";
+
+/// Absolute path, as systemd before v239 doesn't search `PATH` for commands
+const RM: &str = "/bin/rm";
+
+pub fn run(args: AtArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
+    if args.list {
+        return list_jobs(args.system);
+    }
+    if let Some(ref job) = args.cancel {
+        return cancel_job(job, args.system, dry_run, no_interactive);
+    }
+
+    let time = args.time.as_deref().unwrap_or_default();
+    if args.command.is_empty() {
+        return Err(MkunitError::invalid_argument(
+            "No command given. Put it after '--', e.g. mkunit at 2h -- systemctl restart cache",
+        ));
+    }
+
+    let when = schedule::parse_at(time, &Zoned::now())?;
+    let name = job_name(args.name.as_deref(), &when, args.system)?;
+    let service_name = format!("{name}.service");
+    let timer_name = format!("{name}.timer");
+
+    let service_path = unit_path(&name, UnitType::Service, args.system)?;
+    let timer_path = unit_path(&name, UnitType::Timer, args.system)?;
+    let wants_link = unit_dir(args.system)?
+        .join(format!("{TIMERS_TARGET}.wants"))
+        .join(&timer_name);
+
+    // The service deletes the pair once it has run; the timer unloads itself
+    let mut cleanup = vec![RM.to_string(), "-f".to_string()];
+    cleanup.extend(
+        [&service_path, &timer_path, &wants_link]
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned()),
+    );
+
+    // The words were split by the shell already, so they are taken literally
+    let command = quote::exec_line(&args.command, Expansion::Literal);
+    print_warnings(&command_warnings(
+        &command,
+        args.workdir.as_deref(),
+        args.system,
+    )?);
+    let service = ServiceData {
+        description: job_description(&args.command),
+        service_type: "oneshot".to_string(),
+        exec: vec![command],
+        exec_stop_post: vec![format!(
+            "-{}",
+            quote::exec_line(&cleanup, Expansion::Literal)
+        )],
+        workdir: args.workdir,
+        restart: "no".to_string(),
+        wanted_by: None,
+        ..ServiceData::default()
+    };
+
+    let timer = TimerData {
+        description: format!("Run {name} once"),
+        on_calendar: vec![calendar_time(&when)],
+        persistent: true,
+        accuracy: Some("1s".to_string()),
+        remain_after_elapse: Some("false".to_string()),
+        unit: service_name.clone(),
+        wanted_by: TIMERS_TARGET.to_string(),
+        ..TimerData::default()
+    };
+
+    let templates = Templates::new()?;
+    let service_written = write_unit(
+        &templates.render_service(&service)?,
+        &service_path,
+        None,
+        args.system,
+        dry_run,
+    )?;
+    let timer_written = write_unit(
+        &templates.render_timer(&timer)?,
+        &timer_path,
+        None,
+        args.system,
+        dry_run,
+    )?;
+    if let Err(e) = install_or_rollback(
+        &timer_written,
+        &timer_name,
+        true,
+        true,
+        args.system,
+        dry_run,
+    ) {
+        service_written.restore().ok();
+        return Err(e);
+    }
+    // Only recorded once the timer is in place, so a failed job leaves no history
+    install_or_rollback(
+        &service_written,
+        &service_name,
+        false,
+        false,
+        args.system,
+        dry_run,
+    )?;
+
+    if !dry_run {
+        color::print_success(&format!(
+            "Job {name} runs at {}",
+            when.strftime("%a %Y-%m-%d %H:%M:%S")
+        ));
+        color::print_info(&format!("Cancel it with: mkunit at --cancel {name}"));
+    }
+
+    Ok(())
+}
+
+/// Unit name for a job, `at-NAME` or `at-YYYYMMDD-HHMM` with a number if taken
+fn job_name(name: Option<&str>, when: &Zoned, system: bool) -> Result<String> {
+    if let Some(name) = name {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ":_.-".contains(c));
+        if !valid {
+            return Err(MkunitError::invalid_argument(format!(
+                "Invalid job name '{name}'. Use letters, digits and ':_.-'"
+            )));
+        }
+        let name = job_unit(name);
+        if unit_path(&name, UnitType::Timer, system)?.exists() {
+            return Err(MkunitError::invalid_argument(format!(
+                "Job {name} already exists. Cancel it first or choose another --name"
+            )));
+        }
+        return Ok(name);
+    }
+
+    let base = format!("{JOB_PREFIX}{}", when.strftime("%Y%m%d-%H%M"));
+    let mut name = base.clone();
+    let mut n = 1;
+    while unit_path(&name, UnitType::Timer, system)?.exists() {
+        n += 1;
+        name = format!("{base}-{n}");
+    }
+    Ok(name)
+}
+
+/// Job unit name without a type suffix, accepting `NAME`, `at-NAME` or `at-NAME.timer`
+fn job_unit(job: &str) -> String {
+    let job = job
+        .strip_suffix(".timer")
+        .or_else(|| job.strip_suffix(".service"))
+        .unwrap_or(job);
+    if job.starts_with(JOB_PREFIX) {
+        job.to_string()
+    } else {
+        format!("{JOB_PREFIX}{job}")
+    }
+}
+
+/// `Description=` of a job, which has to fit on one line
+fn job_description(command: &[String]) -> String {
+    let summary: String = command
+        .join(" ")
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    format!("mkunit at: {}", summary.replace('%', "%%"))
+}
+
+/// An absolute `OnCalendar=` time, with the time zone when it has a name
+fn calendar_time(when: &Zoned) -> String {
+    let time = when.strftime("%Y-%m-%d %H:%M:%S").to_string();
+    match when.time_zone().iana_name() {
+        Some(tz) => format!("{time} {tz}"),
+        None => time,
+    }
+}
+
+fn list_jobs(system: bool) -> Result<()> {
+    let now = Zoned::now();
+    let mut found = false;
+
+    for path in list_units(system)? {
+        let Some(name) = job_file_stem(&path) else {
+            continue;
+        };
+        if !is_mkunit_created(&path) {
+            continue;
+        }
+
+        let timer = fs::read_to_string(&path)?;
+        let service = fs::read_to_string(path.with_extension("service")).unwrap_or_default();
+        let when = setting(&timer, "OnCalendar").unwrap_or_default();
+        let command = setting(&service, "ExecStart").unwrap_or_default();
+
+        // An absolute time has no next elapse once it has passed
+        let overdue =
+            CalendarSpec::parse(when).is_ok_and(|spec| spec.next_elapses(&now, 1).is_empty());
+        let status = if overdue {
+            format!(" {}", color::warning("(overdue)"))
+        } else {
+            String::new()
+        };
+
+        found = true;
+        println!("  {}  {when}{status}  {command}", color::unit_name(name));
+    }
+
+    if !found {
+        println!("  No pending jobs");
+    }
+    Ok(())
+}
+
+/// `at-NAME` for a job timer file
+fn job_file_stem(path: &Path) -> Option<&str> {
+    if path.extension()? != "timer" {
+        return None;
+    }
+    path.file_stem()?
+        .to_str()
+        .filter(|stem| stem.starts_with(JOB_PREFIX))
+}
+
+/// First value of `key` in a unit file
+fn setting<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key).then(|| v.trim())
+    })
+}
+
+fn cancel_job(job: &str, system: bool, dry_run: bool, no_interactive: bool) -> Result<()> {
+    let name = job_unit(job);
+    let timer_path = unit_path(&name, UnitType::Timer, system)?;
+    if !timer_path.exists() {
+        return Err(MkunitError::invalid_argument(format!(
+            "No pending job {name}. See mkunit at --list"
+        )));
+    }
+
+    let confirmed =
+        prompt::prompt_confirm_or_abort(&format!("Cancel job {name}?"), true, no_interactive)?;
+    if !confirmed {
+        color::print_info("Cancelled");
+        return Ok(());
+    }
+
+    for unit_type in [UnitType::Timer, UnitType::Service] {
+        let unit = format!("{name}.{}", unit_type.extension());
+        if !unit_path(&name, unit_type, system)?.exists() {
+            continue;
+        }
+        let args = RemoveArgs {
+            name: unit,
+            system,
+            force: true,
+        };
+        remove::run(args, dry_run, no_interactive)?;
+    }
+
+    Ok(())
+}
+
+/// Check the job's `ExecStart=` line the way `--exec` commands are checked
+fn command_warnings(
+    command: &str,
+    workdir: Option<&str>,
+    system: bool,
+) -> Result<Vec<ValidationWarning>> {
+    let exec = ExecCommand::from_line(command)?;
+    Ok(validate_and_warn(
+        &[("ExecStart", &exec)],
+        workdir,
+        &[],
+        &[],
+        system,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_unit() {
+        assert_eq!(job_unit("backup"), "at-backup");
+        assert_eq!(job_unit("at-backup"), "at-backup");
+        assert_eq!(job_unit("at-20261018-2045.timer"), "at-20261018-2045");
+        assert_eq!(job_unit("at-backup.service"), "at-backup");
+    }
+
+    #[test]
+    fn test_job_description() {
+        let command = ["printf".to_string(), "50%\nline\tend".to_string()];
+        assert_eq!(job_description(&command), "mkunit at: printf 50%% line end");
+    }
+
+    #[test]
+    fn test_command_warnings() {
+        let command = ["systemctl".to_string(), "restart".to_string()];
+        let warnings =
+            command_warnings(&quote::exec_line(&command, Expansion::Literal), None, false).unwrap();
+        assert!(warnings
+            .iter()
+            .any(|w| w.message == "ExecStart path 'systemctl' is not absolute"));
+
+        let command = [RM.to_string(), "-f".to_string(), "/tmp/x".to_string()];
+        let warnings =
+            command_warnings(&quote::exec_line(&command, Expansion::Literal), None, false).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_calendar_time() {
+        let when: Zoned = "2026-10-18T20:45:00[Europe/Berlin]".parse().unwrap();
+        let expr = calendar_time(&when);
+        assert_eq!(expr, "2026-10-18 20:45:00 Europe/Berlin");
+        assert!(CalendarSpec::parse(&expr).is_ok());
+    }
+
+    #[test]
+    fn test_setting() {
+        let content = "[Timer]\nOnCalendar=2026-10-18 20:45:00 UTC\nUnit=at-x.service\n";
+        assert_eq!(
+            setting(content, "OnCalendar"),
+            Some("2026-10-18 20:45:00 UTC")
+        );
+        assert_eq!(setting(content, "OnBootSec"), None);
+    }
+}
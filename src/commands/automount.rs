@@ -0,0 +1,121 @@
+use crate::cli::AutomountArgs;
+use crate::commands::{install_or_rollback, path_unit_name, write_unit};
+use crate::error::{MkunitError, Result};
+use crate::systemd::{self, unit_path, version, UnitType};
+use crate::templates::{AutomountData, Templates};
+use crate::util::color;
+use crate::util::prompt::PromptBuilder;
+use std::path::Path;
+
+/// First systemd version that understands `ExtraOptions=`
+const EXTRA_OPTIONS_SINCE: u32 = 250;
+
+pub fn run(args: AutomountArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
+    let prompts = PromptBuilder::new(no_interactive);
+
+    // Get where (mount point) - required
+    let mount_where = match args.mount_where {
+        Some(w) => w,
+        None => prompts.required("Mount point")?,
+    };
+
+    // The automount only triggers the mount unit of the same name
+    let name = path_unit_name(args.name.as_deref(), &mount_where, UnitType::Automount)?;
+    let mount_name = format!("{name}.mount");
+    if systemd::find_unit(&mount_name, args.system).is_err() {
+        color::print_warning(&format!(
+            "{mount_name} not found; create it with 'mkunit mount --where {mount_where}'"
+        ));
+    }
+
+    // Build template data
+    let data = AutomountData {
+        description: args
+            .description
+            .unwrap_or_else(|| format!("Automount {mount_where}")),
+        r#where: mount_where,
+        timeout_idle_sec: args.timeout_idle_sec.map(|t| t.to_string()),
+        directory_mode: args.directory_mode.as_deref().map(parse_mode).transpose()?,
+        extra_options: args.extra_options,
+        wanted_by: args.wanted_by,
+    };
+
+    create(
+        &data,
+        &name,
+        args.output.as_deref(),
+        args.install,
+        args.system,
+        dry_run,
+    )
+}
+
+/// Render, write and optionally enable an automount unit
+pub fn create(
+    data: &AutomountData,
+    name: &str,
+    output: Option<&str>,
+    install: bool,
+    system: bool,
+    dry_run: bool,
+) -> Result<()> {
+    if data.extra_options.is_some() {
+        let too_old =
+            version::detect_version().is_ok_and(|v| !v.supports_feature(EXTRA_OPTIONS_SINCE));
+        if too_old {
+            color::print_warning(&format!(
+                "ExtraOptions= needs systemd {EXTRA_OPTIONS_SINCE} or newer and will be ignored"
+            ));
+        }
+    }
+
+    // Render template
+    let templates = Templates::new()?;
+    let content = templates.render_automount(data)?;
+
+    // Get output path
+    let unit_file_path = unit_path(name, UnitType::Automount, system)?;
+
+    // Write unit file
+    let written = write_unit(&content, &unit_file_path, output, system, dry_run)?;
+
+    // Handle installation
+    if output.is_none() {
+        let unit_name = format!("{name}.automount");
+        install_or_rollback(&written, &unit_name, install, false, system, dry_run)?;
+    }
+
+    Ok(())
+}
+
+/// Validate an octal file mode such as `0755`
+pub fn parse_mode(mode: &str) -> Result<String> {
+    match u32::from_str_radix(mode, 8) {
+        Ok(bits) if bits <= 0o7777 => Ok(format!("{bits:04o}")),
+        _ => Err(MkunitError::invalid_argument(format!(
+            "Invalid mode '{mode}', expected an octal mode such as 0755"
+        ))),
+    }
+}
+
+/// Output path for the automount written alongside a mount with `--output`
+#[must_use]
+pub fn output_path(mount_output: &str) -> String {
+    Path::new(mount_output)
+        .with_extension("automount")
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse_mode("755").unwrap(), "0755");
+        assert_eq!(parse_mode("0700").unwrap(), "0700");
+        assert!(parse_mode("0855").is_err());
+        assert!(parse_mode("17777").is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use crate::cli::CalendarArgs;
+use crate::error::Result;
+use crate::systemd::calendar::CalendarSpec;
+use jiff::tz::TimeZone;
+use jiff::Zoned;
+
+pub fn run(args: CalendarArgs, _dry_run: bool, _no_interactive: bool) -> Result<()> {
+    // Parse everything first so a typo in any expression prints nothing
+    let specs = args
+        .expressions
+        .iter()
+        .map(|expr| CalendarSpec::parse(expr).map(|spec| (expr, spec)))
+        .collect::<Result<Vec<_>>>()?;
+
+    for (i, (expr, spec)) in specs.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("  Original form: {expr}");
+        print_preview(spec, args.iterations);
+    }
+
+    Ok(())
+}
+
+/// Print the normalized form and upcoming elapse times, like systemd-analyze calendar
+pub fn print_preview(spec: &CalendarSpec, iterations: usize) {
+    println!("Normalized form: {spec}");
+
+    let now = Zoned::now();
+    let elapses = spec.next_elapses(&now, iterations);
+    if elapses.is_empty() {
+        println!("    Next elapse: never");
+        return;
+    }
+
+    for (i, elapse) in elapses.iter().enumerate() {
+        let label = if i == 0 {
+            "    Next elapse".to_string()
+        } else {
+            format!("{:>15}", format!("Iter. #{}", i + 1))
+        };
+        let local = elapse
+            .with_time_zone(TimeZone::system())
+            .strftime("%a %Y-%m-%d %H:%M:%S %Z");
+        println!("{label}: {local}");
+    }
+}
@@ -1,7 +1,9 @@
 use crate::cli::EditArgs;
 use crate::error::Result;
+use crate::journal::{Action, Operation};
 use crate::systemd::{self, find_unit};
-use crate::util::{color, editor, prompt};
+use crate::util::{color, editor, file, prompt};
+use std::path::Path;
 
 pub fn run(args: EditArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
     // Find the unit file
@@ -14,8 +16,7 @@ pub fn run(args: EditArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
 
     color::print_info(&format!("Editing {}", unit_path.display()));
 
-    // Edit the file
-    let was_modified = editor::edit_file(&unit_path)?;
+    let (was_modified, previous) = edit_unit(&unit_path)?;
 
     if !was_modified {
         color::print_info("No changes made");
@@ -24,33 +25,64 @@ pub fn run(args: EditArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
 
     color::print_success("File saved");
 
+    let unit_name = unit_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(&args.name);
+
+    let mut op = Operation::new(format!("edit {unit_name}"), args.system);
+    op.record_file(&unit_path, previous);
+
     // Reload daemon
     if !args.no_reload {
         color::print_info("Reloading systemd daemon...");
         systemd::daemon_reload(args.system)?;
+        op.record_action(Action::DaemonReload, unit_name);
         color::print_success("Daemon reloaded");
     }
 
     // Prompt for restart
-    if !args.no_restart {
-        let unit_name = unit_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or(&args.name);
-
-        if systemd::is_unit_active(unit_name, args.system) {
-            let restart = prompt::prompt_confirm(
-                &format!("Unit '{unit_name}' is active. Restart it?"),
-                true,
-                no_interactive,
-            )?;
-
-            if restart {
-                systemd::restart_unit(unit_name, args.system)?;
-                color::print_success(&format!("Restarted {unit_name}"));
-            }
+    if !args.no_restart && systemd::is_unit_active(unit_name, args.system) {
+        let restart = prompt::prompt_confirm(
+            &format!("Unit '{unit_name}' is active. Restart it?"),
+            true,
+            no_interactive,
+        )?;
+
+        if restart {
+            systemd::restart_unit(unit_name, args.system)?;
+            op.record_action(Action::Restart, unit_name);
+            color::print_success(&format!("Restarted {unit_name}"));
         }
     }
 
+    op.commit();
+
     Ok(())
 }
+
+/// Open the unit in the editor, returning whether it changed and its original content
+fn edit_unit(unit_path: &Path) -> Result<(bool, Option<String>)> {
+    // Keep the original so the edit can be undone; an unreadable unit is
+    // never opened
+    let previous = file::read_previous(unit_path)?;
+    let was_modified = editor::edit_file(unit_path)?;
+    Ok((was_modified, previous))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_unreadable_unit_is_not_edited() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("binary.service");
+        fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+
+        assert!(edit_unit(&path).is_err());
+        assert_eq!(fs::read(&path).unwrap(), [0xff, 0xfe, 0x00]);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::cli::EscapeArgs;
+use crate::error::{MkunitError, Result};
+use crate::systemd::{escape, instance_unit_name, UnitType};
+
+pub fn run(args: EscapeArgs, _dry_run: bool, _no_interactive: bool) -> Result<()> {
+    if let Some(ref suffix) = args.suffix {
+        if UnitType::from_extension(suffix).is_none() {
+            return Err(MkunitError::invalid_argument(format!(
+                "Unknown unit type suffix '{suffix}'"
+            )));
+        }
+    }
+    if let Some(ref template) = args.template {
+        if !template.contains("@.") {
+            return Err(MkunitError::invalid_argument(format!(
+                "'{template}' is not a template unit name (e.g., foo@.service)"
+            )));
+        }
+    }
+
+    let results = args
+        .strings
+        .iter()
+        .map(|s| convert(s, &args))
+        .collect::<Result<Vec<_>>>()?;
+
+    println!("{}", results.join(" "));
+    Ok(())
+}
+
+fn convert(value: &str, args: &EscapeArgs) -> Result<String> {
+    if args.unescape {
+        return if args.path {
+            escape::unescape_path(value)
+        } else {
+            escape::unescape(value)
+        };
+    }
+
+    let escaped = if args.path {
+        escape::escape_path(value)?
+    } else {
+        escape::escape(value)
+    };
+
+    Ok(match (&args.suffix, &args.template) {
+        (Some(suffix), _) => format!("{escaped}.{suffix}"),
+        (None, Some(template)) => instance_unit_name(template, &escaped),
+        (None, None) => escaped,
+    })
+}
@@ -0,0 +1,47 @@
+use crate::cli::HistoryArgs;
+use crate::error::Result;
+use crate::journal;
+use crate::util::color;
+use jiff::tz::TimeZone;
+use jiff::Timestamp;
+
+pub fn run(args: HistoryArgs, _dry_run: bool, _no_interactive: bool) -> Result<()> {
+    let entries = journal::load()?;
+
+    if entries.is_empty() {
+        println!("  No recorded operations");
+        return Ok(());
+    }
+
+    let skip = entries.len().saturating_sub(args.limit);
+    for entry in entries.iter().skip(skip).rev() {
+        let when = Timestamp::from_second(entry.timestamp).map_or_else(
+            |_| entry.timestamp.to_string(),
+            |ts| {
+                ts.to_zoned(TimeZone::system())
+                    .strftime("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            },
+        );
+        let scope = if entry.system { "system" } else { "user" };
+        let status = if entry.undone {
+            format!(" {}", color::hint("(undone)"))
+        } else {
+            String::new()
+        };
+
+        println!(
+            "  {:>4}  {when}  {} [{scope}]{status}",
+            entry.id,
+            color::unit_name(&entry.command)
+        );
+
+        if args.changes {
+            for change in &entry.changes {
+                println!("          {}", color::hint(&change.to_string()));
+            }
+        }
+    }
+
+    Ok(())
+}
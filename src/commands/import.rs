@@ -0,0 +1,774 @@
+use crate::cli::{CronImportArgs, FstabImportArgs, ImportArgs, ImportSource};
+use crate::commands::{automount, install_or_rollback, mount, path_unit_name, swap, write_unit};
+use crate::error::{MkunitError, Result};
+use crate::systemd::crontab::{self, CronJob, Schedule};
+use crate::systemd::fstab::{self, FstabEntry};
+use crate::systemd::quote::{self, Expansion};
+use crate::systemd::timespan::Timespan;
+use crate::systemd::{escape, unit_path, UnitType};
+use crate::templates::{AutomountData, MountData, ServiceData, SwapData, Templates, TimerData};
+use crate::util::color;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::Command;
+
+pub fn run(args: ImportArgs, dry_run: bool, _no_interactive: bool) -> Result<()> {
+    match args.source {
+        ImportSource::Fstab(fstab_args) => import_fstab(&fstab_args, dry_run),
+        ImportSource::Cron(cron_args) => import_cron(&cron_args, dry_run),
+    }
+}
+
+/// Units generated for one fstab entry
+#[derive(Debug)]
+enum FstabUnit {
+    Mount {
+        name: String,
+        data: MountData,
+        automount: Option<Box<AutomountData>>,
+    },
+    Swap {
+        name: String,
+        data: SwapData,
+    },
+}
+
+/// An fstab entry translated into units
+#[derive(Debug)]
+struct Translation {
+    unit: FstabUnit,
+    /// Options whose effect isn't reproduced by the generated units
+    untranslated: Vec<String>,
+}
+
+fn import_fstab(args: &FstabImportArgs, dry_run: bool) -> Result<()> {
+    let content = fs::read_to_string(&args.file)
+        .map_err(|e| MkunitError::invalid_argument(format!("Cannot read {}: {e}", args.file)))?;
+    let entries = fstab::parse(&content)?;
+
+    let mut imported = 0;
+    let mut failed = 0;
+    for entry in &entries {
+        let translation = match translate_fstab(entry) {
+            Ok(Some(t)) => t,
+            Ok(None) => {
+                color::print_info(&format!(
+                    "Line {}: skipping {}, {}",
+                    entry.line,
+                    entry.file,
+                    skip_reason(entry).unwrap_or_default()
+                ));
+                continue;
+            }
+            Err(e) => {
+                color::print_error(&format!("Line {}: {e}", entry.line));
+                failed += 1;
+                continue;
+            }
+        };
+
+        for option in &translation.untranslated {
+            color::print_warning(&format!(
+                "Line {} ({}): {option} is not translated",
+                entry.line, entry.file
+            ));
+        }
+
+        if let Err(e) = write_fstab_unit(translation.unit, args, dry_run) {
+            color::print_error(&format!("Line {}: {e}", entry.line));
+            failed += 1;
+            continue;
+        }
+        imported += 1;
+    }
+
+    color::print_info(&format!(
+        "Imported {imported} fstab entries from {}",
+        args.file
+    ));
+    if failed > 0 {
+        return Err(MkunitError::validation_error(format!(
+            "{failed} fstab entries could not be imported"
+        )));
+    }
+    Ok(())
+}
+
+fn write_fstab_unit(unit: FstabUnit, args: &FstabImportArgs, dry_run: bool) -> Result<()> {
+    let output = |name: &str, unit_type: UnitType| {
+        args.output_dir.as_ref().map(|dir| {
+            Path::new(dir)
+                .join(format!("{name}.{}", unit_type.extension()))
+                .to_string_lossy()
+                .into_owned()
+        })
+    };
+
+    match unit {
+        FstabUnit::Mount {
+            name,
+            data,
+            automount,
+        } => {
+            let mount_output = output(&name, UnitType::Mount);
+            mount::create(
+                &data,
+                &name,
+                mount_output.as_deref(),
+                args.install,
+                true,
+                dry_run,
+            )?;
+            if let Some(data) = automount {
+                let automount_output = output(&name, UnitType::Automount);
+                automount::create(
+                    &data,
+                    &name,
+                    automount_output.as_deref(),
+                    args.install,
+                    true,
+                    dry_run,
+                )?;
+            }
+        }
+        FstabUnit::Swap { name, data } => {
+            let swap_output = output(&name, UnitType::Swap);
+            swap::create(
+                &data,
+                &name,
+                swap_output.as_deref(),
+                args.install,
+                true,
+                dry_run,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Kernel API filesystems, which systemd mounts itself at fixed places
+const API_FILESYSTEMS: &[&str] = &[
+    "proc",
+    "sysfs",
+    "devtmpfs",
+    "devpts",
+    "cgroup",
+    "cgroup2",
+    "securityfs",
+    "debugfs",
+    "tracefs",
+    "configfs",
+    "pstore",
+    "bpf",
+    "efivarfs",
+    "mqueue",
+    "hugetlbfs",
+    "binfmt_misc",
+    "fusectl",
+];
+
+/// Mount points of the API filesystems, whatever type an fstab line gives them
+const API_MOUNT_POINTS: &[&str] = &[
+    "/proc",
+    "/sys",
+    "/dev",
+    "/dev/pts",
+    "/dev/shm",
+    "/dev/mqueue",
+    "/dev/hugepages",
+    "/run",
+    "/sys/fs/cgroup",
+];
+
+/// Why an fstab entry is left to systemd instead of being imported
+fn skip_reason(entry: &FstabEntry) -> Option<&'static str> {
+    if entry.file == "/" {
+        Some("it is mounted before systemd starts")
+    } else if API_FILESYSTEMS.contains(&entry.vfstype.as_str())
+        || API_MOUNT_POINTS.contains(&entry.file.as_str())
+    {
+        Some("systemd mounts API filesystems itself")
+    } else {
+        None
+    }
+}
+
+/// Unit named by an `x-systemd.requires/after/before` value
+///
+/// The generator accepts paths as well as unit names: device nodes become
+/// `.device` units and anything else the `.mount` unit for that mount point.
+fn dependency_unit(value: &str) -> Result<String> {
+    if !value.starts_with('/') {
+        return Ok(value.to_string());
+    }
+    if value.starts_with("/dev/") {
+        return Ok(format!("{}.device", escape::escape_path(value)?));
+    }
+    escape::path_unit_name(value, UnitType::Mount)
+}
+
+/// Translate an fstab entry the way systemd-fstab-generator would
+///
+/// Returns `None` for the root filesystem and the API filesystems, see
+/// [`skip_reason`].
+#[allow(clippy::too_many_lines)]
+fn translate_fstab(entry: &FstabEntry) -> Result<Option<Translation>> {
+    if skip_reason(entry).is_some() {
+        return Ok(None);
+    }
+
+    let mut untranslated = Vec::new();
+    let mut requires = Vec::new();
+    let mut after = Vec::new();
+    let mut before = Vec::new();
+    let mut use_automount = false;
+    let mut idle_timeout = None;
+    let mut mount_timeout = None;
+    let mut priority = None;
+    let mut options = Vec::new();
+
+    for option in &entry.options {
+        let (key, value) = option
+            .split_once('=')
+            .map_or((option.as_str(), None), |(k, v)| (k, Some(v.to_string())));
+        match key {
+            "defaults" => {}
+            "x-systemd.automount" if !entry.is_swap() => use_automount = true,
+            "x-systemd.idle-timeout" if !entry.is_swap() => {
+                idle_timeout = value.as_deref().map(parse_timeout).transpose()?;
+            }
+            "x-systemd.mount-timeout" if !entry.is_swap() => {
+                mount_timeout = value.as_deref().map(parse_timeout).transpose()?;
+            }
+            "pri" if entry.is_swap() => priority = value,
+            // Like the generator, a required unit is also ordered before
+            "x-systemd.requires" if value.is_some() && !entry.is_swap() => {
+                let unit = value.as_deref().map(dependency_unit).transpose()?;
+                requires.extend(unit.clone());
+                after.extend(unit);
+            }
+            "x-systemd.after" if value.is_some() && !entry.is_swap() => {
+                after.extend(value.as_deref().map(dependency_unit).transpose()?);
+            }
+            "x-systemd.before" if value.is_some() && !entry.is_swap() => {
+                before.extend(value.as_deref().map(dependency_unit).transpose()?);
+            }
+            k if k.starts_with("x-systemd.") || k.starts_with("x-initrd.") => {
+                untranslated.push(option.clone());
+            }
+            // Plain options, including noauto, nofail and _netdev which
+            // systemd also honours in Options=
+            _ => options.push(option.clone()),
+        }
+    }
+
+    if idle_timeout.is_some() && !use_automount {
+        untranslated.push("x-systemd.idle-timeout without x-systemd.automount".to_string());
+        idle_timeout = None;
+    }
+    if entry.passno > 0 {
+        untranslated.push(format!("fsck pass {}", entry.passno));
+    }
+
+    let what = entry.source();
+    let options = (!options.is_empty()).then(|| options.join(","));
+    let noauto = entry.has_option("noauto");
+
+    let unit = if entry.is_swap() {
+        FstabUnit::Swap {
+            name: path_unit_name(None, &what, UnitType::Swap)?,
+            data: SwapData {
+                description: format!("Swap on {what}"),
+                what,
+                priority,
+                options,
+                timeout_sec: None,
+                wanted_by: (!noauto).then(|| "swap.target".to_string()),
+            },
+        }
+    } else {
+        let network = mount::is_network_fs(Some(&entry.vfstype), options.as_deref());
+        if network {
+            after.insert(0, mount::NETWORK_ONLINE_TARGET.to_string());
+        }
+        let units = |units: Vec<String>| (!units.is_empty()).then(|| units.join(" "));
+        let target = if network {
+            "remote-fs.target"
+        } else {
+            "local-fs.target"
+        };
+        let mount_point = entry.file.clone();
+        FstabUnit::Mount {
+            name: path_unit_name(None, &mount_point, UnitType::Mount)?,
+            automount: use_automount.then(|| {
+                Box::new(AutomountData {
+                    description: format!("Automount {mount_point}"),
+                    r#where: mount_point.clone(),
+                    timeout_idle_sec: idle_timeout,
+                    wanted_by: target.to_string(),
+                    ..AutomountData::default()
+                })
+            }),
+            data: MountData {
+                description: format!("Mount {what} at {mount_point}"),
+                requires: units(requires),
+                after: units(after),
+                before: units(before),
+                wants: network.then(|| mount::NETWORK_ONLINE_TARGET.to_string()),
+                what,
+                r#where: mount_point,
+                fs_type: (entry.vfstype != "auto").then(|| entry.vfstype.clone()),
+                options,
+                timeout_sec: mount_timeout,
+                wanted_by: (!noauto && !use_automount).then(|| target.to_string()),
+            },
+        }
+    };
+
+    Ok(Some(Translation { unit, untranslated }))
+}
+
+/// Environment assignments cron uses itself instead of passing to the job
+const CRON_VARIABLES: &[&str] = &["MAILTO", "SHELL", "CRON_TZ"];
+
+/// A cron job translated into a timer and the service it starts
+#[derive(Debug)]
+struct CronTranslation {
+    service: ServiceData,
+    timer: TimerData,
+    /// Settings whose effect isn't reproduced by the generated units
+    untranslated: Vec<String>,
+}
+
+fn import_cron(args: &CronImportArgs, dry_run: bool) -> Result<()> {
+    let (content, source) = read_crontab(args.file.as_deref())?;
+    let system = args.system || args.file.as_deref().is_some_and(is_system_crontab);
+    let jobs = crontab::parse(&content, system)?;
+
+    let mut used_names = HashMap::new();
+    let mut imported = 0;
+    let mut failed = 0;
+    for job in &jobs {
+        let name = cron_unit_name(&args.prefix, job, &mut used_names);
+        let translation = match translate_cron(job, &name, system) {
+            Ok(t) => t,
+            Err(e) => {
+                color::print_error(&format!("Line {}: {e}", job.line));
+                failed += 1;
+                continue;
+            }
+        };
+
+        for setting in &translation.untranslated {
+            color::print_warning(&format!(
+                "Line {} ({name}): {setting} is not translated",
+                job.line
+            ));
+        }
+
+        if let Err(e) = write_cron_units(&translation, &name, args, system, dry_run) {
+            color::print_error(&format!("Line {}: {e}", job.line));
+            failed += 1;
+            continue;
+        }
+        imported += 1;
+    }
+
+    color::print_info(&format!("Imported {imported} cron jobs from {source}"));
+    if failed > 0 {
+        return Err(MkunitError::validation_error(format!(
+            "{failed} cron jobs could not be imported"
+        )));
+    }
+    Ok(())
+}
+
+/// Read a crontab file, stdin for `-`, or the user's crontab via `crontab -l`
+fn read_crontab(file: Option<&str>) -> Result<(String, String)> {
+    match file {
+        Some("-") => {
+            let mut content = String::new();
+            io::stdin().read_to_string(&mut content)?;
+            Ok((content, "stdin".to_string()))
+        }
+        Some(path) => {
+            let content = fs::read_to_string(path)
+                .map_err(|e| MkunitError::invalid_argument(format!("Cannot read {path}: {e}")))?;
+            Ok((content, path.to_string()))
+        }
+        None => {
+            let output = Command::new("crontab").arg("-l").output().map_err(|e| {
+                MkunitError::invalid_argument(format!("Cannot run crontab -l: {e}"))
+            })?;
+            if !output.status.success() {
+                return Err(MkunitError::invalid_argument(format!(
+                    "crontab -l failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            Ok((
+                String::from_utf8_lossy(&output.stdout).into_owned(),
+                "your crontab".to_string(),
+            ))
+        }
+    }
+}
+
+/// Whether a file uses the system crontab format with a user column
+fn is_system_crontab(path: &str) -> bool {
+    path == "/etc/crontab" || Path::new(path).starts_with("/etc/cron.d")
+}
+
+/// Name a job after the program it runs, numbering repeats
+fn cron_unit_name(prefix: &str, job: &CronJob, used: &mut HashMap<String, usize>) -> String {
+    let program = job
+        .command
+        .split_whitespace()
+        .next()
+        .and_then(|word| word.rsplit('/').next())
+        .unwrap_or_default();
+    let program: String = program
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let program = program.trim_matches('-');
+
+    let base = if program.is_empty() {
+        prefix.to_string()
+    } else {
+        format!("{prefix}-{program}")
+    };
+    let count = used.entry(base.clone()).or_insert(0);
+    *count += 1;
+    if *count == 1 {
+        base
+    } else {
+        format!("{base}-{count}")
+    }
+}
+
+/// Translate a cron job into a oneshot service and a timer for it
+fn translate_cron(job: &CronJob, name: &str, system: bool) -> Result<CronTranslation> {
+    let (command, stdin) = crontab::split_command(&job.command);
+    if stdin.is_some() {
+        return Err(MkunitError::invalid_argument(
+            "'%' in the command passes standard input to it, escape it as '\\%' \
+             if it is meant literally",
+        ));
+    }
+
+    let mut untranslated = Vec::new();
+    if let Some(mailto) = job.env_value("MAILTO").filter(|m| !m.is_empty()) {
+        untranslated.push(format!("MAILTO={mailto} (output goes to the journal)"));
+    }
+
+    let (on_calendar, on_boot, on_startup) = match &job.schedule {
+        // The user manager starts at login, like cron does for @reboot jobs
+        // of users without lingering
+        Schedule::Reboot if system => (Vec::new(), vec!["0".to_string()], Vec::new()),
+        Schedule::Reboot => (Vec::new(), Vec::new(), vec!["0".to_string()]),
+        Schedule::Fields {
+            minute,
+            hour,
+            day,
+            month,
+            weekday,
+        } => {
+            let calendar =
+                crontab::to_calendar(minute, hour, day, month, weekday, job.env_value("CRON_TZ"))?;
+            (vec![calendar], Vec::new(), Vec::new())
+        }
+    };
+
+    // cron runs commands through the shell, so $VAR and quoting are left to it
+    let shell = job.env_value("SHELL").unwrap_or("/bin/sh").to_string();
+    let exec = quote::exec_line(
+        &[shell, "-c".to_string(), command.clone()],
+        Expansion::Literal,
+    );
+    let env = job
+        .env
+        .iter()
+        .filter(|(key, _)| !CRON_VARIABLES.contains(&key.as_str()))
+        .map(|(key, value)| quote::env_assignment(key, value))
+        .collect::<Result<Vec<_>>>()?;
+
+    let service = ServiceData {
+        // Description= expands specifiers too
+        description: format!("Cron job: {}", command.replace('%', "%%")),
+        after: None,
+        service_type: "oneshot".to_string(),
+        exec: vec![exec],
+        user: job.user.clone().filter(|user| user != "root"),
+        restart: "no".to_string(),
+        env,
+        wanted_by: None,
+        ..ServiceData::default()
+    };
+    let timer = TimerData {
+        description: format!("Schedule for {name}.service"),
+        on_calendar,
+        on_boot,
+        on_startup,
+        unit: format!("{name}.service"),
+        ..TimerData::default()
+    };
+
+    Ok(CronTranslation {
+        service,
+        timer,
+        untranslated,
+    })
+}
+
+fn write_cron_units(
+    translation: &CronTranslation,
+    name: &str,
+    args: &CronImportArgs,
+    system: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let templates = Templates::new()?;
+    let units = [
+        (
+            UnitType::Service,
+            templates.render_service(&translation.service)?,
+        ),
+        (UnitType::Timer, templates.render_timer(&translation.timer)?),
+    ];
+
+    for (unit_type, content) in units {
+        let file_name = format!("{name}.{}", unit_type.extension());
+        let output = args.output_dir.as_ref().map(|dir| {
+            Path::new(dir)
+                .join(&file_name)
+                .to_string_lossy()
+                .into_owned()
+        });
+        let unit_file_path = unit_path(name, unit_type, system)?;
+        let written = write_unit(
+            &content,
+            &unit_file_path,
+            output.as_deref(),
+            system,
+            dry_run,
+        )?;
+
+        // Only the timer is enabled; it starts the service
+        if output.is_none() {
+            let install = args.install && unit_type == UnitType::Timer;
+            install_or_rollback(&written, &file_name, install, install, system, dry_run)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Normalize an `x-systemd.*-timeout` value, rejecting invalid spans
+fn parse_timeout(value: &str) -> Result<String> {
+    Ok(value.parse::<Timespan>()?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(line: &str) -> Translation {
+        let entries = fstab::parse(line).unwrap();
+        translate_fstab(&entries[0]).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_translate_mount() {
+        let t = translate("LABEL=data /mnt/my\\040data ext4 nofail,x-systemd.mount-timeout=30 0 2");
+        let FstabUnit::Mount {
+            name,
+            data,
+            automount,
+        } = t.unit
+        else {
+            panic!("expected a mount");
+        };
+        assert_eq!(name, "mnt-my\\x20data");
+        assert_eq!(data.what, "/dev/disk/by-label/data");
+        assert_eq!(data.options.as_deref(), Some("nofail"));
+        assert_eq!(data.timeout_sec.as_deref(), Some("30s"));
+        assert_eq!(data.wanted_by.as_deref(), Some("local-fs.target"));
+        assert!(automount.is_none());
+        assert_eq!(t.untranslated, vec!["fsck pass 2"]);
+    }
+
+    #[test]
+    fn test_translate_automount() {
+        let t = translate(
+            "nas:/export /mnt/nas nfs _netdev,noauto,x-systemd.automount,x-systemd.idle-timeout=5min,x-systemd.requires=vpn.service",
+        );
+        let FstabUnit::Mount {
+            data, automount, ..
+        } = t.unit
+        else {
+            panic!("expected a mount");
+        };
+        assert!(data.wanted_by.is_none());
+        assert_eq!(data.options.as_deref(), Some("_netdev,noauto"));
+        let automount = automount.unwrap();
+        assert_eq!(automount.wanted_by, "remote-fs.target");
+        assert_eq!(automount.timeout_idle_sec.as_deref(), Some("5min"));
+        assert_eq!(data.requires.as_deref(), Some("vpn.service"));
+        assert_eq!(
+            data.after.as_deref(),
+            Some("network-online.target vpn.service")
+        );
+        assert!(t.untranslated.is_empty());
+    }
+
+    #[test]
+    fn test_translate_dependencies() {
+        let t = translate(
+            "/dev/sdb1 /srv xfs x-systemd.requires=cryptsetup.target,x-systemd.after=a.service,\
+             x-systemd.after=b.service,x-systemd.before=c.service,x-systemd.wanted-by=d.target",
+        );
+        let FstabUnit::Mount { data, .. } = t.unit else {
+            panic!("expected a mount");
+        };
+        assert_eq!(data.requires.as_deref(), Some("cryptsetup.target"));
+        assert_eq!(
+            data.after.as_deref(),
+            Some("cryptsetup.target a.service b.service")
+        );
+        assert_eq!(data.before.as_deref(), Some("c.service"));
+        assert!(data.options.is_none());
+        assert_eq!(t.untranslated, vec!["x-systemd.wanted-by=d.target"]);
+
+        // Paths name the mount or device unit for them
+        let t = translate(
+            "/dev/sdc1 /srv/data ext4 x-systemd.requires=/srv,x-systemd.after=/dev/mapper/vg-data,\
+             x-systemd.before=/srv/data/cache",
+        );
+        let FstabUnit::Mount { data, .. } = t.unit else {
+            panic!("expected a mount");
+        };
+        assert_eq!(data.requires.as_deref(), Some("srv.mount"));
+        assert_eq!(
+            data.after.as_deref(),
+            Some("srv.mount dev-mapper-vg\\x2ddata.device")
+        );
+        assert_eq!(data.before.as_deref(), Some("srv-data-cache.mount"));
+
+        // Swap units don't take dependencies from fstab
+        let t = translate("/swapfile none swap x-systemd.requires=a.service");
+        assert_eq!(t.untranslated, vec!["x-systemd.requires=a.service"]);
+    }
+
+    #[test]
+    fn test_translate_swap() {
+        let t = translate("UUID=ab-cd none swap sw,pri=5,noauto 0 0");
+        let FstabUnit::Swap { name, data } = t.unit else {
+            panic!("expected a swap");
+        };
+        assert_eq!(name, "dev-disk-by\\x2duuid-ab\\x2dcd");
+        assert_eq!(data.priority.as_deref(), Some("5"));
+        assert_eq!(data.options.as_deref(), Some("sw,noauto"));
+        assert!(data.wanted_by.is_none());
+    }
+
+    #[test]
+    fn test_translate_invalid_timeout() {
+        let entries = fstab::parse("/dev/sdb1 /mnt xfs x-systemd.mount-timeout=soon").unwrap();
+        assert!(translate_fstab(&entries[0]).is_err());
+    }
+
+    fn translate_job(content: &str, system: bool) -> Result<CronTranslation> {
+        let jobs = crontab::parse(content, system).unwrap();
+        translate_cron(&jobs[0], "cron-job", system)
+    }
+
+    #[test]
+    fn test_translate_cron() {
+        let t = translate_job(
+            "SHELL=/bin/bash\nMAILTO=ops\nLANG=C\n30 2 * * 1-5 backup /usr/bin/backup $HOME\n",
+            true,
+        )
+        .unwrap();
+        assert_eq!(t.timer.on_calendar, vec!["Mon..Fri *-*-* 02:30:00"]);
+        assert_eq!(t.timer.unit, "cron-job.service");
+        assert_eq!(t.service.user.as_deref(), Some("backup"));
+        assert_eq!(
+            t.service.exec,
+            vec!["/bin/bash -c \"/usr/bin/backup $$HOME\""]
+        );
+        assert_eq!(t.service.env, vec!["\"LANG=C\""]);
+        assert_eq!(
+            t.untranslated,
+            vec!["MAILTO=ops (output goes to the journal)"]
+        );
+    }
+
+    #[test]
+    fn test_translate_cron_reboot() {
+        let t = translate_job("@reboot root /usr/bin/warmup\n", true).unwrap();
+        assert_eq!(t.timer.on_boot, vec!["0"]);
+        assert!(t.service.user.is_none());
+
+        let t = translate_job("@reboot /usr/bin/warmup\n", false).unwrap();
+        assert_eq!(t.timer.on_startup, vec!["0"]);
+    }
+
+    #[test]
+    fn test_translate_cron_errors() {
+        assert!(translate_job("0 0 1 * mon /bin/true\n", false).is_err());
+        assert!(translate_job("0 0 * * * mail root%hello\n", false).is_err());
+    }
+
+    #[test]
+    fn test_cron_unit_name() {
+        let jobs = crontab::parse(
+            "* * * * * /usr/bin/sync-data --all\n@daily sync-data\n@hourly cd /srv && make\n",
+            false,
+        )
+        .unwrap();
+        let mut used = HashMap::new();
+        let names: Vec<String> = jobs
+            .iter()
+            .map(|job| cron_unit_name("cron", job, &mut used))
+            .collect();
+        assert_eq!(names, vec!["cron-sync-data", "cron-sync-data-2", "cron-cd"]);
+    }
+
+    #[test]
+    fn test_translate_root_is_skipped() {
+        let entries = fstab::parse("/dev/sda1 / ext4 defaults 0 1").unwrap();
+        assert!(translate_fstab(&entries[0]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_translate_api_filesystems_are_skipped() {
+        let entries = fstab::parse(
+            "proc /proc proc defaults 0 0\n\
+             sysfs /sys sysfs defaults 0 0\n\
+             devpts /dev/pts devpts gid=5,mode=620 0 0\n\
+             tmpfs /dev/shm tmpfs defaults 0 0\n\
+             none /sys/fs/cgroup cgroup2 defaults 0 0\n\
+             tmpfs /tmp tmpfs defaults 0 0\n",
+        )
+        .unwrap();
+        let skipped: Vec<bool> = entries
+            .iter()
+            .map(|e| translate_fstab(e).unwrap().is_none())
+            .collect();
+        assert_eq!(skipped, [true, true, true, true, true, false]);
+        assert_eq!(
+            skip_reason(&entries[0]),
+            Some("systemd mounts API filesystems itself")
+        );
+    }
+}
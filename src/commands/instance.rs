@@ -0,0 +1,110 @@
+use crate::cli::{InstanceAction, InstanceArgs, InstanceListArgs, InstanceTargetArgs};
+use crate::error::Result;
+use crate::journal::{Action, Operation};
+use crate::systemd::{self, UnitType};
+use crate::util::color;
+use colored::Colorize;
+
+pub fn run(args: InstanceArgs, dry_run: bool, _no_interactive: bool) -> Result<()> {
+    match args.action {
+        InstanceAction::Add(target) => add(target, dry_run),
+        InstanceAction::Remove(target) => remove(target, dry_run),
+        InstanceAction::List(list_args) => list(list_args),
+    }
+}
+
+fn add(args: InstanceTargetArgs, dry_run: bool) -> Result<()> {
+    let template = systemd::template_unit_name(&args.template, UnitType::Service);
+    systemd::validate_instance(&args.instance)?;
+    systemd::find_unit(&template, args.system)?;
+    let unit_name = systemd::instance_unit_name(&template, &args.instance);
+
+    if dry_run {
+        println!("Would enable: {unit_name}");
+        if !args.no_start {
+            println!("Would start: {unit_name}");
+        }
+        return Ok(());
+    }
+
+    let mut op = Operation::new(format!("instance add {unit_name}"), args.system);
+
+    let result = enable_and_start(&unit_name, &args, &mut op);
+    op.commit();
+    result
+}
+
+fn enable_and_start(unit_name: &str, args: &InstanceTargetArgs, op: &mut Operation) -> Result<()> {
+    systemd::enable_unit(unit_name, args.system)?;
+    op.record_action(Action::Enable, unit_name);
+    color::print_success(&format!("Enabled {unit_name}"));
+
+    if !args.no_start {
+        systemd::start_unit(unit_name, args.system)?;
+        op.record_action(Action::Start, unit_name);
+        color::print_success(&format!("Started {unit_name}"));
+    }
+
+    Ok(())
+}
+
+fn remove(args: InstanceTargetArgs, dry_run: bool) -> Result<()> {
+    let template = systemd::template_unit_name(&args.template, UnitType::Service);
+    systemd::validate_instance(&args.instance)?;
+    let unit_name = systemd::instance_unit_name(&template, &args.instance);
+
+    if dry_run {
+        if !args.no_start {
+            println!("Would stop: {unit_name}");
+        }
+        println!("Would disable: {unit_name}");
+        return Ok(());
+    }
+
+    let mut op = Operation::new(format!("instance remove {unit_name}"), args.system);
+
+    let result = stop_and_disable(&unit_name, &args, &mut op);
+    op.commit();
+    result
+}
+
+fn stop_and_disable(unit_name: &str, args: &InstanceTargetArgs, op: &mut Operation) -> Result<()> {
+    if !args.no_start && systemd::is_unit_active(unit_name, args.system) {
+        systemd::stop_unit(unit_name, args.system)?;
+        op.record_action(Action::Stop, unit_name);
+        color::print_success(&format!("Stopped {unit_name}"));
+    }
+
+    if systemd::is_unit_enabled(unit_name, args.system) {
+        systemd::disable_unit(unit_name, args.system)?;
+        op.record_action(Action::Disable, unit_name);
+        color::print_success(&format!("Disabled {unit_name}"));
+    } else {
+        color::print_info(&format!("{unit_name} is not enabled"));
+    }
+
+    Ok(())
+}
+
+fn list(args: InstanceListArgs) -> Result<()> {
+    let template = systemd::template_unit_name(&args.template, UnitType::Service);
+    let instances = systemd::list_instances(&template, args.system)?;
+
+    println!("{}", format!("Instances of {template}:").bold());
+    if instances.is_empty() {
+        println!("  No enabled instances");
+        return Ok(());
+    }
+
+    for instance in instances {
+        let unit_name = systemd::instance_unit_name(&template, &instance);
+        let state = if systemd::is_unit_active(&unit_name, args.system) {
+            "active".green()
+        } else {
+            "inactive".dimmed()
+        };
+        println!("  {} ({state})", color::unit_name(&instance));
+    }
+
+    Ok(())
+}
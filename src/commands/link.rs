@@ -1,8 +1,9 @@
 use crate::cli::LinkArgs;
-use crate::commands::handle_install_and_start;
+use crate::commands::{handle_install_and_start, record_install};
 use crate::error::{MkunitError, Result};
+use crate::journal::{Action, Operation};
 use crate::systemd::{self, unit_dir, UnitType};
-use crate::util::color;
+use crate::util::{color, file};
 use std::fs;
 use std::os::unix::fs::symlink;
 use std::path::Path;
@@ -38,14 +39,14 @@ pub fn run(args: LinkArgs, dry_run: bool, _no_interactive: bool) -> Result<()> {
         .and_then(|e| e.to_str())
         .ok_or_else(|| {
             MkunitError::invalid_argument(
-                "File must have a systemd unit extension (.service, .timer, .path, .socket, .mount, .target)",
+                "File must have a systemd unit extension (.service, .timer, .path, .socket, .mount, .automount, .swap, .target, .slice)",
             )
         })?;
 
     // Validate it's a known unit type
     if UnitType::from_extension(extension).is_none() {
         return Err(MkunitError::invalid_argument(format!(
-            "Unknown unit type: .{extension}. Expected .service, .timer, .path, .socket, .mount, or .target"
+            "Unknown unit type: .{extension}. Expected .service, .timer, .path, .socket, .mount, .automount, .swap, .target, or .slice"
         )));
     }
 
@@ -61,15 +62,25 @@ pub fn run(args: LinkArgs, dry_run: bool, _no_interactive: bool) -> Result<()> {
     let target_dir = unit_dir(args.system)?;
     let target_path = target_dir.join(filename);
 
+    let mut op = Operation::new(format!("link {filename}"), args.system);
+    let mut replaced_link = None;
+
     // Check if target already exists
     if target_path.exists() || target_path.is_symlink() {
         if args.force {
             if dry_run {
                 println!("Would remove existing: {}", target_path.display());
             } else {
-                // Remove existing file or symlink
-                if target_path.is_symlink() || target_path.is_file() {
+                // Remove existing file or symlink, remembering it for undo
+                if target_path.is_symlink() {
+                    replaced_link = Some(fs::read_link(&target_path)?);
+                    fs::remove_file(&target_path)?;
+                } else if target_path.is_file() {
+                    let previous = file::read_previous(&target_path)?;
                     fs::remove_file(&target_path)?;
+                    if let Some(previous) = previous {
+                        op.record_removed_file(&target_path, previous);
+                    }
                 } else {
                     return Err(MkunitError::invalid_argument(format!(
                         "Target exists and is not a file or symlink: {}",
@@ -88,13 +99,18 @@ pub fn run(args: LinkArgs, dry_run: bool, _no_interactive: bool) -> Result<()> {
                             abs_source.display()
                         ));
                         // Still handle install/start if requested
-                        return handle_install_and_start(
+                        handle_install_and_start(
                             filename,
                             args.install,
                             args.start,
                             args.system,
                             dry_run,
-                        );
+                        )?;
+                        if !dry_run {
+                            record_install(&mut op, filename, args.install, args.start);
+                            op.commit();
+                        }
+                        return Ok(());
                     }
                 }
             }
@@ -118,6 +134,7 @@ pub fn run(args: LinkArgs, dry_run: bool, _no_interactive: bool) -> Result<()> {
     } else {
         fs::create_dir_all(&target_dir)?;
         symlink(&abs_source, &target_path)?;
+        op.record_symlink(&target_path, replaced_link);
         color::print_success(&format!(
             "Linked {} -> {}",
             target_path.display(),
@@ -134,6 +151,7 @@ pub fn run(args: LinkArgs, dry_run: bool, _no_interactive: bool) -> Result<()> {
             );
         } else {
             handle_install_and_start(filename, args.install, args.start, args.system, dry_run)?;
+            record_install(&mut op, filename, args.install, args.start);
         }
     } else {
         // Always daemon-reload after creating a symlink
@@ -144,8 +162,13 @@ pub fn run(args: LinkArgs, dry_run: bool, _no_interactive: bool) -> Result<()> {
             );
         } else {
             systemd::daemon_reload(args.system)?;
+            op.record_action(Action::DaemonReload, filename);
         }
     }
 
+    if !dry_run {
+        op.commit();
+    }
+
     Ok(())
 }
@@ -7,8 +7,15 @@
 #![allow(clippy::fn_params_excessive_bools)]
 #![allow(clippy::case_sensitive_file_extension_comparisons)]
 
+pub mod at;
+pub mod automount;
+pub mod calendar;
 pub mod completions;
 pub mod edit;
+pub mod escape;
+pub mod history;
+pub mod import;
+pub mod instance;
 pub mod link;
 pub mod list;
 pub mod logs;
@@ -17,44 +24,198 @@ pub mod path;
 pub mod remove;
 pub mod service;
 pub mod show;
+pub mod slice;
 pub mod socket;
 pub mod status;
+pub mod swap;
 pub mod target;
 pub mod timer;
+pub mod timers;
+pub mod undo;
 pub mod validate;
 
-use crate::error::{Result, ValidationWarning};
-use crate::systemd;
-use crate::util::color;
+use crate::error::{MkunitError, Result, ValidationWarning};
+use crate::journal::{Action, Operation};
+use crate::systemd::exec::ExecCommand;
+use crate::systemd::listen::ListenAddress;
+use crate::systemd::mountinfo::MountTable;
+use crate::systemd::ports::{PortTable, Protocol};
+use crate::systemd::quote::{self, Expansion};
+use crate::systemd::resource::{ByteLimit, ProfileLimits, ResourceProfile};
+use crate::systemd::specifier::{self, ExpandContext};
+use crate::systemd::{self, UnitType};
+use crate::util::prompt::PromptBuilder;
+use crate::util::{color, file};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// A unit file written by [`write_unit`], with enough state to undo the write
+#[derive(Debug)]
+pub struct WrittenUnit {
+    pub path: PathBuf,
+    /// Contents of the file before it was overwritten, `None` if it was new
+    pub previous: Option<String>,
+    root_owned: bool,
+    dry_run: bool,
+}
+
+impl WrittenUnit {
+    /// Put the file back the way it was before the write
+    pub fn restore(&self) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        match self.previous {
+            Some(ref content) => {
+                file::atomic_write(&self.path, content.as_bytes(), self.root_owned)
+            }
+            None => match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            },
+        }
+    }
+}
 
 /// Common output handling for unit creation commands
+///
+/// The file is written through a temporary file and renamed into place, so a
+/// crash never leaves a truncated unit behind. Units written to the system
+/// unit directory are owned by root.
 pub fn write_unit(
     content: &str,
     unit_path: &Path,
     output_path: Option<&str>,
+    system: bool,
     dry_run: bool,
-) -> Result<()> {
+) -> Result<WrittenUnit> {
     let target_path =
         output_path.map_or_else(|| unit_path.to_path_buf(), |p| Path::new(p).to_path_buf());
+    let root_owned = system && output_path.is_none();
+
+    let written = WrittenUnit {
+        previous: file::read_previous(&target_path)?,
+        path: target_path,
+        root_owned,
+        dry_run,
+    };
 
     if dry_run {
-        println!("Would write to: {}", target_path.display());
+        println!("Would write to: {}", written.path.display());
         println!();
         println!("{}", color::highlight_unit_file(content));
-        return Ok(());
+        return Ok(written);
+    }
+
+    file::atomic_write(&written.path, content.as_bytes(), root_owned)?;
+    color::print_success(&format!("Created {}", written.path.display()));
+
+    Ok(written)
+}
+
+/// Names used when creating a unit that may be a template
+#[derive(Debug)]
+pub struct UnitNames {
+    /// Name without type suffix or `@`, used for defaults
+    pub base: String,
+    /// File name of the unit (`myapp.service` or `myapp@.service`)
+    pub file: String,
+    /// Unit to enable and start (`myapp.service` or `myapp@web.service`)
+    pub install: String,
+    template: bool,
+}
+
+impl UnitNames {
+    /// Default description, mentioning the instance for templates
+    #[must_use]
+    pub fn description(&self, kind: &str) -> String {
+        if self.template {
+            format!("{} {kind} for %i", self.base)
+        } else {
+            format!("{} {kind}", self.base)
+        }
     }
 
-    // Ensure parent directory exists
-    if let Some(parent) = target_path.parent() {
-        fs::create_dir_all(parent)?;
+    /// Default service activated by this unit, the matching instance for templates
+    #[must_use]
+    pub fn service(&self) -> String {
+        if self.template {
+            format!("{}@%i.service", self.base)
+        } else {
+            format!("{}.service", self.base)
+        }
     }
+}
 
-    fs::write(&target_path, content)?;
-    color::print_success(&format!("Created {}", target_path.display()));
+/// Work out file and install names for a unit creation command
+///
+/// A template can only be installed through an instance, so `--install` on a
+/// template requires `--default-instance`.
+pub fn unit_names(
+    name: &str,
+    unit_type: UnitType,
+    template: bool,
+    default_instance: Option<&str>,
+    install: bool,
+) -> Result<UnitNames> {
+    if !template {
+        return Ok(UnitNames {
+            base: name.to_string(),
+            file: format!("{name}.{}", unit_type.extension()),
+            install: format!("{name}.{}", unit_type.extension()),
+            template: false,
+        });
+    }
 
-    Ok(())
+    let file = systemd::template_unit_name(name, unit_type);
+    let base = file.split_once('@').map_or(name, |(b, _)| b).to_string();
+    let install = match default_instance {
+        Some(instance) => {
+            systemd::validate_instance(instance)?;
+            systemd::instance_unit_name(&file, instance)
+        }
+        None if install => {
+            return Err(MkunitError::invalid_argument(format!(
+                "A template can't be installed without an instance. \
+                 Use --default-instance or 'mkunit instance add {file} <instance>'"
+            )));
+        }
+        None => file.clone(),
+    };
+
+    Ok(UnitNames {
+        base,
+        file,
+        install,
+        template: true,
+    })
+}
+
+/// Name of a unit that systemd requires to match a path (mount, automount, swap)
+///
+/// Without `name` the escaped path is used; a `name` that doesn't match it is
+/// rejected, since systemd refuses to load such units. Returns the name
+/// without the type suffix.
+pub fn path_unit_name(name: Option<&str>, path: &str, unit_type: UnitType) -> Result<String> {
+    let expected = systemd::escape::path_unit_name(path, unit_type)?;
+    let stem = expected
+        .strip_suffix(&format!(".{}", unit_type.extension()))
+        .unwrap_or(&expected)
+        .to_string();
+
+    if let Some(name) = name {
+        let given = name
+            .strip_suffix(&format!(".{}", unit_type.extension()))
+            .unwrap_or(name);
+        if given != stem {
+            return Err(MkunitError::invalid_argument(format!(
+                "{unit_type} unit for '{path}' must be named '{expected}', not '{given}.{unit_type}'"
+            )));
+        }
+    }
+
+    Ok(stem)
 }
 
 /// Handle installation and starting of a unit
@@ -99,25 +260,167 @@ pub fn handle_install_and_start(
     Ok(())
 }
 
+/// Record the systemctl actions [`handle_install_and_start`] performs
+pub fn record_install(op: &mut Operation, unit_name: &str, install: bool, start: bool) {
+    if install {
+        op.record_action(Action::DaemonReload, unit_name);
+        op.record_action(Action::Enable, unit_name);
+        if start {
+            op.record_action(Action::Start, unit_name);
+        }
+    }
+}
+
+/// Install and start a freshly written unit, restoring the previous file on failure
+///
+/// On success the write and any systemctl actions are recorded in the journal.
+pub fn install_or_rollback(
+    written: &WrittenUnit,
+    unit_name: &str,
+    install: bool,
+    start: bool,
+    system: bool,
+    dry_run: bool,
+) -> Result<()> {
+    install_with_job_or_rollback(written, None, unit_name, install, start, system, dry_run)
+}
+
+/// [`install_or_rollback`] for a trigger unit and the service written for it
+///
+/// Both files are recorded as one operation, and both are restored if the
+/// trigger can't be installed.
+pub fn install_with_job_or_rollback(
+    written: &WrittenUnit,
+    job: Option<&WrittenUnit>,
+    unit_name: &str,
+    install: bool,
+    start: bool,
+    system: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let err = match handle_install_and_start(unit_name, install, start, system, dry_run) {
+        Ok(()) => {
+            if !dry_run {
+                let mut op = Operation::new(format!("create {unit_name}"), system);
+                if let Some(job) = job {
+                    op.record_file(&job.path, job.previous.clone());
+                }
+                op.record_file(&written.path, written.previous.clone());
+                record_install(&mut op, unit_name, install, start);
+                op.commit();
+            }
+            return Ok(());
+        }
+        Err(e) => e,
+    };
+
+    color::print_warning(&format!(
+        "Rolling back {} after failed install",
+        written.path.display()
+    ));
+
+    // A new unit that got enabled before start failed must not stay enabled
+    if written.previous.is_none() && systemd::is_unit_enabled(unit_name, system) {
+        systemd::disable_unit(unit_name, system).ok();
+    }
+
+    for unit in job.into_iter().chain([written]) {
+        if let Err(e) = unit.restore() {
+            color::print_warning(&format!("Failed to restore {}: {e}", unit.path.display()));
+        }
+    }
+    systemd::daemon_reload(system).ok();
+
+    Err(err)
+}
+
+/// Remove the service written for a trigger again if writing the trigger failed
+pub fn restore_job_on_error<T>(result: Result<T>, job: Option<&WrittenUnit>) -> Result<T> {
+    if let (Err(_), Some(job)) = (&result, job) {
+        if let Err(e) = job.restore() {
+            color::print_warning(&format!("Failed to restore {}: {e}", job.path.display()));
+        }
+    }
+    result
+}
+
+/// A path used by a unit that lives on a mount other than the root filesystem
+#[derive(Debug)]
+pub struct MountDependency {
+    /// Directive the path is used in
+    pub directive: String,
+    pub path: String,
+    pub mount_point: String,
+}
+
+/// Find the paths that need `RequiresMountsFor=` so the unit waits for their mount
+///
+/// `paths` pairs each path with the directive it is used for. A leading `-`
+/// (optional file) is ignored.
+#[must_use]
+pub fn mount_dependencies(paths: &[(&str, &str)], table: &MountTable) -> Vec<MountDependency> {
+    let mut dependencies: Vec<MountDependency> = Vec::new();
+    for (directive, path) in paths {
+        let path = path.strip_prefix('-').unwrap_or(path);
+        if dependencies.iter().any(|d| d.path == path) {
+            continue;
+        }
+        if let Some(mount_point) = table.separate_mount(path) {
+            dependencies.push(MountDependency {
+                directive: (*directive).to_string(),
+                path: path.to_string(),
+                mount_point: mount_point.to_string(),
+            });
+        }
+    }
+    dependencies
+}
+
+/// `RequiresMountsFor=` value for the given dependencies, `None` if there are none
+#[must_use]
+pub fn requires_mounts_for(mounts: &[MountDependency]) -> Option<String> {
+    if mounts.is_empty() {
+        return None;
+    }
+    let paths: Vec<String> = mounts.iter().map(|m| m.path.clone()).collect();
+    Some(quote::exec_line(&paths, Expansion::Systemd))
+}
+
 /// Validate common issues and print warnings
-pub fn validate_and_warn(exec_path: Option<&str>, workdir: Option<&str>) -> Vec<ValidationWarning> {
+///
+/// `execs` pairs each command with the `Exec*=` directive it is used for.
+/// `mounts` are noted so it is clear where `RequiresMountsFor=` came from.
+/// `listeners` pairs each socket address with its `Listen*=` directive; FIFOs
+/// are passed as [`ListenAddress::Unix`] so their directory is checked too.
+pub fn validate_and_warn(
+    execs: &[(&str, &ExecCommand)],
+    workdir: Option<&str>,
+    mounts: &[MountDependency],
+    listeners: &[(&str, &ListenAddress)],
+    system: bool,
+) -> Vec<ValidationWarning> {
     let mut warnings = Vec::new();
 
-    // Check for relative paths in exec
-    if let Some(exec) = exec_path {
-        let first_part = exec.split_whitespace().next().unwrap_or(exec);
+    for (directive, command) in execs {
+        let first_part = command.executable();
+
+        // Check for relative paths in exec
         if !first_part.starts_with('/') && !first_part.starts_with('$') {
             warnings.push(ValidationWarning::with_suggestion(
-                format!("ExecStart path '{first_part}' is not absolute"),
+                format!("{directive} path '{first_part}' is not absolute"),
                 "Use absolute paths for reliability",
             ));
         }
 
         // Check if executable exists
         if first_part.starts_with('/') && !Path::new(first_part).exists() {
-            warnings.push(ValidationWarning::new(format!(
-                "Executable '{first_part}' not found"
-            )));
+            if command.ignore_failure {
+                continue;
+            }
+            warnings.push(ValidationWarning::with_suggestion(
+                format!("{directive} executable '{first_part}' not found"),
+                "Prefix the command with '-' if it may be missing",
+            ));
         }
 
         // Check for shebang if it's a script
@@ -142,12 +445,214 @@ pub fn validate_and_warn(exec_path: Option<&str>, workdir: Option<&str>) -> Vec<
         }
     }
 
+    for mount in mounts {
+        warnings.push(ValidationWarning::new(format!(
+            "{} '{}' is on the {} mount; added RequiresMountsFor={}",
+            mount.directive, mount.path, mount.mount_point, mount.path
+        )));
+    }
+
+    // Check that socket paths can be created
+    for (directive, address) in listeners {
+        let ListenAddress::Unix(path) = address else {
+            continue;
+        };
+        let Some(dir) = Path::new(path).parent() else {
+            continue;
+        };
+        // The manager provides the directory of a bare specifier such as %t
+        if path.starts_with('%') && dir.as_os_str().len() == 2 {
+            continue;
+        }
+        // Directory specifiers don't depend on the unit name
+        let context = ExpandContext {
+            unit_name: String::new(),
+            unit_path: None,
+            system,
+        };
+        let dir = specifier::expand(&dir.to_string_lossy(), &context);
+        let dir = Path::new(&dir);
+        if !dir.exists() {
+            let hint = if system {
+                "systemd creates it when the socket starts; set it up beforehand if it needs \
+                 a particular owner"
+            } else {
+                "A user socket can only create it where you may write, such as under \
+                 $XDG_RUNTIME_DIR (%t)"
+            };
+            warnings.push(ValidationWarning::with_suggestion(
+                format!(
+                    "Directory '{}' for {directive}={path} does not exist",
+                    dir.display()
+                ),
+                hint,
+            ));
+        }
+    }
+
+    if listeners
+        .iter()
+        .any(|(_, address)| address.port().is_some())
+    {
+        warnings.extend(port_warnings(listeners, system, &PortTable::current()));
+    }
+
     warnings
 }
 
+/// Warn about ports that are taken or that a user manager may not bind
+fn port_warnings(
+    listeners: &[(&str, &ListenAddress)],
+    system: bool,
+    table: &PortTable,
+) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+
+    for (directive, address) in listeners {
+        let Some(port) = address.port() else {
+            continue;
+        };
+
+        if !system && port < table.unprivileged_port_start {
+            warnings.push(ValidationWarning::with_suggestion(
+                format!(
+                    "{directive}={address} is a privileged port, which a user socket can't bind"
+                ),
+                format!(
+                    "Use a port of {} or above, or create a system socket with --system",
+                    table.unprivileged_port_start
+                ),
+            ));
+        }
+
+        let protocol = match *directive {
+            "ListenStream" => Protocol::Tcp,
+            "ListenDatagram" => Protocol::Udp,
+            _ => continue,
+        };
+        if let Some(bound) = table.conflict(protocol, address) {
+            warnings.push(ValidationWarning::with_suggestion(
+                format!("{directive}={address} is already in use ({bound} is bound)"),
+                "Another service or socket unit holds the port; see 'ss -tulpn'",
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Offer a resource limit preset, `None` if no limits were chosen
+pub fn prompt_resource_profile(prompts: &PromptBuilder) -> Result<Option<ProfileLimits>> {
+    let labels = ResourceProfile::ALL.map(ResourceProfile::label);
+    let choice = prompts.select("Resource limits", &labels, 0)?;
+    Ok(ResourceProfile::ALL[choice].limits())
+}
+
+/// Warn about a `MemoryHigh=` that can never take effect
+pub fn check_memory_limits(high: Option<ByteLimit>, max: Option<ByteLimit>) {
+    // MemoryHigh only throttles if it is reached before MemoryMax
+    if let (Some(ByteLimit::Bytes(high)), Some(ByteLimit::Bytes(max))) = (high, max) {
+        if high >= max {
+            color::print_warning("MemoryHigh is not below MemoryMax and will have no effect");
+        }
+    }
+}
+
 /// Print validation warnings
 pub fn print_warnings(warnings: &[ValidationWarning]) {
     for warning in warnings {
         color::print_warning(&warning.to_string());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mount_dependencies() {
+        let table = MountTable::parse(
+            "22 1 8:2 / / rw - ext4 /dev/sda2 rw\n24 22 8:3 / /srv rw - xfs /dev/sda3 rw\n",
+        );
+        let mounts = mount_dependencies(
+            &[
+                ("ExecStart", "/srv/app/bin/run"),
+                ("ExecStop", "/usr/bin/kill"),
+                ("WorkingDirectory", "/srv/app"),
+                ("EnvironmentFile", "-/srv/app/env"),
+                ("ExecReload", "/srv/app/bin/run"),
+            ],
+            &table,
+        );
+        let paths: Vec<&str> = mounts.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, ["/srv/app/bin/run", "/srv/app", "/srv/app/env"]);
+        assert_eq!(mounts[0].mount_point, "/srv");
+        assert_eq!(
+            requires_mounts_for(&mounts).as_deref(),
+            Some("/srv/app/bin/run /srv/app /srv/app/env")
+        );
+        assert!(requires_mounts_for(&[]).is_none());
+    }
+
+    #[test]
+    fn test_port_warnings() {
+        // 0.0.0.0:8080 listening, the same in either byte order
+        let mut table = PortTable::default();
+        table.add(
+            Protocol::Tcp,
+            "  sl  local_address rem_address   st\n   0: 00000000:1F90 00000000:0000 0A\n",
+        );
+        let addresses: Vec<ListenAddress> = ["80", "127.0.0.1:8080", "8080", "/run/app.sock"]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        let listeners = [
+            ("ListenStream", &addresses[0]),
+            ("ListenStream", &addresses[1]),
+            ("ListenDatagram", &addresses[2]),
+            ("ListenStream", &addresses[3]),
+        ];
+        let messages = |system| -> Vec<String> {
+            port_warnings(&listeners, system, &table)
+                .into_iter()
+                .map(|w| w.message)
+                .collect()
+        };
+
+        assert_eq!(
+            messages(false),
+            [
+                "ListenStream=80 is a privileged port, which a user socket can't bind",
+                "ListenStream=127.0.0.1:8080 is already in use (TCP 0.0.0.0:8080 is bound)",
+            ]
+        );
+        assert_eq!(
+            messages(true),
+            ["ListenStream=127.0.0.1:8080 is already in use (TCP 0.0.0.0:8080 is bound)"]
+        );
+    }
+
+    #[test]
+    fn test_socket_directory() {
+        let missing: ListenAddress = "/nonexistent/mkunit/app.sock".parse().unwrap();
+        let existing: ListenAddress = "/tmp/app.sock".parse().unwrap();
+        let specifier: ListenAddress = "%t/app.sock".parse().unwrap();
+        let warnings = validate_and_warn(
+            &[],
+            None,
+            &[],
+            &[
+                ("ListenStream", &missing),
+                ("ListenStream", &existing),
+                ("ListenStream", &specifier),
+            ],
+            true,
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].message,
+            "Directory '/nonexistent/mkunit' for ListenStream=/nonexistent/mkunit/app.sock \
+             does not exist"
+        );
+    }
+}
@@ -1,17 +1,41 @@
 use crate::cli::MountArgs;
-use crate::commands::{handle_install_and_start, write_unit};
-use crate::error::Result;
+use crate::commands::{automount, install_or_rollback, path_unit_name, print_warnings, write_unit};
+use crate::error::{Result, ValidationWarning};
+use crate::systemd::fstab::resolve_source;
 use crate::systemd::{unit_path, UnitType};
-use crate::templates::{MountData, Templates};
+use crate::templates::{AutomountData, MountData, Templates};
 use crate::util::prompt::PromptBuilder;
+use std::fs;
+use std::path::Path;
+
+/// Filesystem types that need the network, mounted via remote-fs.target
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "sshfs",
+    "fuse.sshfs",
+    "glusterfs",
+    "ceph",
+    "9p",
+    "davfs",
+];
+
+/// Directories searched for `mount.<type>` helpers, as mount(8) does
+const MOUNT_HELPER_DIRS: &[&str] = &["/sbin", "/usr/sbin", "/usr/bin", "/bin"];
+
+/// Ordering for network filesystems, which need the network to be configured
+pub const NETWORK_ONLINE_TARGET: &str = "network-online.target";
 
 pub fn run(args: MountArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
     let prompts = PromptBuilder::new(no_interactive);
 
-    // Get what (source) - required
+    // Get what (source) - required, with UUID= and friends turned into the
+    // device node so that systemd waits for the device
     let what = match args.what {
-        Some(w) => w,
-        None => prompts.required("Source device/path")?,
+        Some(w) => resolve_source(&w),
+        None => resolve_source(&prompts.required("Source device/path")?),
     };
 
     // Get where (mount point) - required
@@ -20,36 +44,190 @@ pub fn run(args: MountArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
         None => prompts.required("Mount point")?,
     };
 
+    // systemd only loads a mount unit named after its mount point
+    let name = path_unit_name(args.name.as_deref(), &mount_where, UnitType::Mount)?;
+
     // Get description
     let description = args
         .description
         .unwrap_or_else(|| format!("Mount {what} at {mount_where}"));
 
+    let network = is_network_fs(args.fs_type.as_deref(), args.options.as_deref());
+    print_warnings(&check_source(&what, args.fs_type.as_deref(), &mount_where));
+
+    // With an automount the mount is started on access, so only the automount is installed
+    let wanted_by = if args.automount {
+        None
+    } else {
+        Some(
+            args.wanted_by
+                .clone()
+                .unwrap_or_else(|| "multi-user.target".to_string()),
+        )
+    };
+
     // Build template data
     let data = MountData {
         description,
+        requires: None,
+        after: network.then(|| NETWORK_ONLINE_TARGET.to_string()),
+        before: None,
+        wants: network.then(|| NETWORK_ONLINE_TARGET.to_string()),
         what,
-        r#where: mount_where,
+        r#where: mount_where.clone(),
         fs_type: args.fs_type,
         options: args.options,
-        wanted_by: args.wanted_by,
+        timeout_sec: None,
+        wanted_by,
     };
 
+    create(
+        &data,
+        &name,
+        args.output.as_deref(),
+        args.install && !args.automount,
+        args.system,
+        dry_run,
+    )?;
+
+    if args.automount {
+        let default_target = if network {
+            "remote-fs.target"
+        } else {
+            "local-fs.target"
+        };
+        let data = AutomountData {
+            description: format!("Automount {mount_where}"),
+            r#where: mount_where,
+            timeout_idle_sec: args.timeout_idle_sec.map(|t| t.to_string()),
+            directory_mode: args
+                .directory_mode
+                .as_deref()
+                .map(automount::parse_mode)
+                .transpose()?,
+            extra_options: args.extra_options,
+            wanted_by: args.wanted_by.unwrap_or_else(|| default_target.to_string()),
+        };
+        let output = args.output.as_deref().map(automount::output_path);
+        automount::create(
+            &data,
+            &name,
+            output.as_deref(),
+            args.install,
+            args.system,
+            dry_run,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Render, write and optionally enable a mount unit
+pub fn create(
+    data: &MountData,
+    name: &str,
+    output: Option<&str>,
+    install: bool,
+    system: bool,
+    dry_run: bool,
+) -> Result<()> {
     // Render template
     let templates = Templates::new()?;
-    let content = templates.render_mount(&data)?;
+    let content = templates.render_mount(data)?;
 
     // Get output path
-    let unit_file_path = unit_path(&args.name, UnitType::Mount, args.system)?;
+    let unit_file_path = unit_path(name, UnitType::Mount, system)?;
 
     // Write unit file
-    write_unit(&content, &unit_file_path, args.output.as_deref(), dry_run)?;
+    let written = write_unit(&content, &unit_file_path, output, system, dry_run)?;
 
     // Handle installation
-    if args.output.is_none() {
-        let unit_name = format!("{}.mount", args.name);
-        handle_install_and_start(&unit_name, args.install, false, args.system, dry_run)?;
+    if output.is_none() {
+        let unit_name = format!("{name}.mount");
+        install_or_rollback(&written, &unit_name, install, false, system, dry_run)?;
     }
 
     Ok(())
 }
+
+/// Whether a filesystem needs the network to be up before it can be mounted
+#[must_use]
+pub fn is_network_fs(fs_type: Option<&str>, options: Option<&str>) -> bool {
+    fs_type.is_some_and(|t| NETWORK_FS_TYPES.contains(&t))
+        || options.is_some_and(|o| o.split(',').any(|opt| opt == "_netdev"))
+}
+
+/// Check that the source device, filesystem type and mount point look usable
+#[must_use]
+pub fn check_source(
+    what: &str,
+    fs_type: Option<&str>,
+    mount_where: &str,
+) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+
+    if what.starts_with("/dev/") && !Path::new(what).exists() {
+        warnings.push(ValidationWarning::with_suggestion(
+            format!("Device '{what}' does not exist"),
+            "Check the UUID or label with 'lsblk -f'",
+        ));
+    }
+
+    if let Some(fs_type) = fs_type.filter(|t| !matches!(*t, "auto" | "none")) {
+        let listed = fs::read_to_string("/proc/filesystems")
+            .is_ok_and(|content| listed_in_proc_filesystems(&content, fs_type));
+        if !listed && !has_mount_helper(fs_type) {
+            warnings.push(ValidationWarning::with_suggestion(
+                format!("Filesystem type '{fs_type}' is not supported by the kernel and has no mount.{fs_type} helper"),
+                "Load the kernel module or install the package providing the helper",
+            ));
+        }
+    }
+
+    let not_empty = fs::read_dir(mount_where).is_ok_and(|mut entries| entries.next().is_some());
+    if not_empty {
+        warnings.push(ValidationWarning::new(format!(
+            "Mount point '{mount_where}' is not empty; its contents will be hidden while mounted"
+        )));
+    }
+
+    warnings
+}
+
+/// Whether a filesystem type appears in the contents of /proc/filesystems
+fn listed_in_proc_filesystems(content: &str, fs_type: &str) -> bool {
+    // FUSE filesystems are listed as "fuse" and handled by mount.fuse
+    let base = fs_type.split('.').next().unwrap_or(fs_type);
+    content
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .any(|name| name == fs_type || name == base)
+}
+
+fn has_mount_helper(fs_type: &str) -> bool {
+    MOUNT_HELPER_DIRS
+        .iter()
+        .any(|dir| Path::new(dir).join(format!("mount.{fs_type}")).exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listed_in_proc_filesystems() {
+        let content = "nodev\tsysfs\nnodev\ttmpfs\n\text4\nnodev\tfuse\n";
+        assert!(listed_in_proc_filesystems(content, "ext4"));
+        assert!(listed_in_proc_filesystems(content, "tmpfs"));
+        assert!(listed_in_proc_filesystems(content, "fuse.sshfs"));
+        assert!(!listed_in_proc_filesystems(content, "xfs"));
+        assert!(!listed_in_proc_filesystems(content, "nodev"));
+    }
+
+    #[test]
+    fn test_is_network_fs() {
+        assert!(is_network_fs(Some("nfs"), None));
+        assert!(is_network_fs(Some("ext4"), Some("defaults,_netdev")));
+        assert!(!is_network_fs(Some("ext4"), Some("defaults")));
+    }
+}
@@ -1,5 +1,9 @@
 use crate::cli::PathArgs;
-use crate::commands::{handle_install_and_start, write_unit};
+use crate::commands::{
+    install_with_job_or_rollback, restore_job_on_error,
+    service::{self, JobKind},
+    unit_names, write_unit,
+};
 use crate::error::Result;
 use crate::systemd::{unit_path, UnitType};
 use crate::templates::{PathData, Templates};
@@ -7,16 +11,21 @@ use crate::util::prompt::PromptBuilder;
 
 pub fn run(args: PathArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
     let prompts = PromptBuilder::new(no_interactive);
+    let names = unit_names(
+        &args.name,
+        UnitType::Path,
+        args.template,
+        args.default_instance.as_deref(),
+        args.install,
+    )?;
 
     // Get unit to trigger
-    let unit = args
-        .unit
-        .unwrap_or_else(|| format!("{}.service", args.name));
+    let unit = args.unit.unwrap_or_else(|| names.service());
 
     // Get description
     let description = args
         .description
-        .unwrap_or_else(|| format!("{} path watcher", args.name));
+        .unwrap_or_else(|| names.description("path watcher"));
 
     // Ensure at least one path is specified
     let has_path = args.path_exists.is_some()
@@ -42,6 +51,7 @@ pub fn run(args: PathArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
         make_directory: args.make_directory,
         unit,
         wanted_by: args.wanted_by,
+        default_instance: args.default_instance,
     };
 
     // Render template
@@ -49,15 +59,42 @@ pub fn run(args: PathArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
     let content = templates.render_path(&data)?;
 
     // Get output path
-    let unit_file_path = unit_path(&args.name, UnitType::Path, args.system)?;
+    let unit_file_path = unit_path(&names.file, UnitType::Path, args.system)?;
+
+    // Create the triggered service when a command was given
+    let job = service::create_job(
+        &args.job,
+        JobKind::Oneshot,
+        &data.unit,
+        names.description("job"),
+        args.output.as_deref(),
+        args.system,
+        dry_run,
+    )?;
 
     // Write unit file
-    write_unit(&content, &unit_file_path, args.output.as_deref(), dry_run)?;
+    let written = restore_job_on_error(
+        write_unit(
+            &content,
+            &unit_file_path,
+            args.output.as_deref(),
+            args.system,
+            dry_run,
+        ),
+        job.as_ref(),
+    )?;
 
     // Handle installation
     if args.output.is_none() {
-        let unit_name = format!("{}.path", args.name);
-        handle_install_and_start(&unit_name, args.install, false, args.system, dry_run)?;
+        install_with_job_or_rollback(
+            &written,
+            job.as_ref(),
+            &names.install,
+            args.install,
+            false,
+            args.system,
+            dry_run,
+        )?;
     }
 
     Ok(())
@@ -1,8 +1,10 @@
 use crate::cli::RemoveArgs;
 use crate::error::Result;
+use crate::journal::{Action, Operation};
 use crate::systemd::{self, find_unit, is_mkunit_created};
-use crate::util::{color, prompt};
+use crate::util::{color, file, prompt};
 use std::fs;
+use std::path::Path;
 
 pub fn run(args: RemoveArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
     // Find the unit file
@@ -38,26 +40,74 @@ pub fn run(args: RemoveArgs, dry_run: bool, no_interactive: bool) -> Result<()>
         return Ok(());
     }
 
+    // Whatever was done before a failure still has to be undoable
+    let mut op = Operation::new(format!("remove {unit_name}"), args.system);
+    let result = remove_unit(&mut op, unit_name, &unit_path, args.system);
+    op.commit();
+    result?;
+
+    color::print_success(&format!("Removed {unit_name}"));
+
+    Ok(())
+}
+
+/// Stop, disable and delete a unit, recording each step in `op` once it succeeded
+fn remove_unit(op: &mut Operation, unit_name: &str, unit_path: &Path, system: bool) -> Result<()> {
+    // Keep what is being removed so it can be restored; nothing is touched
+    // if it can't be read
+    let link_target = if unit_path.is_symlink() {
+        Some(fs::read_link(unit_path)?)
+    } else {
+        None
+    };
+    let content = match link_target {
+        Some(_) => None,
+        None => file::read_previous(unit_path)?,
+    };
+
     // Stop if active
-    if systemd::is_unit_active(unit_name, args.system) {
+    if systemd::is_unit_active(unit_name, system) {
         color::print_info(&format!("Stopping {unit_name}..."));
-        systemd::stop_unit(unit_name, args.system)?;
+        systemd::stop_unit(unit_name, system)?;
+        op.record_action(Action::Stop, unit_name);
     }
 
     // Disable if enabled
-    if systemd::is_unit_enabled(unit_name, args.system) {
+    if systemd::is_unit_enabled(unit_name, system) {
         color::print_info(&format!("Disabling {unit_name}..."));
-        systemd::disable_unit(unit_name, args.system)?;
+        systemd::disable_unit(unit_name, system)?;
+        op.record_action(Action::Disable, unit_name);
     }
 
     // Remove the file
     color::print_info(&format!("Removing {}...", unit_path.display()));
-    fs::remove_file(&unit_path)?;
+    fs::remove_file(unit_path)?;
+    if let Some(target) = link_target {
+        op.record_removed_symlink(unit_path, target);
+    } else if let Some(content) = content {
+        op.record_removed_file(unit_path, content);
+    }
 
     // Reload daemon
-    systemd::daemon_reload(args.system)?;
-
-    color::print_success(&format!("Removed {unit_name}"));
+    systemd::daemon_reload(system)?;
+    op.record_action(Action::DaemonReload, unit_name);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_unreadable_unit_is_kept() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("binary.service");
+        fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+
+        let mut op = Operation::new("remove binary.service", false);
+        assert!(remove_unit(&mut op, "binary.service", &path, false).is_err());
+        assert!(path.exists());
+    }
+}
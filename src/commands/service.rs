@@ -1,60 +1,79 @@
-use crate::cli::ServiceArgs;
-use crate::commands::{handle_install_and_start, print_warnings, validate_and_warn, write_unit};
-use crate::error::Result;
-use crate::systemd::{unit_path, UnitType};
-use crate::templates::{ServiceData, Templates};
+use crate::cli::{JobArgs, ServiceArgs, ServiceType};
+use crate::commands::{
+    check_memory_limits, install_or_rollback, mount_dependencies, print_warnings,
+    prompt_resource_profile, requires_mounts_for, unit_names, validate_and_warn, write_unit,
+    MountDependency, WrittenUnit,
+};
+use crate::error::{MkunitError, Result};
+use crate::systemd::exec::ExecCommand;
+use crate::systemd::mountinfo::MountTable;
+use crate::systemd::quote::{self, Expansion};
+use crate::systemd::{template_unit_name, unit_path, UnitType};
+use crate::templates::{ResourceData, ServiceData, Templates};
 use crate::util::prompt::PromptBuilder;
 use std::env;
+use std::path::Path;
 
+#[allow(clippy::too_many_lines)]
 pub fn run(args: ServiceArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
     let prompts = PromptBuilder::new(no_interactive);
+    let names = unit_names(
+        &args.name,
+        UnitType::Service,
+        args.template,
+        args.default_instance.as_deref(),
+        args.install,
+    )?;
 
-    // Get exec command (required)
-    let exec = match args.exec {
-        Some(e) => e,
-        None => prompts.required("Command to run")?,
+    // Get exec commands (ExecStart is required)
+    let exec = if args.exec.is_empty() {
+        vec![prompts.required("Command to run")?]
+    } else {
+        args.exec.clone()
     };
+    if exec.len() > 1 && args.service_type != ServiceType::Oneshot {
+        return Err(MkunitError::invalid_argument(
+            "Only oneshot services can have more than one ExecStart command",
+        ));
+    }
+
+    // Split the shell-style commands, keeping their prefixes
+    let commands = ServiceCommands::parse(&args, &exec)?;
+    let expansion = if args.literal {
+        Expansion::Literal
+    } else {
+        Expansion::Systemd
+    };
+
+    let env = args
+        .env
+        .iter()
+        .map(|pair| quote::env_from_pair(pair))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Get resource limits
+    let resources = resource_data(&args, &prompts)?;
 
     // Get description
     let description = args
         .description
-        .unwrap_or_else(|| format!("{} service", args.name));
+        .unwrap_or_else(|| names.description("service"));
 
     // Get working directory
     let workdir = match args.workdir {
         Some(w) => Some(w),
-        None => {
-            let current = env::current_dir()
-                .ok()
-                .and_then(|p| p.to_str().map(String::from));
-            if let Some(ref cwd) = current {
-                let use_cwd = prompts.confirm(
-                    &format!("Use current directory as working directory ({cwd})?"),
-                    false,
-                )?;
-                if use_cwd {
-                    current
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        }
+        None => prompt_workdir(&prompts)?,
     };
 
     // Get user
     let user = match args.user {
         Some(u) => Some(u),
-        None => {
-            if !args.system {
-                None // User units run as the user by default
-            } else {
-                prompts.optional_empty("Run as user (leave empty for root)")?
-            }
-        }
+        None => prompt_user(&prompts, args.system)?,
     };
 
+    // Paths on separate mounts must wait for those mounts
+    let mounts = service_mounts(&commands, workdir.as_deref(), args.env_file.as_deref());
+
     // Build template data
     let data = ServiceData {
         description,
@@ -65,21 +84,39 @@ pub fn run(args: ServiceArgs, dry_run: bool, no_interactive: bool) -> Result<()>
         },
         wants: args.wants,
         requires: args.requires,
+        requires_mounts_for: requires_mounts_for(&mounts),
         service_type: args.service_type.to_string(),
-        exec: exec.clone(),
+        exec_start_pre: exec_lines(&commands.start_pre, expansion),
+        exec: exec_lines(&commands.start, expansion),
+        exec_start_post: exec_lines(&commands.start_post, expansion),
+        exec_reload: exec_lines(&commands.reload, expansion),
+        exec_stop: exec_lines(&commands.stop, expansion),
+        exec_stop_post: exec_lines(&commands.stop_post, expansion),
         workdir: workdir.clone(),
         user,
         group: args.group,
+        standard_input: None,
+        standard_error: None,
         restart: args.restart.to_string(),
-        restart_sec: args.restart_sec,
-        env: args.env,
+        restart_sec: args.restart_sec.to_string(),
+        timeout_start_sec: args.timeout_start_sec.map(|t| t.to_string()),
+        timeout_stop_sec: args.timeout_stop_sec.map(|t| t.to_string()),
+        env,
         env_file: args.env_file,
         hardening: args.hardening,
-        wanted_by: args.wanted_by,
+        resources,
+        wanted_by: Some(args.wanted_by),
+        default_instance: args.default_instance,
     };
 
     // Validate and warn
-    let warnings = validate_and_warn(Some(&exec), workdir.as_deref());
+    let warnings = validate_and_warn(
+        &commands.directives(),
+        workdir.as_deref(),
+        &mounts,
+        &[],
+        args.system,
+    );
     print_warnings(&warnings);
 
     // Render template
@@ -87,16 +124,282 @@ pub fn run(args: ServiceArgs, dry_run: bool, no_interactive: bool) -> Result<()>
     let content = templates.render_service(&data)?;
 
     // Get output path
-    let unit_file_path = unit_path(&args.name, UnitType::Service, args.system)?;
+    let unit_file_path = unit_path(&names.file, UnitType::Service, args.system)?;
 
     // Write unit file
-    write_unit(&content, &unit_file_path, args.output.as_deref(), dry_run)?;
+    let written = write_unit(
+        &content,
+        &unit_file_path,
+        args.output.as_deref(),
+        args.system,
+        dry_run,
+    )?;
 
     // Handle installation
     if args.output.is_none() {
-        let unit_name = format!("{}.service", args.name);
-        handle_install_and_start(&unit_name, args.install, args.start, args.system, dry_run)?;
+        install_or_rollback(
+            &written,
+            &names.install,
+            args.install,
+            args.start,
+            args.system,
+            dry_run,
+        )?;
     }
 
     Ok(())
 }
+
+/// How a service created from `--exec` is started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    /// Runs to completion each time a timer or path unit triggers it
+    Oneshot,
+    /// Started by a socket and handed its listening sockets (`sd_listen_fds`)
+    Listener,
+    /// An instance per connection of an `Accept=yes` socket, with the
+    /// connection as standard input and output
+    Connection,
+}
+
+/// Create the service started by a timer, path or socket unit from `--exec`
+///
+/// Does nothing without a command. The service is written next to the
+/// trigger and isn't enabled; the trigger starts it. The caller installs it
+/// together with the trigger through [`install_with_job_or_rollback`].
+pub fn create_job(
+    job: &JobArgs,
+    kind: JobKind,
+    unit: &str,
+    description: String,
+    trigger_output: Option<&str>,
+    system: bool,
+    dry_run: bool,
+) -> Result<Option<WrittenUnit>> {
+    if job.exec.is_empty() {
+        return Ok(None);
+    }
+
+    // A template trigger starts `name@%i.service`, written as `name@.service`
+    let file = match unit.split_once('@') {
+        Some((prefix, _)) => template_unit_name(prefix, UnitType::Service),
+        None => unit.to_string(),
+    };
+    if let Some((_, ext)) = file.rsplit_once('.') {
+        if UnitType::from_extension(ext).is_some_and(|t| t != UnitType::Service) {
+            return Err(MkunitError::invalid_argument(format!(
+                "--exec creates a service, but the unit to trigger is {unit}"
+            )));
+        }
+    }
+
+    if kind != JobKind::Oneshot && job.exec.len() > 1 {
+        return Err(MkunitError::invalid_argument(
+            "A socket-activated service runs a single --exec command",
+        ));
+    }
+
+    let commands = ServiceCommands {
+        start: job
+            .exec
+            .iter()
+            .map(|e| ExecCommand::parse(e))
+            .collect::<Result<_>>()?,
+        ..ServiceCommands::default()
+    };
+    let expansion = if job.literal {
+        Expansion::Literal
+    } else {
+        Expansion::Systemd
+    };
+    let env = job
+        .env
+        .iter()
+        .map(|pair| quote::env_from_pair(pair))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mounts = service_mounts(&commands, job.workdir.as_deref(), job.env_file.as_deref());
+    let mut data = ServiceData {
+        description,
+        requires_mounts_for: requires_mounts_for(&mounts),
+        exec: exec_lines(&commands.start, expansion),
+        workdir: job.workdir.clone(),
+        user: job.user.clone(),
+        group: job.group.clone(),
+        env,
+        env_file: job.env_file.clone(),
+        wanted_by: None,
+        ..ServiceData::default()
+    };
+    match kind {
+        JobKind::Oneshot => {
+            data.service_type = ServiceType::Oneshot.to_string();
+            data.restart = "no".to_string();
+        }
+        JobKind::Listener => {}
+        JobKind::Connection => {
+            // Standard output follows the input; errors go to the journal
+            // instead of the client
+            data.standard_input = Some("socket".to_string());
+            data.standard_error = Some("journal".to_string());
+            data.restart = "no".to_string();
+        }
+    }
+
+    let warnings = validate_and_warn(
+        &commands.directives(),
+        job.workdir.as_deref(),
+        &mounts,
+        &[],
+        system,
+    );
+    print_warnings(&warnings);
+
+    let templates = Templates::new()?;
+    let content = templates.render_service(&data)?;
+    let unit_file_path = unit_path(&file, UnitType::Service, system)?;
+    let output = trigger_output.map(|trigger| {
+        let name = unit_file_path.file_name().unwrap_or_default();
+        Path::new(trigger)
+            .with_file_name(name)
+            .to_string_lossy()
+            .into_owned()
+    });
+
+    let written = write_unit(
+        &content,
+        &unit_file_path,
+        output.as_deref(),
+        system,
+        dry_run,
+    )?;
+
+    Ok(Some(written))
+}
+
+/// Ask which user a system service runs as
+fn prompt_user(prompts: &PromptBuilder, system: bool) -> Result<Option<String>> {
+    if !system {
+        return Ok(None); // User units run as the user by default
+    }
+    prompts.optional_empty("Run as user (leave empty for root)")
+}
+
+/// Offer the current directory as the working directory
+fn prompt_workdir(prompts: &PromptBuilder) -> Result<Option<String>> {
+    let Some(cwd) = env::current_dir()
+        .ok()
+        .and_then(|p| p.to_str().map(String::from))
+    else {
+        return Ok(None);
+    };
+
+    let use_cwd = prompts.confirm(
+        &format!("Use current directory as working directory ({cwd})?"),
+        false,
+    )?;
+    Ok(use_cwd.then_some(cwd))
+}
+
+/// Collect resource controls, offering a preset profile if none were given
+fn resource_data(args: &ServiceArgs, prompts: &PromptBuilder) -> Result<ResourceData> {
+    let mut memory_high = args.memory_high;
+    let mut memory_max = args.memory_max;
+    let mut cpu_quota = args.cpu_quota;
+    let mut tasks_max = args.tasks_max;
+
+    let any_set = memory_high.is_some()
+        || memory_max.is_some()
+        || cpu_quota.is_some()
+        || args.cpu_weight.is_some()
+        || args.io_weight.is_some()
+        || tasks_max.is_some()
+        || args.limit_nofile.is_some()
+        || args.slice.is_some();
+
+    if !any_set {
+        if let Some(limits) = prompt_resource_profile(prompts)? {
+            memory_high = Some(limits.memory_high);
+            memory_max = Some(limits.memory_max);
+            cpu_quota = Some(limits.cpu_quota);
+            tasks_max = Some(limits.tasks_max);
+        }
+    }
+    check_memory_limits(memory_high, memory_max);
+
+    Ok(ResourceData {
+        slice: args.slice.clone(),
+        memory_high: memory_high.map(|v| v.to_string()),
+        memory_max: memory_max.map(|v| v.to_string()),
+        cpu_quota: cpu_quota.map(|v| v.to_string()),
+        cpu_weight: args.cpu_weight.map(|v| v.to_string()),
+        io_weight: args.io_weight.map(|v| v.to_string()),
+        tasks_max: tasks_max.map(|v| v.to_string()),
+        limit_nofile: args.limit_nofile.map(|v| v.to_string()),
+    })
+}
+
+/// Parsed commands for each `Exec*=` directive of the service
+#[derive(Default)]
+struct ServiceCommands {
+    start_pre: Vec<ExecCommand>,
+    start: Vec<ExecCommand>,
+    start_post: Vec<ExecCommand>,
+    reload: Vec<ExecCommand>,
+    stop: Vec<ExecCommand>,
+    stop_post: Vec<ExecCommand>,
+}
+
+impl ServiceCommands {
+    fn parse(args: &ServiceArgs, start: &[String]) -> Result<Self> {
+        let parse_all = |values: &[String]| -> Result<Vec<ExecCommand>> {
+            values.iter().map(|v| ExecCommand::parse(v)).collect()
+        };
+
+        Ok(Self {
+            start_pre: parse_all(&args.exec_start_pre)?,
+            start: parse_all(start)?,
+            start_post: parse_all(&args.exec_start_post)?,
+            reload: parse_all(&args.exec_reload)?,
+            stop: parse_all(&args.exec_stop)?,
+            stop_post: parse_all(&args.exec_stop_post)?,
+        })
+    }
+
+    /// Every command paired with its directive, in the order systemd runs them
+    fn directives(&self) -> Vec<(&'static str, &ExecCommand)> {
+        [
+            ("ExecStartPre", &self.start_pre),
+            ("ExecStart", &self.start),
+            ("ExecStartPost", &self.start_post),
+            ("ExecReload", &self.reload),
+            ("ExecStop", &self.stop),
+            ("ExecStopPost", &self.stop_post),
+        ]
+        .into_iter()
+        .flat_map(|(directive, commands)| commands.iter().map(move |c| (directive, c)))
+        .collect()
+    }
+}
+
+/// Render commands as `Exec*=` values
+fn exec_lines(commands: &[ExecCommand], expansion: Expansion) -> Vec<String> {
+    commands.iter().map(|c| c.to_line(expansion)).collect()
+}
+
+/// Paths used by the service that live on a separate mount
+fn service_mounts(
+    commands: &ServiceCommands,
+    workdir: Option<&str>,
+    env_file: Option<&str>,
+) -> Vec<MountDependency> {
+    let mut paths: Vec<(&str, &str)> = commands
+        .directives()
+        .into_iter()
+        .map(|(directive, command)| (directive, command.executable()))
+        .collect();
+    paths.extend(workdir.map(|dir| ("WorkingDirectory", dir)));
+    paths.extend(env_file.map(|file| ("EnvironmentFile", file)));
+
+    mount_dependencies(&paths, &MountTable::current())
+}
@@ -1,6 +1,7 @@
 use crate::cli::ShowArgs;
 use crate::error::Result;
 use crate::systemd::find_unit;
+use crate::systemd::specifier::{self, ExpandContext};
 use crate::util::color;
 use std::fs;
 
@@ -9,7 +10,49 @@ pub fn run(args: ShowArgs, _dry_run: bool, _no_interactive: bool) -> Result<()>
     let unit_path = find_unit(&args.name, args.system)?;
 
     // Read and display content
-    let content = fs::read_to_string(&unit_path)?;
+    let mut content = fs::read_to_string(&unit_path)?;
+
+    if args.expand {
+        let file_name = unit_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&args.name);
+
+        // Fill in the instance of a template unit (foo@.service -> foo@bar.service)
+        let unit_name = match (args.instance.as_deref(), file_name.split_once("@.")) {
+            (Some(instance), Some((prefix, suffix))) => format!("{prefix}@{instance}.{suffix}"),
+            _ => file_name.to_string(),
+        };
+
+        let legend = specifier::used(&content);
+
+        let ctx = ExpandContext {
+            unit_name,
+            unit_path: Some(unit_path.display().to_string()),
+            system: args.system,
+        };
+        content = content
+            .lines()
+            .map(|line| {
+                if line.trim_start().starts_with(['#', ';']) {
+                    line.to_string()
+                } else {
+                    specifier::expand(line, &ctx)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        color::print_info(&format!(
+            "Specifiers expanded for {} ({} manager)",
+            ctx.unit_name,
+            if args.system { "system" } else { "user" }
+        ));
+        for spec in legend {
+            println!("  {}", color::hint(&spec.to_string()));
+        }
+        println!();
+    }
 
     println!("{}", color::path(&unit_path.display().to_string()));
     println!();
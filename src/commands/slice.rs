@@ -0,0 +1,89 @@
+use crate::cli::SliceArgs;
+use crate::commands::{
+    check_memory_limits, install_or_rollback, prompt_resource_profile, write_unit,
+};
+use crate::error::Result;
+use crate::systemd::{unit_path, UnitType};
+use crate::templates::{ResourceData, SliceData, Templates};
+use crate::util::color;
+use crate::util::prompt::PromptBuilder;
+
+pub fn run(args: SliceArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
+    let prompts = PromptBuilder::new(no_interactive);
+
+    // Name is already normalized to end in .slice
+    let stem = args.name.trim_end_matches(".slice");
+
+    // Get description
+    let description = args.description.unwrap_or_else(|| format!("{stem} slice"));
+
+    let mut memory_high = args.memory_high;
+    let mut memory_max = args.memory_max;
+    let mut cpu_quota = args.cpu_quota;
+    let mut tasks_max = args.tasks_max;
+
+    let any_set = memory_high.is_some()
+        || memory_max.is_some()
+        || cpu_quota.is_some()
+        || args.cpu_weight.is_some()
+        || args.io_weight.is_some()
+        || tasks_max.is_some();
+
+    // A slice is only useful with limits, so offer a preset if none were given
+    if !any_set {
+        match prompt_resource_profile(&prompts)? {
+            Some(limits) => {
+                memory_high = Some(limits.memory_high);
+                memory_max = Some(limits.memory_max);
+                cpu_quota = Some(limits.cpu_quota);
+                tasks_max = Some(limits.tasks_max);
+            }
+            None => color::print_warning(&format!(
+                "{} has no resource limits; units in it are only grouped",
+                args.name
+            )),
+        }
+    }
+    check_memory_limits(memory_high, memory_max);
+
+    // Build template data
+    let data = SliceData {
+        description,
+        resources: ResourceData {
+            memory_high: memory_high.map(|v| v.to_string()),
+            memory_max: memory_max.map(|v| v.to_string()),
+            cpu_quota: cpu_quota.map(|v| v.to_string()),
+            cpu_weight: args.cpu_weight.map(|v| v.to_string()),
+            io_weight: args.io_weight.map(|v| v.to_string()),
+            tasks_max: tasks_max.map(|v| v.to_string()),
+            ..Default::default()
+        },
+    };
+
+    // Render template
+    let templates = Templates::new()?;
+    let content = templates.render_slice(&data)?;
+
+    // Get output path
+    let unit_file_path = unit_path(&args.name, UnitType::Slice, args.system)?;
+
+    // Write unit file
+    let written = write_unit(
+        &content,
+        &unit_file_path,
+        args.output.as_deref(),
+        args.system,
+        dry_run,
+    )?;
+
+    // Slices have no [Install] section; they start when a unit is placed in them
+    if args.output.is_none() {
+        install_or_rollback(&written, &args.name, false, false, args.system, dry_run)?;
+        color::print_info(&format!(
+            "Place services in it with: mkunit service <name> --slice {}",
+            args.name
+        ));
+    }
+
+    Ok(())
+}
@@ -1,55 +1,298 @@
 use crate::cli::SocketArgs;
-use crate::commands::{handle_install_and_start, write_unit};
-use crate::error::Result;
-use crate::systemd::{unit_path, UnitType};
+use crate::commands::service::{self, JobKind};
+use crate::commands::{
+    automount, install_with_job_or_rollback, print_warnings, restore_job_on_error, unit_names,
+    validate_and_warn, write_unit,
+};
+use crate::error::{MkunitError, Result, ValidationWarning};
+use crate::systemd::listen::ListenAddress;
+use crate::systemd::{template_unit_name, unit_path, UnitType};
 use crate::templates::{SocketData, Templates};
 use crate::util::prompt::PromptBuilder;
+use std::net::IpAddr;
 
+#[allow(clippy::too_many_lines)]
 pub fn run(args: SocketArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
     let prompts = PromptBuilder::new(no_interactive);
+    let names = unit_names(
+        &args.name,
+        UnitType::Socket,
+        args.template,
+        args.default_instance.as_deref(),
+        args.install,
+    )?;
+
+    check_accept(&args, &names.base)?;
 
     // Get description
     let description = args
         .description
-        .unwrap_or_else(|| format!("{} socket", args.name));
+        .unwrap_or_else(|| names.description("socket"));
 
     // Ensure at least one listen is specified
-    let has_listen = args.listen_stream.is_some()
-        || args.listen_datagram.is_some()
-        || args.listen_fifo.is_some();
+    let has_listen = !args.listen_stream.is_empty()
+        || !args.listen_datagram.is_empty()
+        || !args.listen_sequential_packet.is_empty()
+        || !args.listen_fifo.is_empty()
+        || !args.listen_netlink.is_empty();
 
-    let listen_stream = if !has_listen {
-        Some(prompts.required("Listen address (e.g., '8080', '/run/myapp.sock')")?)
-    } else {
-        args.listen_stream
-    };
+    let mut listen_stream = args.listen_stream;
+    if !has_listen {
+        let address = prompts.required("Listen address (e.g., '8080', '/run/myapp.sock')")?;
+        listen_stream.push(address.parse()?);
+    }
+    let packet: Vec<ListenAddress> = args
+        .listen_datagram
+        .iter()
+        .chain(&args.listen_sequential_packet)
+        .cloned()
+        .collect();
+
+    print_warnings(&listener_warnings(
+        [
+            ("ListenStream", &listen_stream),
+            ("ListenDatagram", &args.listen_datagram),
+            ("ListenSequentialPacket", &args.listen_sequential_packet),
+        ],
+        &args.listen_fifo,
+        args.system,
+    ));
 
     // Build template data
+    let strings = |addresses: &[ListenAddress]| addresses.iter().map(ToString::to_string).collect();
     let data = SocketData {
         description,
-        listen_stream,
-        listen_datagram: args.listen_datagram,
+        listen_stream: strings(&listen_stream),
+        listen_datagram: strings(&args.listen_datagram),
+        listen_sequential_packet: strings(&args.listen_sequential_packet),
         listen_fifo: args.listen_fifo,
+        listen_netlink: args.listen_netlink,
+        socket_user: args.socket_user,
+        socket_group: args.socket_group,
+        socket_mode: args
+            .socket_mode
+            .as_deref()
+            .map(automount::parse_mode)
+            .transpose()?,
+        file_descriptor_name: args.file_descriptor_name,
+        reuse_port: args.reuse_port,
+        bind_ipv6_only: args.bind_ipv6_only.map(|b| b.to_string()),
+        backlog: args.backlog,
+        no_delay: args.no_delay,
+        keep_alive: args.keep_alive,
         accept: args.accept,
         max_connections: args.max_connections,
         unit: args.unit,
+        default_instance: args.default_instance,
     };
+    print_warnings(&option_warnings(&listen_stream, &packet, &data));
 
     // Render template
     let templates = Templates::new()?;
     let content = templates.render_socket(&data)?;
 
     // Get output path
-    let unit_file_path = unit_path(&args.name, UnitType::Socket, args.system)?;
+    let unit_file_path = unit_path(&names.file, UnitType::Socket, args.system)?;
+
+    // Create the activated service when a command was given. With Accept=yes
+    // each connection starts an instance of the name@.service template.
+    let (kind, service_unit, service_description) = if data.accept {
+        let unit = template_unit_name(&names.base, UnitType::Service);
+        (
+            JobKind::Connection,
+            unit,
+            format!("{} connection %i", names.base),
+        )
+    } else {
+        let unit = data.unit.clone().unwrap_or_else(|| names.service());
+        (JobKind::Listener, unit, names.description("service"))
+    };
+    let job = service::create_job(
+        &args.job,
+        kind,
+        &service_unit,
+        service_description,
+        args.output.as_deref(),
+        args.system,
+        dry_run,
+    )?;
 
     // Write unit file
-    write_unit(&content, &unit_file_path, args.output.as_deref(), dry_run)?;
+    let written = restore_job_on_error(
+        write_unit(
+            &content,
+            &unit_file_path,
+            args.output.as_deref(),
+            args.system,
+            dry_run,
+        ),
+        job.as_ref(),
+    )?;
 
     // Handle installation
     if args.output.is_none() {
-        let unit_name = format!("{}.socket", args.name);
-        handle_install_and_start(&unit_name, args.install, false, args.system, dry_run)?;
+        install_with_job_or_rollback(
+            &written,
+            job.as_ref(),
+            &names.install,
+            args.install,
+            false,
+            args.system,
+            dry_run,
+        )?;
     }
 
     Ok(())
 }
+
+/// Reject `--accept` combinations systemd refuses to load
+fn check_accept(args: &SocketArgs, base: &str) -> Result<()> {
+    if !args.accept {
+        return Ok(());
+    }
+    if let Some(ref unit) = args.unit {
+        return Err(MkunitError::invalid_argument(format!(
+            "--unit {unit} can't be used with --accept: each connection starts an \
+             instance of {}",
+            template_unit_name(base, UnitType::Service)
+        )));
+    }
+    if !args.listen_datagram.is_empty()
+        || !args.listen_fifo.is_empty()
+        || !args.listen_netlink.is_empty()
+    {
+        return Err(MkunitError::invalid_argument(
+            "--accept only works with --listen-stream and --listen-sequential-packet \
+             sockets, which have connections to accept",
+        ));
+    }
+    Ok(())
+}
+
+/// Taken or privileged ports and missing directories of the listeners
+fn listener_warnings(
+    sockets: [(&str, &Vec<ListenAddress>); 3],
+    fifos: &[String],
+    system: bool,
+) -> Vec<ValidationWarning> {
+    // A FIFO's directory needs to exist just like a unix socket's
+    let fifos: Vec<ListenAddress> = fifos
+        .iter()
+        .map(|path| ListenAddress::Unix(path.clone()))
+        .collect();
+    let listeners: Vec<(&str, &ListenAddress)> = sockets
+        .into_iter()
+        .chain([("ListenFIFO", &fifos)])
+        .flat_map(|(directive, addresses)| addresses.iter().map(move |a| (directive, a)))
+        .collect();
+    validate_and_warn(&[], None, &[], &listeners, system)
+}
+
+/// Options that none of the listeners use, which systemd silently ignores
+fn option_warnings(
+    stream: &[ListenAddress],
+    packet: &[ListenAddress],
+    data: &SocketData,
+) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+    let all = || stream.iter().chain(packet);
+    let ip: Vec<&ListenAddress> = all().filter(|a| a.port().is_some()).collect();
+
+    let has_file =
+        all().any(|a| matches!(a, ListenAddress::Unix(_))) || !data.listen_fifo.is_empty();
+    let file_options = [
+        ("SocketUser", data.socket_user.is_some()),
+        ("SocketGroup", data.socket_group.is_some()),
+        ("SocketMode", data.socket_mode.is_some()),
+    ];
+    let kind = "unix sockets and FIFOs in the file system";
+    unused(&mut warnings, &file_options, has_file, kind);
+
+    let has_tcp = stream.iter().any(|a| a.port().is_some());
+    let tcp_options = [("NoDelay", data.no_delay), ("KeepAlive", data.keep_alive)];
+    unused(&mut warnings, &tcp_options, has_tcp, "TCP sockets");
+
+    let ip_options = [
+        ("ReusePort", data.reuse_port),
+        ("BindIPv6Only", data.bind_ipv6_only.is_some()),
+    ];
+    unused(&mut warnings, &ip_options, !ip.is_empty(), "IP sockets");
+
+    let only_ipv4 = ip.iter().all(|a| {
+        matches!(
+            a,
+            ListenAddress::Inet {
+                ip: IpAddr::V4(_),
+                ..
+            }
+        )
+    });
+    if data.bind_ipv6_only.is_some() && !ip.is_empty() && only_ipv4 {
+        warnings.push(ValidationWarning::new(
+            "BindIPv6Only= has no effect, all IP listeners are IPv4 addresses",
+        ));
+    }
+
+    warnings
+}
+
+/// Warn about the set options in `options` unless a listener of `kind` exists
+fn unused(warnings: &mut Vec<ValidationWarning>, options: &[(&str, bool)], used: bool, kind: &str) {
+    let set: Vec<String> = options
+        .iter()
+        .filter(|(_, set)| *set)
+        .map(|(name, _)| format!("{name}="))
+        .collect();
+    if !set.is_empty() && !used {
+        warnings.push(ValidationWarning::new(format!(
+            "{} only applies to {kind}, which this socket doesn't listen on",
+            set.join(", ")
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(values: &[&str]) -> Vec<ListenAddress> {
+        values.iter().map(|v| v.parse().unwrap()).collect()
+    }
+
+    fn messages(stream: &[&str], packet: &[&str], data: &SocketData) -> Vec<String> {
+        option_warnings(&addresses(stream), &addresses(packet), data)
+            .into_iter()
+            .map(|w| w.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_option_warnings() {
+        let data = SocketData {
+            socket_mode: Some("0660".to_string()),
+            no_delay: true,
+            keep_alive: true,
+            reuse_port: true,
+            bind_ipv6_only: Some("both".to_string()),
+            ..SocketData::default()
+        };
+
+        assert_eq!(
+            messages(&["/run/app.sock"], &[], &data),
+            [
+                "NoDelay=, KeepAlive= only applies to TCP sockets, which this socket doesn't listen on",
+                "ReusePort=, BindIPv6Only= only applies to IP sockets, which this socket doesn't listen on",
+            ]
+        );
+        assert_eq!(
+            messages(&["0.0.0.0:80"], &["127.0.0.1:53"], &data),
+            [
+                "SocketMode= only applies to unix sockets and FIFOs in the file system, \
+                 which this socket doesn't listen on",
+                "BindIPv6Only= has no effect, all IP listeners are IPv4 addresses",
+            ]
+        );
+        assert!(messages(&["8080", "/run/app.sock"], &[], &data).is_empty());
+        assert!(messages(&["[::]:80"], &[], &SocketData::default()).is_empty());
+    }
+}
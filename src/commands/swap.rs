@@ -0,0 +1,185 @@
+use crate::cli::SwapArgs;
+use crate::commands::{install_or_rollback, path_unit_name, write_unit};
+use crate::error::{MkunitError, Result};
+use crate::journal::Operation;
+use crate::systemd::resource::format_bytes;
+use crate::systemd::{unit_path, UnitType};
+use crate::templates::{SwapData, Templates};
+use crate::util::color;
+use crate::util::prompt::PromptBuilder;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::process::Command;
+
+/// Smallest swap area mkswap accepts (10 pages)
+const MIN_SWAPFILE_SIZE: u64 = 40 * 1024;
+
+/// Zeros are written in chunks of this size
+const CHUNK_SIZE: usize = 1 << 20;
+
+pub fn run(args: SwapArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
+    let prompts = PromptBuilder::new(no_interactive);
+
+    // Get what (swap device or file) - required
+    let what = match args.what {
+        Some(w) => w,
+        None => prompts.required("Swap device or file")?,
+    };
+
+    // systemd only loads a swap unit named after its device or file
+    let name = path_unit_name(args.name.as_deref(), &what, UnitType::Swap)?;
+
+    // Get description
+    let description = args
+        .description
+        .unwrap_or_else(|| format!("Swap on {what}"));
+
+    // Create the swapfile first so a failure leaves no unit behind
+    let created = match args.create_file {
+        Some(size) => create_swapfile(Path::new(&what), size, dry_run)?,
+        None => false,
+    };
+
+    // Build template data
+    let data = SwapData {
+        description,
+        what: what.clone(),
+        priority: args.priority.map(|p| p.to_string()),
+        options: args.options,
+        timeout_sec: args.timeout_sec.map(|t| t.to_string()),
+        wanted_by: Some(args.wanted_by),
+    };
+
+    let result = create(
+        &data,
+        &name,
+        args.output.as_deref(),
+        args.install,
+        args.system,
+        dry_run,
+    );
+    if result.is_err() && created {
+        color::print_warning(&format!("Removing swapfile {what}"));
+        fs::remove_file(&what).ok();
+    }
+    result
+}
+
+/// Render, write and optionally enable a swap unit
+pub fn create(
+    data: &SwapData,
+    name: &str,
+    output: Option<&str>,
+    install: bool,
+    system: bool,
+    dry_run: bool,
+) -> Result<()> {
+    // Render template
+    let templates = Templates::new()?;
+    let content = templates.render_swap(data)?;
+
+    // Get output path
+    let unit_file_path = unit_path(name, UnitType::Swap, system)?;
+
+    // Write unit file
+    let written = write_unit(&content, &unit_file_path, output, system, dry_run)?;
+
+    // Handle installation
+    if output.is_none() {
+        let unit_name = format!("{name}.swap");
+        install_or_rollback(&written, &unit_name, install, false, system, dry_run)?;
+    }
+
+    Ok(())
+}
+
+/// Allocate a swapfile, make it private and format it with mkswap
+///
+/// The file is filled with zeros rather than preallocated: swapon rejects
+/// files with holes and some filesystems don't support fallocate for swap.
+/// Returns whether a file was created.
+fn create_swapfile(path: &Path, size: u64, dry_run: bool) -> Result<bool> {
+    if size < MIN_SWAPFILE_SIZE {
+        return Err(MkunitError::invalid_argument(format!(
+            "Swapfile size must be at least {}",
+            format_bytes(MIN_SWAPFILE_SIZE)
+        )));
+    }
+    if !path.is_absolute() {
+        return Err(MkunitError::invalid_argument(format!(
+            "Swapfile path '{}' must be absolute",
+            path.display()
+        )));
+    }
+    if path.exists() {
+        return Err(MkunitError::invalid_argument(format!(
+            "{} already exists; remove --create-file to use it as is",
+            path.display()
+        )));
+    }
+
+    if dry_run {
+        println!(
+            "Would create swapfile: {} ({}, mode 0600)",
+            path.display(),
+            format_bytes(size)
+        );
+        println!("Would run: mkswap {}", path.display());
+        return Ok(false);
+    }
+
+    color::print_info(&format!(
+        "Allocating {} swapfile at {}...",
+        format_bytes(size),
+        path.display()
+    ));
+    if let Err(e) = fill_swapfile(path, size).and_then(|()| mkswap(path)) {
+        fs::remove_file(path).ok();
+        return Err(e);
+    }
+
+    let mut op = Operation::new(format!("create swapfile {}", path.display()), true);
+    op.record_file(path, None);
+    op.commit();
+
+    color::print_success(&format!("Created swapfile {}", path.display()));
+    Ok(true)
+}
+
+fn fill_swapfile(path: &Path, size: u64) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+
+    let zeros = vec![0u8; CHUNK_SIZE];
+    let mut remaining = size;
+    while remaining > 0 {
+        let chunk = usize::try_from(remaining).map_or(CHUNK_SIZE, |r| r.min(CHUNK_SIZE));
+        file.write_all(&zeros[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()?;
+
+    Ok(())
+}
+
+fn mkswap(path: &Path) -> Result<()> {
+    let output = Command::new("mkswap")
+        .arg(path)
+        .output()
+        .map_err(|e| MkunitError::Other(format!("Failed to run mkswap: {e}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(MkunitError::Other(format!(
+            "mkswap failed: {}",
+            stderr.trim()
+        )));
+    }
+
+    Ok(())
+}
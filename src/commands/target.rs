@@ -1,5 +1,5 @@
 use crate::cli::TargetArgs;
-use crate::commands::{handle_install_and_start, write_unit};
+use crate::commands::{install_or_rollback, write_unit};
 use crate::error::Result;
 use crate::systemd::{unit_path, UnitType};
 use crate::templates::{TargetData, Templates};
@@ -40,12 +40,25 @@ pub fn run(args: TargetArgs, dry_run: bool, _no_interactive: bool) -> Result<()>
     let unit_file_path = unit_path(&args.name, UnitType::Target, args.system)?;
 
     // Write unit file
-    write_unit(&content, &unit_file_path, args.output.as_deref(), dry_run)?;
+    let written = write_unit(
+        &content,
+        &unit_file_path,
+        args.output.as_deref(),
+        args.system,
+        dry_run,
+    )?;
 
     // Handle installation
     if args.output.is_none() {
         let unit_name = format!("{}.target", args.name);
-        handle_install_and_start(&unit_name, args.install, false, args.system, dry_run)?;
+        install_or_rollback(
+            &written,
+            &unit_name,
+            args.install,
+            false,
+            args.system,
+            dry_run,
+        )?;
     }
 
     Ok(())
@@ -1,78 +1,336 @@
 use crate::cli::TimerArgs;
-use crate::commands::{handle_install_and_start, write_unit};
+use crate::commands::{
+    calendar, install_with_job_or_rollback, restore_job_on_error,
+    service::{self, JobKind},
+    unit_names, write_unit,
+};
 use crate::error::{MkunitError, Result};
+use crate::systemd::calendar::CalendarSpec;
+use crate::systemd::schedule::{self, Schedule};
+use crate::systemd::timespan::Timespan;
+use crate::systemd::version::{self, SystemdVersion};
 use crate::systemd::{unit_path, UnitType};
 use crate::templates::{Templates, TimerData};
+use crate::util::color;
 use crate::util::prompt::PromptBuilder;
 
+/// Number of upcoming elapse times shown when creating a timer
+const PREVIEW_ITERATIONS: usize = 3;
+
 pub fn run(args: TimerArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
     let prompts = PromptBuilder::new(no_interactive);
+    let names = unit_names(
+        &args.name,
+        UnitType::Timer,
+        args.template,
+        args.default_instance.as_deref(),
+        args.install,
+    )?;
 
     // Get unit to trigger
-    let unit = args
-        .unit
-        .unwrap_or_else(|| format!("{}.service", args.name));
+    let unit = args.unit.unwrap_or_else(|| names.service());
 
     // Get description
     let description = args
         .description
-        .unwrap_or_else(|| format!("{} timer", args.name));
+        .unwrap_or_else(|| names.description("timer"));
 
     // Ensure at least one trigger is specified
-    let has_trigger = args.on_calendar.is_some()
-        || args.on_boot.is_some()
-        || args.on_startup.is_some()
-        || args.on_active.is_some()
-        || args.on_unit_active.is_some()
-        || args.on_unit_inactive.is_some();
-
-    let on_calendar = if !has_trigger {
-        // Prompt for trigger
-        let trigger = prompts
-            .required("Timer trigger (e.g., 'daily', '*-*-* 04:00:00', or '5m' for on-boot)")?;
-
-        // Detect if it's a duration or calendar expression
-        if trigger.ends_with('s') || trigger.ends_with('m') || trigger.ends_with('h') {
-            // Looks like a duration, use on-boot
-            return Err(MkunitError::invalid_argument(
-                "For boot-relative timers, use --on-boot flag explicitly",
-            ));
-        }
-        Some(trigger)
-    } else {
-        args.on_calendar
+    let has_trigger = !args.on_calendar.is_empty()
+        || args.every.is_some()
+        || !args.on_boot.is_empty()
+        || !args.on_startup.is_empty()
+        || !args.on_active.is_empty()
+        || !args.on_unit_active.is_empty()
+        || !args.on_unit_inactive.is_empty()
+        || args.on_clock_change
+        || args.on_timezone_change;
+
+    let mut triggers = Triggers {
+        calendar: args.on_calendar,
+        boot: args.on_boot,
+        active: args.on_active,
+        unit_active: args.on_unit_active,
     };
+    if let Some(ref every) = args.every {
+        let schedule = schedule::parse(every)?;
+        confirm_schedule(every, &schedule, &prompts)?;
+        triggers.apply(schedule);
+    } else if !has_trigger {
+        // Without a trigger, ask for one
+        triggers = prompt_trigger(&prompts)?;
+    }
+
+    // Catch typos now instead of after the timer is enabled
+    for expr in &triggers.calendar {
+        let spec = CalendarSpec::parse(expr)?;
+        calendar::print_preview(&spec, PREVIEW_ITERATIONS);
+    }
 
     // Build template data
-    let data = TimerData {
+    let spans = |spans: Vec<Timespan>| spans.iter().map(ToString::to_string).collect();
+    let mut data = TimerData {
         description,
-        on_calendar,
-        on_boot: args.on_boot,
-        on_startup: args.on_startup,
-        on_active: args.on_active,
-        on_unit_active: args.on_unit_active,
-        on_unit_inactive: args.on_unit_inactive,
+        on_calendar: triggers.calendar,
+        on_boot: spans(triggers.boot),
+        on_startup: spans(args.on_startup),
+        on_active: spans(triggers.active),
+        on_unit_active: spans(triggers.unit_active),
+        on_unit_inactive: spans(args.on_unit_inactive),
+        on_clock_change: args.on_clock_change,
+        on_timezone_change: args.on_timezone_change,
         persistent: args.persistent,
-        randomize_delay: args.randomize_delay,
+        randomize_delay: args.randomize_delay.map(|t| t.to_string()),
+        fixed_random_delay: args.fixed_random_delay,
+        accuracy: args.accuracy.map(|t| t.to_string()),
+        wake_system: args.wake_system,
+        remain_after_elapse: args.remain_after_elapse.map(|b| b.to_string()),
         unit,
         wanted_by: args.wanted_by,
+        default_instance: args.default_instance,
     };
+    check_timer_features(&mut data);
 
     // Render template
     let templates = Templates::new()?;
     let content = templates.render_timer(&data)?;
 
     // Get output path
-    let unit_file_path = unit_path(&args.name, UnitType::Timer, args.system)?;
+    let unit_file_path = unit_path(&names.file, UnitType::Timer, args.system)?;
+
+    // Create the triggered service when a command was given
+    let job = service::create_job(
+        &args.job,
+        JobKind::Oneshot,
+        &data.unit,
+        names.description("job"),
+        args.output.as_deref(),
+        args.system,
+        dry_run,
+    )?;
 
     // Write unit file
-    write_unit(&content, &unit_file_path, args.output.as_deref(), dry_run)?;
+    let written = restore_job_on_error(
+        write_unit(
+            &content,
+            &unit_file_path,
+            args.output.as_deref(),
+            args.system,
+            dry_run,
+        ),
+        job.as_ref(),
+    )?;
 
     // Handle installation
     if args.output.is_none() {
-        let unit_name = format!("{}.timer", args.name);
-        handle_install_and_start(&unit_name, args.install, false, args.system, dry_run)?;
+        install_with_job_or_rollback(
+            &written,
+            job.as_ref(),
+            &names.install,
+            args.install,
+            false,
+            args.system,
+            dry_run,
+        )?;
     }
 
     Ok(())
 }
+
+/// Timer settings and the first systemd version that understands them
+const TIMER_FEATURES: &[(&str, u32)] = &[
+    ("AccuracySec", 209),
+    ("WakeSystem", 212),
+    ("RemainAfterElapse", 229),
+    ("OnClockChange", 242),
+    ("OnTimezoneChange", 242),
+    ("FixedRandomDelay", 247),
+];
+
+/// Leave out settings the installed systemd is too old to understand, with a warning
+fn check_timer_features(data: &mut TimerData) {
+    if used_features(data).next().is_none() {
+        return;
+    }
+    let Ok(version) = version::detect_version() else {
+        return;
+    };
+    for (setting, since) in drop_unsupported_features(data, &version) {
+        color::print_warning(&format!(
+            "{setting}= needs systemd {since} or newer and is left out"
+        ));
+    }
+}
+
+/// Settings from [`TIMER_FEATURES`] that the timer uses
+fn used_features(data: &TimerData) -> impl Iterator<Item = &(&'static str, u32)> {
+    TIMER_FEATURES.iter().filter(|(setting, _)| match *setting {
+        "AccuracySec" => data.accuracy.is_some(),
+        "WakeSystem" => data.wake_system,
+        "RemainAfterElapse" => data.remain_after_elapse.is_some(),
+        "OnClockChange" => data.on_clock_change,
+        "OnTimezoneChange" => data.on_timezone_change,
+        "FixedRandomDelay" => data.fixed_random_delay,
+        _ => false,
+    })
+}
+
+/// Remove used settings the given systemd version doesn't support, returning them
+fn drop_unsupported_features(
+    data: &mut TimerData,
+    version: &SystemdVersion,
+) -> Vec<(&'static str, u32)> {
+    let unsupported: Vec<_> = used_features(data)
+        .filter(|(_, since)| !version.supports_feature(*since))
+        .copied()
+        .collect();
+    for (setting, _) in &unsupported {
+        match *setting {
+            "AccuracySec" => data.accuracy = None,
+            "WakeSystem" => data.wake_system = false,
+            "RemainAfterElapse" => data.remain_after_elapse = None,
+            "OnClockChange" => data.on_clock_change = false,
+            "OnTimezoneChange" => data.on_timezone_change = false,
+            "FixedRandomDelay" => data.fixed_random_delay = false,
+            _ => {}
+        }
+    }
+    unsupported
+}
+
+/// Triggers set from the command line, `--every` or the prompt
+#[derive(Debug, Default)]
+struct Triggers {
+    calendar: Vec<String>,
+    boot: Vec<Timespan>,
+    active: Vec<Timespan>,
+    unit_active: Vec<Timespan>,
+}
+
+impl Triggers {
+    fn apply(&mut self, schedule: Schedule) {
+        match schedule {
+            Schedule::Calendar(expr) => self.calendar.push(expr),
+            Schedule::Interval(span) => {
+                // OnUnitActiveSec only counts from the last run, so the
+                // first run is OnActiveSec after the timer starts
+                if self.active.is_empty() {
+                    self.active.push(span);
+                }
+                self.unit_active.push(span);
+            }
+        }
+    }
+}
+
+/// Show what a plain English schedule was turned into and ask to keep it
+fn confirm_schedule(phrase: &str, schedule: &Schedule, prompts: &PromptBuilder) -> Result<()> {
+    let translated = match schedule {
+        Schedule::Calendar(expr) => format!("OnCalendar={expr}"),
+        Schedule::Interval(span) => format!("OnActiveSec={span}, OnUnitActiveSec={span}"),
+    };
+    color::print_info(&format!("'{phrase}' means {translated}"));
+
+    if prompts.confirm("Use this schedule?", true)? {
+        Ok(())
+    } else {
+        Err(MkunitError::UserCancelled)
+    }
+}
+
+/// Kinds of trigger offered by the interactive picker
+const TRIGGER_CHOICES: [&str; 5] = [
+    "Every day at a set time",
+    "Weekdays at a set time",
+    "Every few minutes or hours",
+    "Describe it (e.g., 'first monday of the month at 02:30')",
+    "Calendar expression or delay after boot",
+];
+
+/// Ask for a trigger with a picker for common schedules
+fn prompt_trigger(prompts: &PromptBuilder) -> Result<Triggers> {
+    let phrase = match prompts.select("When should the timer run?", &TRIGGER_CHOICES, 0)? {
+        0 => format!(
+            "day at {}",
+            prompts.required("Time of day (e.g., 9am, 14:30)")?
+        ),
+        1 => format!(
+            "weekday at {}",
+            prompts.required("Time of day (e.g., 9am, 14:30)")?
+        ),
+        2 => prompts.required("Interval (e.g., 15 minutes, 2h)")?,
+        3 => prompts.required("Schedule")?,
+        _ => return prompt_expression(prompts),
+    };
+
+    let schedule = schedule::parse(&phrase)?;
+    confirm_schedule(&phrase, &schedule, prompts)?;
+    let mut triggers = Triggers::default();
+    triggers.apply(schedule);
+    Ok(triggers)
+}
+
+/// Ask for a calendar expression, or a time span run after boot
+fn prompt_expression(prompts: &PromptBuilder) -> Result<Triggers> {
+    let trigger = prompts
+        .required("Timer trigger (e.g., 'daily', '*-*-* 04:00:00', or '5min' after boot)")?;
+
+    match CalendarSpec::parse(&trigger) {
+        Ok(_) => Ok(Triggers {
+            calendar: vec![trigger],
+            ..Triggers::default()
+        }),
+        Err(calendar_err) => match trigger.parse::<Timespan>() {
+            Ok(span) => {
+                color::print_info(&format!("Running {span} after boot (OnBootSec)"));
+                Ok(Triggers {
+                    boot: vec![span],
+                    ..Triggers::default()
+                })
+            }
+            Err(_) => Err(MkunitError::invalid_argument(format!(
+                "'{trigger}' is neither a calendar expression nor a time span: {calendar_err}"
+            ))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsupported_features() {
+        let data = || TimerData {
+            description: "Backup".to_string(),
+            unit: "backup.service".to_string(),
+            on_calendar: vec!["daily".to_string()],
+            on_clock_change: true,
+            accuracy: Some("1s".to_string()),
+            fixed_random_delay: true,
+            ..TimerData::default()
+        };
+
+        let version = SystemdVersion {
+            major: 256,
+            full_version: "systemd 256".to_string(),
+        };
+        let mut current = data();
+        assert!(drop_unsupported_features(&mut current, &version).is_empty());
+        assert!(current.fixed_random_delay);
+
+        let version = SystemdVersion {
+            major: 245,
+            full_version: "systemd 245".to_string(),
+        };
+        let mut old = data();
+        assert_eq!(
+            drop_unsupported_features(&mut old, &version),
+            vec![("FixedRandomDelay", 247)]
+        );
+        let content = Templates::new().unwrap().render_timer(&old).unwrap();
+        assert!(!content.contains("FixedRandomDelay="));
+        assert!(content.contains("OnClockChange=true"));
+        assert!(content.contains("AccuracySec=1s"));
+    }
+}
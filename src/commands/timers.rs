@@ -0,0 +1,470 @@
+use crate::cli::TimersArgs;
+use crate::error::{MkunitError, Result};
+use crate::systemd::calendar::CalendarSpec;
+use crate::systemd::timespan::Timespan;
+use crate::systemd::{list_units, schedule, show_properties, unit_name_from_path};
+use crate::util::color;
+use colored::ColoredString;
+use jiff::civil::DateTime;
+use jiff::tz::TimeZone;
+use jiff::{SignedDuration, Timestamp, Zoned};
+use serde::Serialize;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Trigger settings and how to describe their value
+const TRIGGERS: &[(&str, &str)] = &[
+    ("OnActiveSec", "{} after activation"),
+    ("OnBootSec", "{} after boot"),
+    ("OnStartupSec", "{} after login"),
+    ("OnUnitActiveSec", "every {}"),
+    ("OnUnitInactiveSec", "{} after finishing"),
+];
+
+/// Where the next elapse time came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum NextSource {
+    Manager,
+    Calendar,
+}
+
+#[derive(Debug, Serialize)]
+struct TimerRow {
+    timer: String,
+    system: bool,
+    unit: String,
+    schedule: Vec<String>,
+    #[serde(serialize_with = "serialize_time")]
+    last_trigger: Option<Timestamp>,
+    last_result: Option<String>,
+    #[serde(serialize_with = "serialize_time")]
+    next_elapse: Option<Timestamp>,
+    next_source: Option<NextSource>,
+}
+
+/// The parts of a `.timer` file the overview needs
+#[derive(Debug, Default, PartialEq, Eq)]
+struct TimerSettings {
+    unit: Option<String>,
+    calendars: Vec<String>,
+    schedule: Vec<String>,
+}
+
+pub fn run(args: TimersArgs, _dry_run: bool, _no_interactive: bool) -> Result<()> {
+    let scopes: &[bool] = if args.all {
+        &[false, true]
+    } else if args.system {
+        &[true]
+    } else {
+        &[false]
+    };
+
+    let mut manager = Manager::default();
+    let mut rows = Vec::new();
+    for &system in scopes {
+        for path in list_units(system)? {
+            // Like list-timers, only loadable timers; a template has no schedule of its own
+            let is_timer = path.extension().is_some_and(|ext| ext == "timer");
+            let is_template = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.contains("@."));
+            if is_timer && !is_template {
+                rows.push(timer_row(&path, system, &mut manager)?);
+            }
+        }
+    }
+    rows.sort_by_key(|row| (row.next_elapse.is_none(), row.next_elapse));
+
+    if args.json {
+        let json =
+            serde_json::to_string_pretty(&rows).map_err(|e| MkunitError::Other(e.to_string()))?;
+        println!("{json}");
+    } else {
+        print_table(&rows);
+    }
+
+    Ok(())
+}
+
+/// Messages of `systemctl` failures that concern the manager rather than one unit
+const MANAGER_ERRORS: &[&str] = &[
+    "Failed to run systemctl",
+    "Failed to connect to bus",
+    "Failed to connect to user scope bus",
+    "Failed to get D-Bus connection",
+    "System has not been booted with systemd",
+    "Transport endpoint is not connected",
+    "Connection refused",
+];
+
+/// Tracks whether the service manager answers, so it's only asked until it fails once
+#[derive(Default)]
+struct Manager {
+    unreachable: bool,
+}
+
+impl Manager {
+    /// Values of `properties`, `None` if the manager or this unit couldn't be queried
+    fn show(&mut self, name: &str, properties: &[&str], system: bool) -> Option<Vec<String>> {
+        if self.unreachable {
+            return None;
+        }
+        match show_properties(name, properties, system) {
+            Ok(mut values) => Some(
+                properties
+                    .iter()
+                    .map(|p| values.remove(*p).unwrap_or_default())
+                    .collect(),
+            ),
+            Err(e) if is_manager_error(&e.to_string()) => {
+                self.unreachable = true;
+                color::print_warning(&format!(
+                    "Could not query the service manager ({e}); next runs are computed from the schedule"
+                ));
+                None
+            }
+            Err(e) => {
+                log::debug!("Could not query {name}: {e}");
+                None
+            }
+        }
+    }
+}
+
+fn is_manager_error(message: &str) -> bool {
+    MANAGER_ERRORS.iter().any(|m| message.contains(m))
+}
+
+fn timer_row(path: &Path, system: bool, manager: &mut Manager) -> Result<TimerRow> {
+    let timer = unit_name_from_path(path).unwrap_or_default();
+    let settings = parse_timer(&fs::read_to_string(path)?);
+    let unit = settings
+        .unit
+        .clone()
+        .unwrap_or_else(|| timer.replace(".timer", ".service"));
+
+    let mut row = TimerRow {
+        timer,
+        system,
+        unit,
+        schedule: settings.schedule,
+        last_trigger: None,
+        last_result: None,
+        next_elapse: None,
+        next_source: None,
+    };
+
+    if let Some(values) = manager.show(
+        &row.timer,
+        &[
+            "NextElapseUSecRealtime",
+            "NextElapseUSecMonotonic",
+            "LastTriggerUSec",
+        ],
+        system,
+    ) {
+        // A timer with both kinds of triggers elapses at the earlier one
+        let monotonic = uptime().and_then(|up| monotonic_elapse(&values[1], up, Timestamp::now()));
+        row.next_elapse = parse_timestamp(&values[0])
+            .into_iter()
+            .chain(monotonic)
+            .min();
+        row.next_source = row.next_elapse.map(|_| NextSource::Manager);
+        row.last_trigger = parse_timestamp(&values[2]);
+    }
+    if row.last_trigger.is_some() {
+        if let Some(values) = manager.show(&row.unit, &["Result"], system) {
+            row.last_result = Some(values[0].clone()).filter(|r| !r.is_empty());
+        }
+    }
+
+    // Also covers timers the manager hasn't loaded, which it answers with empty values
+    if row.next_elapse.is_none() {
+        row.next_elapse = next_calendar_elapse(&settings.calendars, &Zoned::now());
+        row.next_source = row.next_elapse.map(|_| NextSource::Calendar);
+    }
+
+    Ok(row)
+}
+
+/// Read the `[Timer]` section of a timer file
+fn parse_timer(content: &str) -> TimerSettings {
+    let mut settings = TimerSettings::default();
+    let mut in_timer = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_timer = line == "[Timer]";
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if !in_timer || value.is_empty() {
+            continue;
+        }
+
+        match key {
+            "Unit" => settings.unit = Some(value.to_string()),
+            "OnCalendar" => {
+                let described = CalendarSpec::parse(value)
+                    .map_or_else(|_| value.to_string(), |spec| schedule::describe(&spec));
+                settings.calendars.push(value.to_string());
+                settings.schedule.push(described);
+            }
+            "OnClockChange" if value == "yes" || value == "true" => {
+                settings.schedule.push("on clock change".to_string());
+            }
+            "OnTimezoneChange" if value == "yes" || value == "true" => {
+                settings.schedule.push("on time zone change".to_string());
+            }
+            _ => {
+                if let Some((_, template)) = TRIGGERS.iter().find(|(k, _)| *k == key) {
+                    let span = value
+                        .parse::<Timespan>()
+                        .map_or_else(|_| value.to_string(), |t| t.to_string());
+                    settings.schedule.push(template.replace("{}", &span));
+                }
+            }
+        }
+    }
+
+    settings
+}
+
+/// Parse a timestamp from `systemctl show`
+///
+/// Newer versions print `@1700000000`; older ones the local time, such as
+/// `Mon 2026-03-02 12:00:00 CET`.
+fn parse_timestamp(value: &str) -> Option<Timestamp> {
+    if let Some(seconds) = value.strip_prefix('@') {
+        let seconds = seconds.parse::<i64>().ok()?;
+        return Timestamp::from_second(seconds).ok().filter(|_| seconds > 0);
+    }
+
+    let [_weekday, date, time, zone] = value.split_whitespace().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let civil: DateTime = format!("{date} {time}").parse().ok()?;
+    let tz = if zone == "UTC" {
+        TimeZone::UTC
+    } else {
+        TimeZone::system()
+    };
+    civil.to_zoned(tz).ok().map(|zoned| zoned.timestamp())
+}
+
+/// Time since boot, as the monotonic clock timers count from
+fn uptime() -> Option<SignedDuration> {
+    let uptime = fs::read_to_string("/proc/uptime").ok()?;
+    uptime
+        .split_whitespace()
+        .next()?
+        .parse::<Timespan>()
+        .ok()?
+        .to_duration()
+}
+
+/// Convert `NextElapseUSecMonotonic`, a span since boot, to the time it falls on
+///
+/// Timers with `OnActiveSec=`, `OnUnitActiveSec=` and the like only have a
+/// monotonic next elapse.
+fn monotonic_elapse(value: &str, uptime: SignedDuration, now: Timestamp) -> Option<Timestamp> {
+    let since_boot = value
+        .parse::<Timespan>()
+        .ok()
+        .filter(|span| !span.is_zero())?
+        .to_duration()?;
+    now.checked_add(since_boot - uptime).ok()
+}
+
+/// The earliest elapse of any calendar expression after `now`
+fn next_calendar_elapse(calendars: &[String], now: &Zoned) -> Option<Timestamp> {
+    calendars
+        .iter()
+        .filter_map(|expr| CalendarSpec::parse(expr).ok())
+        .filter_map(|spec| spec.next_elapses(now, 1).into_iter().next())
+        .map(|zoned| zoned.timestamp())
+        .min()
+}
+
+#[allow(clippy::ref_option)]
+fn serialize_time<S: serde::Serializer>(
+    time: &Option<Timestamp>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match time {
+        Some(ts) => serializer.serialize_str(
+            &ts.to_zoned(TimeZone::system())
+                .strftime("%Y-%m-%dT%H:%M:%S%:z")
+                .to_string(),
+        ),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn format_time(time: Option<Timestamp>) -> String {
+    time.map_or_else(
+        || "-".to_string(),
+        |ts| {
+            ts.to_zoned(TimeZone::system())
+                .strftime("%a %Y-%m-%d %H:%M")
+                .to_string()
+        },
+    )
+}
+
+fn print_table(rows: &[TimerRow]) {
+    if rows.is_empty() {
+        println!("  No timers found");
+        return;
+    }
+
+    let table: Vec<[String; 5]> = rows
+        .iter()
+        .map(|row| {
+            [
+                format_time(row.next_elapse),
+                format_time(row.last_trigger),
+                row.last_result.clone().unwrap_or_else(|| "-".to_string()),
+                row.timer.clone(),
+                row.unit.clone(),
+            ]
+        })
+        .collect();
+
+    let headers = ["NEXT", "LAST", "RESULT", "TIMER", "ACTIVATES"];
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            table
+                .iter()
+                .map(|cells| cells[i].len())
+                .chain([headers[i].len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let mut header = String::new();
+    for (name, width) in headers.iter().zip(&widths) {
+        let _ = write!(header, "{name:<width$}  ");
+    }
+    println!("  {}{}", color::hint(&header), color::hint("SCHEDULE"));
+
+    for (row, cells) in rows.iter().zip(&table) {
+        let pad = |i: usize| format!("{:<width$}", cells[i], width = widths[i]);
+        let result: ColoredString = match row.last_result.as_deref() {
+            Some("success") => color::success(&pad(2)),
+            Some(_) => color::error(&pad(2)),
+            None => color::hint(&pad(2)),
+        };
+        let next = if row.next_source == Some(NextSource::Calendar) {
+            color::hint(&pad(0))
+        } else {
+            color::info(&pad(0))
+        };
+        let scope = if row.system { " [system]" } else { "" };
+
+        println!(
+            "  {next}  {}  {result}  {}  {}  {}{}",
+            pad(1),
+            color::unit_name(&pad(3)),
+            pad(4),
+            row.schedule.join(", "),
+            color::hint(scope)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systemd::parse_properties;
+
+    #[test]
+    fn test_parse_timer() {
+        let settings = parse_timer(
+            "[Unit]\nDescription=Backup\n\n[Timer]\nOnCalendar=Mon..Fri 09:00\n\
+             OnBootSec=90\nOnUnitActiveSec=1h\nOnClockChange=yes\nUnit=backup@home.service\n\n\
+             [Install]\nWantedBy=timers.target\n",
+        );
+        assert_eq!(
+            settings,
+            TimerSettings {
+                unit: Some("backup@home.service".to_string()),
+                calendars: vec!["Mon..Fri 09:00".to_string()],
+                schedule: vec![
+                    "weekdays at 09:00".to_string(),
+                    "1min 30s after boot".to_string(),
+                    "every 1h".to_string(),
+                    "on clock change".to_string(),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_properties() {
+        let values = parse_properties(
+            "NextElapseUSecRealtime=@1700000000\nLastTriggerUSec=\nResult=success\n",
+        );
+        assert_eq!(
+            parse_timestamp(&values["NextElapseUSecRealtime"]),
+            Some(Timestamp::from_second(1_700_000_000).unwrap())
+        );
+        assert_eq!(parse_timestamp(&values["LastTriggerUSec"]), None);
+        assert_eq!(parse_timestamp("n/a"), None);
+        assert_eq!(parse_timestamp("@0"), None);
+        assert_eq!(values["Result"], "success");
+
+        // Before systemd 251
+        assert_eq!(
+            parse_timestamp("Mon 2026-03-02 12:00:00 UTC"),
+            Some("2026-03-02T12:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_monotonic_elapse() {
+        let now: Timestamp = "2026-03-02T10:00:00Z".parse().unwrap();
+        let uptime = SignedDuration::from_hours(2);
+        assert_eq!(
+            monotonic_elapse("2h 30min", uptime, now),
+            Some("2026-03-02T10:30:00Z".parse().unwrap())
+        );
+        assert_eq!(monotonic_elapse("0", uptime, now), None);
+        assert_eq!(monotonic_elapse("infinity", uptime, now), None);
+        assert_eq!(monotonic_elapse("", uptime, now), None);
+    }
+
+    #[test]
+    fn test_is_manager_error() {
+        assert!(is_manager_error(
+            "Systemd error: Failed to connect to bus: No medium found"
+        ));
+        assert!(is_manager_error(
+            "Systemd error: System has not been booted with systemd as init system (PID 1). Can't operate."
+        ));
+        assert!(!is_manager_error(
+            "Systemd error: Failed to get properties: Unit name backup@.timer is missing the instance name."
+        ));
+    }
+
+    #[test]
+    fn test_next_calendar_elapse() {
+        let now: Zoned = "2026-03-02T10:00:00[UTC]".parse().unwrap();
+        let calendars = vec![
+            "*-*-* 18:00:00 UTC".to_string(),
+            "*-*-* 12:00:00 UTC".to_string(),
+        ];
+        assert_eq!(
+            next_calendar_elapse(&calendars, &now),
+            Some("2026-03-02T12:00:00Z".parse().unwrap())
+        );
+        assert_eq!(next_calendar_elapse(&[], &now), None);
+    }
+}
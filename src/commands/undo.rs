@@ -0,0 +1,230 @@
+use crate::cli::UndoArgs;
+use crate::error::{MkunitError, Result};
+use crate::journal::{self, Action, Change, Entry};
+use crate::systemd;
+use crate::util::{color, file, prompt};
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+pub fn run(args: UndoArgs, dry_run: bool, no_interactive: bool) -> Result<()> {
+    let entries = journal::load()?;
+
+    let entry = match args.id {
+        Some(id) => entries
+            .iter()
+            .find(|e| e.id == id)
+            .ok_or_else(|| MkunitError::invalid_argument(format!("No operation with ID {id}")))?,
+        None => entries
+            .iter()
+            .rev()
+            .find(|e| !e.undone)
+            .ok_or_else(|| MkunitError::invalid_argument("No operations to undo"))?,
+    };
+
+    if entry.undone {
+        return Err(MkunitError::invalid_argument(format!(
+            "Operation {} has already been undone",
+            entry.id
+        )));
+    }
+
+    // Restoring older content would silently discard the later changes
+    let conflicts = later_changes(&entries, entry);
+    if !conflicts.is_empty() && !args.force {
+        let list: Vec<String> = conflicts
+            .iter()
+            .map(|(later, path)| {
+                format!(
+                    "operation {} ({}) changed {}",
+                    later.id,
+                    later.command,
+                    path.display()
+                )
+            })
+            .collect();
+        return Err(MkunitError::invalid_argument(format!(
+            "Later operations changed the same files: {}. Undo them first, or use --force \
+             to overwrite their changes",
+            list.join("; ")
+        )));
+    }
+
+    if !args.force {
+        let confirmed = prompt::prompt_confirm_or_abort(
+            &format!("Undo operation {} ({})?", entry.id, entry.command),
+            false,
+            no_interactive,
+        )?;
+        if !confirmed {
+            color::print_info("Cancelled");
+            return Ok(());
+        }
+    }
+
+    if dry_run {
+        for change in entry.changes.iter().rev() {
+            println!("Would revert: {change}");
+        }
+        return Ok(());
+    }
+
+    undo_entry(entry)?;
+    journal::mark_undone(entry.id)?;
+
+    color::print_success(&format!("Undid operation {} ({})", entry.id, entry.command));
+
+    Ok(())
+}
+
+/// Files of `entry` that a later operation, not undone yet, changed again
+fn later_changes<'a>(entries: &'a [Entry], entry: &Entry) -> Vec<(&'a Entry, &'a Path)> {
+    let paths: Vec<&Path> = entry.changes.iter().filter_map(change_path).collect();
+    entries
+        .iter()
+        .filter(|later| later.id > entry.id && !later.undone)
+        .flat_map(|later| {
+            later
+                .changes
+                .iter()
+                .filter_map(change_path)
+                .filter(|path| paths.contains(path))
+                .map(move |path| (later, path))
+        })
+        .collect()
+}
+
+fn change_path(change: &Change) -> Option<&Path> {
+    match change {
+        Change::File { path, .. } | Change::Symlink { path, .. } => Some(path),
+        Change::Systemctl { .. } => None,
+    }
+}
+
+/// Reverse the changes of an entry
+///
+/// Units are stopped and disabled before their files are touched, and only
+/// enabled or started again once the restored files have been reloaded.
+fn undo_entry(entry: &Entry) -> Result<()> {
+    let inverse_actions: Vec<(Action, &str)> = entry
+        .changes
+        .iter()
+        .rev()
+        .filter_map(|change| match change {
+            Change::Systemctl { action, unit } => action.inverse().map(|a| (a, unit.as_str())),
+            _ => None,
+        })
+        .collect();
+
+    for (action, unit) in inverse_actions.iter().filter(|(a, _)| a.is_deactivating()) {
+        run_action(*action, unit, entry.system);
+    }
+
+    for change in entry.changes.iter().rev() {
+        match change {
+            Change::File { path, previous, .. } => {
+                restore_file(path, previous.as_deref(), entry.system)?;
+            }
+            Change::Symlink { path, previous, .. } => {
+                remove_if_present(path)?;
+                if let Some(target) = previous {
+                    symlink(target, path)?;
+                }
+                color::print_info(&format!("Restored {}", path.display()));
+            }
+            Change::Systemctl { .. } => {}
+        }
+    }
+
+    run_action(Action::DaemonReload, "", entry.system);
+
+    for (action, unit) in inverse_actions.iter().filter(|(a, _)| !a.is_deactivating()) {
+        run_action(*action, unit, entry.system);
+    }
+
+    Ok(())
+}
+
+fn restore_file(path: &Path, previous: Option<&str>, system: bool) -> Result<()> {
+    match previous {
+        Some(content) => {
+            // Only files in the system unit directory belong to root; an
+            // --output path keeps the invoking user as owner
+            let root_owned =
+                system && systemd::unit_dir(true).is_ok_and(|dir| path.starts_with(dir));
+            file::atomic_write(path, content.as_bytes(), root_owned)?;
+            color::print_info(&format!("Restored {}", path.display()));
+        }
+        None => {
+            remove_if_present(path)?;
+            color::print_info(&format!("Removed {}", path.display()));
+        }
+    }
+    Ok(())
+}
+
+fn remove_if_present(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Run a systemctl action, warning instead of failing so the rest of the undo still happens
+fn run_action(action: Action, unit: &str, system: bool) {
+    let result = match action {
+        Action::DaemonReload => systemd::daemon_reload(system),
+        Action::Enable => systemd::enable_unit(unit, system),
+        Action::Disable => systemd::disable_unit(unit, system),
+        Action::Start => systemd::start_unit(unit, system),
+        Action::Stop => systemd::stop_unit(unit, system),
+        Action::Restart => systemd::restart_unit(unit, system),
+    };
+
+    let label = format!("{action} {unit}");
+    match result {
+        Ok(()) => color::print_success(&format!("Ran {}", label.trim_end())),
+        Err(e) => color::print_warning(&format!("Failed to {}: {e}", label.trim_end())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entry(id: u64, path: &str, undone: bool) -> Entry {
+        Entry {
+            id,
+            timestamp: 0,
+            command: format!("edit {path}"),
+            system: false,
+            changes: vec![Change::File {
+                path: PathBuf::from(path),
+                previous: None,
+                removed: false,
+            }],
+            undone,
+        }
+    }
+
+    #[test]
+    fn test_later_changes() {
+        let entries = [
+            entry(1, "/u/app.service", false),
+            entry(2, "/u/other.service", false),
+            entry(3, "/u/app.service", true),
+            entry(4, "/u/app.service", false),
+        ];
+        let ids = |i: usize| -> Vec<u64> {
+            later_changes(&entries, &entries[i])
+                .iter()
+                .map(|(e, _)| e.id)
+                .collect()
+        };
+
+        assert_eq!(ids(0), [4]);
+        assert!(ids(1).is_empty());
+        assert!(ids(3).is_empty());
+    }
+}
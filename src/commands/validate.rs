@@ -1,6 +1,13 @@
 use crate::cli::ValidateArgs;
 use crate::error::{MkunitError, Result};
-use crate::systemd;
+use crate::systemd::calendar::CalendarSpec;
+use crate::systemd::exec::ExecCommand;
+use crate::systemd::quote;
+use crate::systemd::resource::{ByteLimit, CpuQuota, FileLimit, TasksLimit, Weight};
+use crate::systemd::specifier;
+use crate::systemd::timespan::Timespan;
+use crate::systemd::version::{self, SystemdVersion};
+use crate::systemd::{self, escape, UnitType};
 use crate::util::color;
 use std::fs;
 use std::path::Path;
@@ -23,6 +30,17 @@ pub fn run(args: ValidateArgs, _dry_run: bool, _no_interactive: bool) -> Result<
     let mut warnings = Vec::new();
     let mut errors = Vec::new();
 
+    // Specifier checks depend on the unit name and the installed systemd
+    let is_template = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.contains("@."));
+    let version = version::detect_version().ok();
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+
     // Basic syntax checks
     let mut in_section = false;
     let mut current_section = String::new();
@@ -47,10 +65,7 @@ pub fn run(args: ValidateArgs, _dry_run: bool, _no_interactive: bool) -> Result<
                 in_section = true;
 
                 // Check for valid section names
-                let valid_sections = [
-                    "Unit", "Service", "Timer", "Path", "Socket", "Mount", "Target", "Install",
-                ];
-                if !valid_sections.contains(&current_section.as_str()) {
+                if !VALID_SECTIONS.contains(&current_section.as_str()) {
                     warnings.push(format!(
                         "Line {line_num}: Unknown section [{current_section}]"
                     ));
@@ -78,22 +93,19 @@ pub fn run(args: ValidateArgs, _dry_run: bool, _no_interactive: bool) -> Result<
         let key = key.trim();
         let value = value.trim();
 
-        // Check for common issues
-        if key == "ExecStart" || key == "ExecStartPre" || key == "ExecStartPost" {
-            let cmd = value.trim_start_matches(['-', '+', '!', ':', '@'].as_ref());
-            let first_word = cmd.split_whitespace().next().unwrap_or("");
-            if !first_word.starts_with('/') && !first_word.starts_with('$') {
-                warnings.push(format!(
-                    "Line {line_num}: Exec path is not absolute: {first_word}"
-                ));
-            }
-        }
-
-        if key == "WorkingDirectory" && !value.starts_with('/') && !value.starts_with('~') {
-            warnings.push(format!(
-                "Line {line_num}: WorkingDirectory is not absolute: {value}"
-            ));
+        check_value(key, value, line_num, &mut warnings, &mut errors);
+        if let Some(error) = check_path_name(file_name, &current_section, key, value) {
+            errors.push(format!("Line {line_num}: {error}"));
         }
+        check_specifiers(
+            key,
+            value,
+            line_num,
+            is_template,
+            version.as_ref(),
+            &mut warnings,
+            &mut errors,
+        );
     }
 
     // Run systemd-analyze verify if available
@@ -130,3 +142,175 @@ pub fn run(args: ValidateArgs, _dry_run: bool, _no_interactive: bool) -> Result<
 
     Ok(())
 }
+
+/// Sections mkunit knows about
+const VALID_SECTIONS: &[&str] = &[
+    "Unit",
+    "Service",
+    "Timer",
+    "Path",
+    "Socket",
+    "Mount",
+    "Automount",
+    "Swap",
+    "Target",
+    "Slice",
+    "Install",
+];
+
+/// Keys holding a command line
+const EXEC_KEYS: &[&str] = &[
+    "ExecCondition",
+    "ExecStartPre",
+    "ExecStart",
+    "ExecStartPost",
+    "ExecReload",
+    "ExecStop",
+    "ExecStopPost",
+];
+
+/// Resource control settings, which take `%` as a literal percentage
+const RESOURCE_KEYS: &[&str] = &[
+    "CPUQuota",
+    "CPUQuotaPeriodSec",
+    "CPUWeight",
+    "StartupCPUWeight",
+    "CPUShares",
+    "StartupCPUShares",
+    "AllowedCPUs",
+    "StartupAllowedCPUs",
+    "MemoryMin",
+    "MemoryLow",
+    "MemoryHigh",
+    "MemoryMax",
+    "MemorySwapMax",
+    "MemoryZSwapMax",
+    "MemoryLimit",
+    "TasksMax",
+    "IOWeight",
+    "StartupIOWeight",
+    "BlockIOWeight",
+    "ManagedOOMMemoryPressureLimit",
+];
+
+/// Whether systemd resolves specifiers in the value of `key`
+///
+/// Resource limits, rlimits and durations are parsed as numbers, so a `%` in
+/// them is a percentage rather than a specifier.
+fn resolves_specifiers(key: &str) -> bool {
+    !RESOURCE_KEYS.contains(&key)
+        && !key.starts_with("Limit")
+        && !key.ends_with("Sec")
+        && key != "OnCalendar"
+}
+
+/// Check a single key/value pair for common issues
+fn check_value(
+    key: &str,
+    value: &str,
+    line_num: usize,
+    warnings: &mut Vec<String>,
+    errors: &mut Vec<String>,
+) {
+    if EXEC_KEYS.contains(&key) {
+        match ExecCommand::from_line(value) {
+            Ok(command) => {
+                let first_word = command.executable();
+                if !first_word.starts_with('/') && !first_word.starts_with('$') {
+                    warnings.push(format!(
+                        "Line {line_num}: Exec path is not absolute: {first_word}"
+                    ));
+                }
+            }
+            Err(e) => errors.push(format!("Line {line_num}: {key}: {e}")),
+        }
+    }
+
+    if key == "Environment" {
+        if let Err(e) = quote::parse_environment(value) {
+            errors.push(format!("Line {line_num}: {key}: {e}"));
+        }
+    }
+
+    let resource = match key {
+        "MemoryHigh" | "MemoryMax" => Some(value.parse::<ByteLimit>().map(drop)),
+        "CPUQuota" => Some(value.parse::<CpuQuota>().map(drop)),
+        "CPUWeight" | "IOWeight" => Some(value.parse::<Weight>().map(drop)),
+        "TasksMax" => Some(value.parse::<TasksLimit>().map(drop)),
+        "LimitNOFILE" => Some(value.parse::<FileLimit>().map(drop)),
+        _ => None,
+    };
+    if let Some(Err(e)) = resource {
+        errors.push(format!("Line {line_num}: {key}: {e}"));
+    }
+
+    if key == "OnCalendar" && !value.is_empty() {
+        if let Err(e) = CalendarSpec::parse(value) {
+            errors.push(format!("Line {line_num}: {key}: {e}"));
+        }
+    }
+
+    // Durations such as RestartSec=, OnBootSec= and TimeoutIdleSec=
+    if key.ends_with("Sec") && !value.is_empty() {
+        if let Err(e) = value.parse::<Timespan>() {
+            errors.push(format!("Line {line_num}: {key}: {e}"));
+        }
+    }
+
+    if key == "WorkingDirectory" && !value.starts_with('/') && !value.starts_with('~') {
+        warnings.push(format!(
+            "Line {line_num}: WorkingDirectory is not absolute: {value}"
+        ));
+    }
+}
+
+/// Check that a mount, automount or swap unit is named after its path
+///
+/// systemd refuses to load these units when the name doesn't match.
+fn check_path_name(file_name: &str, section: &str, key: &str, value: &str) -> Option<String> {
+    let unit_type = match (section, key) {
+        ("Mount", "Where") => UnitType::Mount,
+        ("Automount", "Where") => UnitType::Automount,
+        ("Swap", "What") => UnitType::Swap,
+        _ => return None,
+    };
+    // Only the file name says which unit this is; `foo.unit.bak` can't be checked
+    if !file_name.ends_with(&format!(".{}", unit_type.extension())) {
+        return None;
+    }
+    // Swap devices given as UUID= or LABEL= are named after their /dev path
+    if !value.starts_with('/') {
+        return None;
+    }
+
+    match escape::path_unit_name(value, unit_type) {
+        Ok(expected) if expected == file_name => None,
+        Ok(expected) => Some(format!(
+            "{key}={value} requires the unit to be named {expected}, not {file_name}"
+        )),
+        Err(e) => Some(format!("{key}: {e}")),
+    }
+}
+
+/// Check the specifiers used in a value
+fn check_specifiers(
+    key: &str,
+    value: &str,
+    line_num: usize,
+    is_template: bool,
+    version: Option<&SystemdVersion>,
+    warnings: &mut Vec<String>,
+    errors: &mut Vec<String>,
+) {
+    if !resolves_specifiers(key) {
+        return;
+    }
+    for issue in specifier::check(value, is_template, version) {
+        let message = format!("Line {line_num}: {issue}");
+        if issue.is_error() {
+            errors.push(message);
+        } else {
+            warnings.push(message);
+        }
+    }
+}
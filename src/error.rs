@@ -32,7 +32,10 @@ pub enum MkunitError {
     },
 
     #[error("Permission denied: {message}")]
-    PermissionDenied { message: String },
+    PermissionDenied {
+        message: String,
+        hint: Option<String>,
+    },
 
     #[error("Invalid argument: {message}")]
     InvalidArgument { message: String },
@@ -110,6 +113,27 @@ impl MkunitError {
     pub fn permission_denied(message: impl Into<String>) -> Self {
         Self::PermissionDenied {
             message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn permission_denied_with_hint(
+        message: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self::PermissionDenied {
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    /// Hint to print after the error message, if any
+    pub fn hint(&self) -> Option<&str> {
+        match self {
+            Self::UnitNotFound { hint, .. } | Self::PermissionDenied { hint, .. } => {
+                hint.as_deref()
+            }
+            _ => None,
         }
     }
 
@@ -0,0 +1,350 @@
+//! Operation journal for mutating commands
+//!
+//! Commands that change unit files or unit state record what they did here so
+//! `mkunit history` can list operations and `mkunit undo` can reverse them.
+//! This is unrelated to the systemd journal.
+
+use crate::error::{MkunitError, Result};
+use crate::util::{color, file};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// File name of the journal inside the state directory
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// A systemctl action performed by an operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    DaemonReload,
+    Enable,
+    Disable,
+    Start,
+    Stop,
+    Restart,
+}
+
+impl Action {
+    /// The action that reverses this one, if any
+    #[must_use]
+    pub fn inverse(self) -> Option<Self> {
+        match self {
+            Self::DaemonReload => None,
+            Self::Enable => Some(Self::Disable),
+            Self::Disable => Some(Self::Enable),
+            Self::Start => Some(Self::Stop),
+            Self::Stop => Some(Self::Start),
+            Self::Restart => Some(Self::Restart),
+        }
+    }
+
+    /// Whether this action takes a unit out of service
+    #[must_use]
+    pub fn is_deactivating(self) -> bool {
+        matches!(self, Self::Disable | Self::Stop)
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DaemonReload => write!(f, "daemon-reload"),
+            Self::Enable => write!(f, "enable"),
+            Self::Disable => write!(f, "disable"),
+            Self::Start => write!(f, "start"),
+            Self::Stop => write!(f, "stop"),
+            Self::Restart => write!(f, "restart"),
+        }
+    }
+}
+
+/// A single change made by an operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    /// A file was written or removed; `previous` is its old content, `None` if it did not exist
+    File {
+        path: PathBuf,
+        previous: Option<String>,
+        #[serde(default, skip_serializing_if = "is_false")]
+        removed: bool,
+    },
+    /// A symlink was created or removed; `previous` is the old link target, if there was one
+    Symlink {
+        path: PathBuf,
+        previous: Option<PathBuf>,
+        #[serde(default, skip_serializing_if = "is_false")]
+        removed: bool,
+    },
+    /// A systemctl action was run
+    Systemctl { action: Action, unit: String },
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File { path, removed, .. } | Self::Symlink { path, removed, .. } if *removed => {
+                write!(f, "removed {}", path.display())
+            }
+            Self::File {
+                path,
+                previous: None,
+                ..
+            } => write!(f, "created {}", path.display()),
+            Self::File { path, .. } => write!(f, "changed {}", path.display()),
+            Self::Symlink { path, .. } => write!(f, "linked {}", path.display()),
+            Self::Systemctl { action, unit } => write!(f, "systemctl {action} {unit}"),
+        }
+    }
+}
+
+/// A recorded operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u64,
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+    pub command: String,
+    pub system: bool,
+    pub changes: Vec<Change>,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+/// Builder for an operation that is appended to the journal once complete
+#[derive(Debug)]
+pub struct Operation {
+    command: String,
+    system: bool,
+    changes: Vec<Change>,
+}
+
+impl Operation {
+    #[must_use]
+    pub fn new(command: impl Into<String>, system: bool) -> Self {
+        Self {
+            command: command.into(),
+            system,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn record_file(&mut self, path: &Path, previous: Option<String>) {
+        self.changes.push(Change::File {
+            path: path.to_path_buf(),
+            previous,
+            removed: false,
+        });
+    }
+
+    pub fn record_symlink(&mut self, path: &Path, previous: Option<PathBuf>) {
+        self.changes.push(Change::Symlink {
+            path: path.to_path_buf(),
+            previous,
+            removed: false,
+        });
+    }
+
+    /// Record a deleted file with the content it had
+    pub fn record_removed_file(&mut self, path: &Path, previous: String) {
+        self.changes.push(Change::File {
+            path: path.to_path_buf(),
+            previous: Some(previous),
+            removed: true,
+        });
+    }
+
+    /// Record a deleted symlink with the target it pointed to
+    pub fn record_removed_symlink(&mut self, path: &Path, previous: PathBuf) {
+        self.changes.push(Change::Symlink {
+            path: path.to_path_buf(),
+            previous: Some(previous),
+            removed: true,
+        });
+    }
+
+    pub fn record_action(&mut self, action: Action, unit: &str) {
+        self.changes.push(Change::Systemctl {
+            action,
+            unit: unit.to_string(),
+        });
+    }
+
+    /// Append the operation to the journal
+    ///
+    /// The changes have already happened at this point, so a journal that can't
+    /// be written only produces a warning.
+    pub fn commit(self) {
+        if self.changes.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.append() {
+            color::print_warning(&format!("Could not record operation in history: {e}"));
+        }
+    }
+
+    fn append(self) -> Result<()> {
+        let path = journal_path()?;
+        let id = load_from(&path)?.last().map_or(1, |e| e.id + 1);
+
+        let entry = Entry {
+            id,
+            timestamp: jiff::Timestamp::now().as_second(),
+            command: self.command,
+            system: self.system,
+            changes: self.changes,
+            undone: false,
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let line = serde_json::to_string(&entry).map_err(|e| MkunitError::Other(e.to_string()))?;
+        let mut journal = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(journal, "{line}")?;
+
+        log::debug!("Recorded operation {id} in {}", path.display());
+        Ok(())
+    }
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Get the XDG state directory for mkunit
+pub fn state_dir() -> Result<PathBuf> {
+    if let Ok(dir) = env::var("XDG_STATE_HOME") {
+        if Path::new(&dir).is_absolute() {
+            return Ok(PathBuf::from(dir).join("mkunit"));
+        }
+    }
+
+    let home = env::var("HOME")
+        .map_err(|_| MkunitError::Other("HOME environment variable not set".to_string()))?;
+
+    Ok(PathBuf::from(home)
+        .join(".local")
+        .join("state")
+        .join("mkunit"))
+}
+
+/// Get the path of the journal file
+pub fn journal_path() -> Result<PathBuf> {
+    Ok(state_dir()?.join(JOURNAL_FILE))
+}
+
+/// Load all journal entries, oldest first
+pub fn load() -> Result<Vec<Entry>> {
+    load_from(&journal_path()?)
+}
+
+fn load_from(path: &Path) -> Result<Vec<Entry>> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| MkunitError::Other(format!("Corrupt journal {}: {e}", path.display())))
+        })
+        .collect()
+}
+
+/// Mark an entry as undone
+pub fn mark_undone(id: u64) -> Result<()> {
+    let path = journal_path()?;
+    let mut entries = load_from(&path)?;
+
+    for entry in entries.iter_mut().filter(|e| e.id == id) {
+        entry.undone = true;
+    }
+
+    let mut content = String::new();
+    for entry in &entries {
+        let line = serde_json::to_string(entry).map_err(|e| MkunitError::Other(e.to_string()))?;
+        content.push_str(&line);
+        content.push('\n');
+    }
+
+    file::atomic_write(&path, content.as_bytes(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_inverse() {
+        assert_eq!(Action::Enable.inverse(), Some(Action::Disable));
+        assert_eq!(Action::Stop.inverse(), Some(Action::Start));
+        assert_eq!(Action::DaemonReload.inverse(), None);
+    }
+
+    #[test]
+    fn test_entry_round_trip() {
+        let entry = Entry {
+            id: 7,
+            timestamp: 0,
+            command: "create myapp.service".to_string(),
+            system: false,
+            changes: vec![
+                Change::File {
+                    path: PathBuf::from("/tmp/myapp.service"),
+                    previous: None,
+                    removed: false,
+                },
+                Change::Systemctl {
+                    action: Action::Enable,
+                    unit: "myapp.service".to_string(),
+                },
+            ],
+            undone: false,
+        };
+
+        let line = serde_json::to_string(&entry).unwrap();
+        assert!(line.contains("\"action\":\"enable\""));
+
+        let parsed: Entry = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.id, 7);
+        assert_eq!(parsed.changes, entry.changes);
+    }
+
+    #[test]
+    fn test_change_display() {
+        let mut op = Operation::new("remove myapp.service", false);
+        op.record_file(Path::new("/u/new.service"), None);
+        op.record_file(Path::new("/u/old.service"), Some("old".to_string()));
+        op.record_removed_file(Path::new("/u/gone.service"), "gone".to_string());
+        op.record_symlink(Path::new("/u/link.service"), None);
+        op.record_removed_symlink(Path::new("/u/unlinked.service"), PathBuf::from("/srv/a"));
+
+        let described: Vec<String> = op.changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            described,
+            [
+                "created /u/new.service",
+                "changed /u/old.service",
+                "removed /u/gone.service",
+                "linked /u/link.service",
+                "removed /u/unlinked.service",
+            ]
+        );
+
+        // Entries written before removals were flagged still load
+        let old = r#"{"kind":"file","path":"/u/a.service","previous":"x"}"#;
+        let change: Change = serde_json::from_str(old).unwrap();
+        assert_eq!(change.to_string(), "changed /u/a.service");
+    }
+}
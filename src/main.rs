@@ -27,6 +27,7 @@
 mod cli;
 mod commands;
 mod error;
+mod journal;
 mod systemd;
 mod templates;
 mod util;
@@ -58,10 +59,7 @@ fn main() {
             util::color::print_error(&e.to_string());
 
             // Print hint if available
-            if let error::MkunitError::UnitNotFound {
-                hint: Some(ref h), ..
-            } = e
-            {
+            if let Some(h) = e.hint() {
                 eprintln!();
                 eprintln!("  Hint: {h}");
             }
@@ -81,15 +79,26 @@ fn run_command(cli: Cli) -> error::Result<()> {
         Commands::Path(args) => commands::path::run(args, dry_run, no_interactive),
         Commands::Socket(args) => commands::socket::run(args, dry_run, no_interactive),
         Commands::Mount(args) => commands::mount::run(args, dry_run, no_interactive),
+        Commands::Automount(args) => commands::automount::run(args, dry_run, no_interactive),
+        Commands::Swap(args) => commands::swap::run(args, dry_run, no_interactive),
         Commands::Target(args) => commands::target::run(args, dry_run, no_interactive),
+        Commands::Slice(args) => commands::slice::run(args, dry_run, no_interactive),
         Commands::Edit(args) => commands::edit::run(args, dry_run, no_interactive),
         Commands::Show(args) => commands::show::run(args, dry_run, no_interactive),
         Commands::Validate(args) => commands::validate::run(args, dry_run, no_interactive),
+        Commands::Escape(args) => commands::escape::run(args, dry_run, no_interactive),
+        Commands::Calendar(args) => commands::calendar::run(args, dry_run, no_interactive),
         Commands::Status(args) => commands::status::run(args, dry_run, no_interactive),
         Commands::Logs(args) => commands::logs::run(args, dry_run, no_interactive),
         Commands::Remove(args) => commands::remove::run(args, dry_run, no_interactive),
         Commands::List(args) => commands::list::run(args, dry_run, no_interactive),
+        Commands::Timers(args) => commands::timers::run(args, dry_run, no_interactive),
+        Commands::At(args) => commands::at::run(args, dry_run, no_interactive),
         Commands::Link(args) => commands::link::run(args, dry_run, no_interactive),
+        Commands::Import(args) => commands::import::run(args, dry_run, no_interactive),
+        Commands::Instance(args) => commands::instance::run(args, dry_run, no_interactive),
+        Commands::History(args) => commands::history::run(args, dry_run, no_interactive),
+        Commands::Undo(args) => commands::undo::run(args, dry_run, no_interactive),
         Commands::Completions(args) => commands::completions::run(args),
     }
 }
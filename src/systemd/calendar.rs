@@ -0,0 +1,693 @@
+//! Calendar expressions for `OnCalendar=`, see systemd.time(7)
+//!
+//! An expression has the form `[WEEKDAYS] [YEAR-]MONTH-DAY [HH:MM[:SS]] [TIMEZONE]`
+//! where each number may be `*`, a list (`1,15`), a range (`1..5`) or a
+//! repetition (`0/15`, `8..18/2`). `~` in place of the last `-` counts days
+//! from the end of the month. Shorthands like `daily` expand to a full
+//! expression. Everything is evaluated here, without `systemd-analyze`.
+
+use crate::error::{MkunitError, Result};
+use jiff::civil::{Date, DateTime};
+use jiff::tz::TimeZone;
+use jiff::Zoned;
+use std::fmt;
+
+/// Last year systemd can schedule, elapse searches stop here
+const MAX_YEAR: i32 = 2199;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const FULL_WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+const ALL_WEEKDAYS: u8 = 0x7f;
+
+/// Shorthands and the expressions they stand for
+const SHORTHANDS: &[(&str, &str)] = &[
+    ("minutely", "*-*-* *:*:00"),
+    ("hourly", "*-*-* *:00:00"),
+    ("daily", "*-*-* 00:00:00"),
+    ("monthly", "*-*-01 00:00:00"),
+    ("weekly", "Mon *-*-* 00:00:00"),
+    ("yearly", "*-01-01 00:00:00"),
+    ("annually", "*-01-01 00:00:00"),
+    ("quarterly", "*-01,04,07,10-01 00:00:00"),
+    ("semiannually", "*-01,07-01 00:00:00"),
+];
+
+/// One element of a comma separated list: a value, range or repetition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Item {
+    start: i32,
+    stop: Option<i32>,
+    repeat: Option<i32>,
+}
+
+impl Item {
+    fn matches(self, value: i32) -> bool {
+        match (self.stop, self.repeat) {
+            (stop, Some(repeat)) => {
+                value >= self.start
+                    && stop.is_none_or(|stop| value <= stop)
+                    && (value - self.start) % repeat == 0
+            }
+            (Some(stop), None) => (self.start..=stop).contains(&value),
+            (None, None) => value == self.start,
+        }
+    }
+
+    /// Match a day counted from the end of the month, where an open
+    /// repetition such as `~07/1` walks towards the last day
+    fn matches_from_end(self, value: i32) -> bool {
+        match (self.stop, self.repeat) {
+            (None, Some(repeat)) => value <= self.start && (self.start - value) % repeat == 0,
+            _ => self.matches(value),
+        }
+    }
+}
+
+/// The values allowed for one date or time field, empty for `*`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Component {
+    items: Vec<Item>,
+}
+
+impl Component {
+    fn wildcard() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    fn matches(&self, value: i32) -> bool {
+        self.items.is_empty() || self.items.iter().any(|item| item.matches(value))
+    }
+
+    fn matches_from_end(&self, value: i32) -> bool {
+        self.items.is_empty() || self.items.iter().any(|item| item.matches_from_end(value))
+    }
+
+    /// Smallest matching value in `from..=max`
+    fn next(&self, from: i32, max: i32) -> Option<i32> {
+        (from..=max).find(|&v| self.matches(v))
+    }
+
+    fn parse(input: &str, field: Field) -> Result<Self> {
+        if input == "*" {
+            return Ok(Self::wildcard());
+        }
+
+        let mut items = Vec::new();
+        for part in input.split(',') {
+            let (range, repeat) = match part.split_once('/') {
+                Some((range, repeat)) => (range, Some(field.parse_repeat(repeat)?)),
+                None => (part, None),
+            };
+
+            let (start, stop) = match range.split_once("..") {
+                _ if range == "*" => {
+                    if repeat.is_none() {
+                        return Ok(Self::wildcard());
+                    }
+                    (field.min, None)
+                }
+                Some((start, stop)) => (field.parse_value(start)?, Some(field.parse_value(stop)?)),
+                None => (field.parse_value(range)?, None),
+            };
+            if stop.is_some_and(|stop| stop < start) {
+                return Err(MkunitError::invalid_argument(format!(
+                    "Range '{range}' in the {} field runs backwards",
+                    field.name
+                )));
+            }
+
+            items.push(Item {
+                start,
+                stop,
+                repeat,
+            });
+        }
+
+        items.sort_unstable();
+        items.dedup();
+        Ok(Self { items })
+    }
+
+    fn fmt_padded(&self, f: &mut fmt::Formatter<'_>, width: usize) -> fmt::Result {
+        if self.items.is_empty() {
+            return f.write_str("*");
+        }
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{:0width$}", item.start)?;
+            if let Some(stop) = item.stop {
+                write!(f, "..{stop:0width$}")?;
+            }
+            if let Some(repeat) = item.repeat {
+                write!(f, "/{repeat}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Limits and name of a date or time field, for parsing and messages
+#[derive(Clone, Copy)]
+struct Field {
+    name: &'static str,
+    min: i32,
+    max: i32,
+}
+
+const YEAR: Field = Field {
+    name: "year",
+    min: 1970,
+    max: MAX_YEAR,
+};
+const MONTH: Field = Field {
+    name: "month",
+    min: 1,
+    max: 12,
+};
+const DAY: Field = Field {
+    name: "day",
+    min: 1,
+    max: 31,
+};
+const HOUR: Field = Field {
+    name: "hour",
+    min: 0,
+    max: 23,
+};
+const MINUTE: Field = Field {
+    name: "minute",
+    min: 0,
+    max: 59,
+};
+const SECOND: Field = Field {
+    name: "second",
+    min: 0,
+    max: 59,
+};
+
+impl Field {
+    fn parse_value(self, input: &str) -> Result<i32> {
+        if input.contains('.') && self.name == "second" {
+            return Err(MkunitError::invalid_argument(
+                "Fractional seconds are not supported in calendar expressions",
+            ));
+        }
+        let invalid = || MkunitError::invalid_argument(format!("Invalid {} '{input}'", self.name));
+        if input.is_empty() || !input.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let mut value: i32 = input.parse().map_err(|_| invalid())?;
+
+        // Two digit years, as in 23-01-01
+        if self.name == "year" && input.len() <= 2 {
+            value += if value < 70 { 2000 } else { 1900 };
+        }
+
+        if !(self.min..=self.max).contains(&value) {
+            return Err(MkunitError::invalid_argument(format!(
+                "{} {value} is out of range ({}-{})",
+                capitalize(self.name),
+                self.min,
+                self.max
+            )));
+        }
+        Ok(value)
+    }
+
+    fn parse_repeat(self, input: &str) -> Result<i32> {
+        match input.parse::<i32>() {
+            Ok(repeat) if repeat > 0 && repeat <= self.max => Ok(repeat),
+            _ => Err(MkunitError::invalid_argument(format!(
+                "Invalid repetition '/{input}' in the {} field",
+                self.name
+            ))),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+/// A parsed calendar expression
+#[derive(Debug, Clone)]
+pub struct CalendarSpec {
+    /// Bit 0 is Monday
+    weekdays: u8,
+    year: Component,
+    month: Component,
+    day: Component,
+    /// Days count back from the end of the month (`~`)
+    end_of_month: bool,
+    hour: Component,
+    minute: Component,
+    second: Component,
+    /// Time zone name as written, and the zone it refers to
+    time_zone: Option<(String, TimeZone)>,
+}
+
+impl CalendarSpec {
+    /// Parse a calendar expression
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut tokens: Vec<&str> = input.split_whitespace().collect();
+        if tokens.is_empty() {
+            return Err(MkunitError::invalid_argument("Empty calendar expression"));
+        }
+
+        let time_zone = match tokens.last() {
+            Some(last) if tokens.len() > 1 && is_time_zone_token(last) => {
+                let name = (*last).to_string();
+                tokens.pop();
+                let zone = if name == "UTC" {
+                    TimeZone::UTC
+                } else {
+                    TimeZone::get(&name).map_err(|_| {
+                        MkunitError::invalid_argument(format!("Unknown time zone '{name}'"))
+                    })?
+                };
+                Some((name, zone))
+            }
+            _ => None,
+        };
+
+        if let [word] = tokens.as_slice() {
+            if let Some((_, expansion)) = SHORTHANDS.iter().find(|(s, _)| s == word) {
+                tokens = expansion.split_whitespace().collect();
+            }
+        }
+
+        let mut spec = Self {
+            weekdays: ALL_WEEKDAYS,
+            year: Component::wildcard(),
+            month: Component::wildcard(),
+            day: Component::wildcard(),
+            end_of_month: false,
+            hour: Component::wildcard(),
+            minute: Component::wildcard(),
+            second: Component::wildcard(),
+            time_zone,
+        };
+        spec.parse_tokens(&tokens, input)?;
+        Ok(spec)
+    }
+
+    fn parse_tokens(&mut self, tokens: &[&str], input: &str) -> Result<()> {
+        let mut rest = tokens;
+
+        if let Some(first) = rest.first().filter(|t| t.starts_with(char::is_alphabetic)) {
+            self.weekdays = parse_weekdays(first)?;
+            rest = &rest[1..];
+        }
+
+        let mut has_time = false;
+        if let Some(date) = rest.first().filter(|t| !t.contains(':')) {
+            self.parse_date(date)?;
+            rest = &rest[1..];
+        }
+        if let Some(time) = rest.first().filter(|t| t.contains(':')) {
+            self.parse_time(time)?;
+            has_time = true;
+            rest = &rest[1..];
+        }
+        if !rest.is_empty() {
+            return Err(MkunitError::invalid_argument(format!(
+                "Unexpected '{}' in calendar expression '{input}'",
+                rest[0]
+            )));
+        }
+
+        // A missing time means midnight
+        if !has_time {
+            self.hour = Component::parse("00", HOUR)?;
+            self.minute = Component::parse("00", MINUTE)?;
+            self.second = Component::parse("00", SECOND)?;
+        }
+        Ok(())
+    }
+
+    fn parse_date(&mut self, token: &str) -> Result<()> {
+        let (front, day) = if let Some((front, day)) = token.rsplit_once('~') {
+            self.end_of_month = true;
+            (front, day)
+        } else {
+            token
+                .rsplit_once('-')
+                .ok_or_else(|| MkunitError::invalid_argument(format!("Invalid date '{token}'")))?
+        };
+
+        let (year, month) = match front.split_once('-') {
+            Some((year, month)) => (Some(year), month),
+            None => (None, front),
+        };
+        if let Some(year) = year {
+            self.year = Component::parse(year, YEAR)?;
+        }
+        self.month = Component::parse(month, MONTH)?;
+        self.day = Component::parse(day, DAY)?;
+        Ok(())
+    }
+
+    fn parse_time(&mut self, token: &str) -> Result<()> {
+        let parts: Vec<&str> = token.split(':').collect();
+        let (hour, minute, second) = match parts.as_slice() {
+            [hour, minute] => (*hour, *minute, "00"),
+            [hour, minute, second] => (*hour, *minute, *second),
+            _ => {
+                return Err(MkunitError::invalid_argument(format!(
+                    "Invalid time '{token}', expected HH:MM or HH:MM:SS"
+                )));
+            }
+        };
+        self.hour = Component::parse(hour, HOUR)?;
+        self.minute = Component::parse(minute, MINUTE)?;
+        self.second = Component::parse(second, SECOND)?;
+        Ok(())
+    }
+
+    fn day_matches(&self, date: Date) -> bool {
+        let day = i32::from(date.day());
+        let day_matches = if self.end_of_month {
+            self.day
+                .matches_from_end(i32::from(date.days_in_month()) + 1 - day)
+        } else {
+            self.day.matches(day)
+        };
+        let weekday = date.weekday().to_monday_zero_offset();
+        day_matches && self.weekdays & (1 << weekday) != 0
+    }
+
+    /// First matching wall clock time strictly after `after`
+    fn next_after(&self, after: DateTime) -> Option<DateTime> {
+        let (mut y, mut mo, mut d) = (
+            i32::from(after.year()),
+            i32::from(after.month()),
+            i32::from(after.day()),
+        );
+        let (mut h, mut mi, mut s) = (
+            i32::from(after.hour()),
+            i32::from(after.minute()),
+            i32::from(after.second()) + 1,
+        );
+
+        // Values past the end of a field (second 60, day 32, ...) fail the
+        // search for that field and carry into the next larger one
+        loop {
+            let ny = self.year.next(y, MAX_YEAR)?;
+            if ny != y {
+                (y, mo, d, h, mi, s) = (ny, 1, 1, 0, 0, 0);
+            }
+            let Some(nmo) = self.month.next(mo, 12) else {
+                (y, mo, d, h, mi, s) = (y + 1, 1, 1, 0, 0, 0);
+                continue;
+            };
+            if nmo != mo {
+                (mo, d, h, mi, s) = (nmo, 1, 0, 0, 0);
+            }
+            let first = Date::new(i16::try_from(y).ok()?, i8::try_from(mo).ok()?, 1).ok()?;
+            let days = i32::from(first.days_in_month());
+            let Some(nd) = (d..=days).find(|&day| {
+                first
+                    .with()
+                    .day(i8::try_from(day).unwrap_or(1))
+                    .build()
+                    .is_ok_and(|date| self.day_matches(date))
+            }) else {
+                (mo, d, h, mi, s) = (mo + 1, 1, 0, 0, 0);
+                continue;
+            };
+            if nd != d {
+                (d, h, mi, s) = (nd, 0, 0, 0);
+            }
+            let Some(nh) = self.hour.next(h, 23) else {
+                (d, h, mi, s) = (d + 1, 0, 0, 0);
+                continue;
+            };
+            if nh != h {
+                (h, mi, s) = (nh, 0, 0);
+            }
+            let Some(nmi) = self.minute.next(mi, 59) else {
+                (h, mi, s) = (h + 1, 0, 0);
+                continue;
+            };
+            if nmi != mi {
+                (mi, s) = (nmi, 0);
+            }
+            let Some(ns) = self.second.next(s, 59) else {
+                (mi, s) = (mi + 1, 0);
+                continue;
+            };
+
+            return DateTime::new(
+                i16::try_from(y).ok()?,
+                i8::try_from(mo).ok()?,
+                i8::try_from(d).ok()?,
+                i8::try_from(h).ok()?,
+                i8::try_from(mi).ok()?,
+                i8::try_from(ns).ok()?,
+                0,
+            )
+            .ok();
+        }
+    }
+
+    /// The next `count` times the expression elapses after `after`
+    ///
+    /// Times are evaluated in the expression's time zone, or in the zone of
+    /// `after` if it has none. An empty result means it never elapses again.
+    #[must_use]
+    pub fn next_elapses(&self, after: &Zoned, count: usize) -> Vec<Zoned> {
+        let tz = self
+            .time_zone
+            .as_ref()
+            .map_or_else(|| after.time_zone().clone(), |(_, tz)| tz.clone());
+        let mut local = after.with_time_zone(tz.clone()).datetime();
+        let mut elapses: Vec<Zoned> = Vec::new();
+
+        while elapses.len() < count {
+            let Some(next) = self.next_after(local) else {
+                break;
+            };
+            local = next;
+            // Times skipped by a DST change run right after the gap
+            let Ok(zoned) = tz.to_ambiguous_zoned(next).compatible() else {
+                break;
+            };
+            let later = elapses.last().map_or(after.timestamp(), Zoned::timestamp);
+            if zoned.timestamp() > later {
+                elapses.push(zoned);
+            }
+        }
+        elapses
+    }
+}
+
+impl fmt::Display for CalendarSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weekdays != ALL_WEEKDAYS {
+            write!(f, "{} ", format_weekdays(self.weekdays))?;
+        }
+        self.year.fmt_padded(f, 4)?;
+        f.write_str("-")?;
+        self.month.fmt_padded(f, 2)?;
+        f.write_str(if self.end_of_month { "~" } else { "-" })?;
+        self.day.fmt_padded(f, 2)?;
+        f.write_str(" ")?;
+        self.hour.fmt_padded(f, 2)?;
+        f.write_str(":")?;
+        self.minute.fmt_padded(f, 2)?;
+        f.write_str(":")?;
+        self.second.fmt_padded(f, 2)?;
+        if let Some((name, _)) = &self.time_zone {
+            write!(f, " {name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether the last word of an expression names a time zone
+fn is_time_zone_token(token: &str) -> bool {
+    token.starts_with(char::is_alphabetic)
+        && parse_weekdays(token).is_err()
+        && !SHORTHANDS.iter().any(|(s, _)| *s == token)
+}
+
+fn weekday_index(name: &str) -> Option<usize> {
+    WEEKDAYS
+        .iter()
+        .position(|d| d.eq_ignore_ascii_case(name))
+        .or_else(|| {
+            FULL_WEEKDAYS
+                .iter()
+                .position(|d| d.eq_ignore_ascii_case(name))
+        })
+}
+
+/// Parse `Mon..Fri`, `Sat,Sun` and mixes of them into a bit set
+fn parse_weekdays(input: &str) -> Result<u8> {
+    let unknown = |name: &str| {
+        MkunitError::invalid_argument(format!("Unknown weekday '{name}' in '{input}'"))
+    };
+
+    let mut bits = 0u8;
+    for part in input.split(',') {
+        let (first, last) = part
+            .split_once("..")
+            .or_else(|| part.split_once('-'))
+            .unwrap_or((part, part));
+        let first = weekday_index(first).ok_or_else(|| unknown(first))?;
+        let last = weekday_index(last).ok_or_else(|| unknown(last))?;
+        if last < first {
+            return Err(MkunitError::invalid_argument(format!(
+                "Weekday range '{part}' runs backwards"
+            )));
+        }
+        for day in first..=last {
+            bits |= 1 << day;
+        }
+    }
+    Ok(bits)
+}
+
+/// Format a weekday bit set the way systemd does: runs of three or more
+/// become ranges
+fn format_weekdays(bits: u8) -> String {
+    let mut parts = Vec::new();
+    let mut day = 0;
+    while day < 7 {
+        if bits & (1 << day) == 0 {
+            day += 1;
+            continue;
+        }
+        let start = day;
+        while day + 1 < 7 && bits & (1 << (day + 1)) != 0 {
+            day += 1;
+        }
+        match day - start {
+            0 => parts.push(WEEKDAYS[start].to_string()),
+            1 => parts.push(format!("{},{}", WEEKDAYS[start], WEEKDAYS[day])),
+            _ => parts.push(format!("{}..{}", WEEKDAYS[start], WEEKDAYS[day])),
+        }
+        day += 1;
+    }
+    parts.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn normalize(input: &str) -> String {
+        CalendarSpec::parse(input).unwrap().to_string()
+    }
+
+    fn elapses(input: &str, after: &str, count: usize) -> Vec<String> {
+        let after: Zoned = after.parse().unwrap();
+        CalendarSpec::parse(input)
+            .unwrap()
+            .next_elapses(&after, count)
+            .iter()
+            .map(|z| z.strftime("%a %Y-%m-%d %H:%M:%S %Z").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("daily"), "*-*-* 00:00:00");
+        assert_eq!(normalize("weekly"), "Mon *-*-* 00:00:00");
+        assert_eq!(normalize("quarterly"), "*-01,04,07,10-01 00:00:00");
+        assert_eq!(normalize("*:0/15"), "*-*-* *:00/15:00");
+        assert_eq!(normalize("mon..fri 9:30"), "Mon..Fri *-*-* 09:30:00");
+        assert_eq!(normalize("Sat,Sunday 10:00"), "Sat,Sun *-*-* 10:00:00");
+        assert_eq!(
+            normalize("Mon,Tue,Wed,Fri 1:2:3"),
+            "Mon..Wed,Fri *-*-* 01:02:03"
+        );
+        assert_eq!(normalize("*-02~03"), "*-02~03 00:00:00");
+        assert_eq!(normalize("2026-10-18 8:00"), "2026-10-18 08:00:00");
+        assert_eq!(normalize("26-1-1"), "2026-01-01 00:00:00");
+        assert_eq!(normalize("*-*-* 8..18/2:00"), "*-*-* 08..18/2:00:00");
+        assert_eq!(normalize("daily UTC"), "*-*-* 00:00:00 UTC");
+        assert_eq!(normalize("*-*-* 5,1,5:00"), "*-*-* 01,05:00:00");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(CalendarSpec::parse("").is_err());
+        assert!(CalendarSpec::parse("dialy").is_err());
+        assert!(CalendarSpec::parse("Fri..Mon").is_err());
+        assert!(CalendarSpec::parse("*-13-01").is_err());
+        assert!(CalendarSpec::parse("*-*-* 24:00").is_err());
+        assert!(CalendarSpec::parse("*-*-* 10..08:00").is_err());
+        assert!(CalendarSpec::parse("*:*/0").is_err());
+        assert!(CalendarSpec::parse("daily Mars/Olympus").is_err());
+        assert!(CalendarSpec::parse("*-*-* 12:00 13:00").is_err());
+    }
+
+    #[test]
+    fn test_next_elapses() {
+        assert_eq!(
+            elapses("daily", "2026-10-18T10:30:00[UTC]", 2),
+            ["Mon 2026-10-19 00:00:00 UTC", "Tue 2026-10-20 00:00:00 UTC"]
+        );
+        assert_eq!(
+            elapses("Mon..Fri 09:00", "2026-10-16T09:00:00[UTC]", 2),
+            ["Mon 2026-10-19 09:00:00 UTC", "Tue 2026-10-20 09:00:00 UTC"]
+        );
+        assert_eq!(
+            elapses("*:0/20", "2026-12-31T23:30:00[UTC]", 2),
+            ["Thu 2026-12-31 23:40:00 UTC", "Fri 2027-01-01 00:00:00 UTC"]
+        );
+        // Last Monday of May, and the last day of February in a leap year
+        assert_eq!(
+            elapses("Mon *-05~07/1", "2026-01-01T00:00:00[UTC]", 1),
+            ["Mon 2026-05-25 00:00:00 UTC"]
+        );
+        assert_eq!(
+            elapses("*-02~01", "2028-01-01T00:00:00[UTC]", 1),
+            ["Tue 2028-02-29 00:00:00 UTC"]
+        );
+        assert!(elapses("*-02-30", "2026-01-01T00:00:00[UTC]", 1).is_empty());
+    }
+
+    #[test]
+    fn test_next_elapses_time_zone() {
+        // 09:00 in Tokyo is midnight UTC
+        let after: Zoned = "2026-10-18T10:00:00[UTC]".parse().unwrap();
+        let next = CalendarSpec::parse("*-*-* 09:00 Asia/Tokyo")
+            .unwrap()
+            .next_elapses(&after, 1);
+        assert_eq!(
+            next[0].with_time_zone(TimeZone::UTC).to_string(),
+            "2026-10-19T00:00:00+00:00[UTC]"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn normalized_form_is_stable(
+            hour in 0..24i32,
+            step in 1..30i32,
+            days in 1..0x7fu8,
+            day in 1..29i32,
+        ) {
+            let input = format!("{} *-*-{day} {hour}:00/{step}", format_weekdays(days));
+            let normalized = normalize(&input);
+            prop_assert_eq!(normalize(&normalized), normalized);
+        }
+    }
+}
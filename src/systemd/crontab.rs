@@ -0,0 +1,527 @@
+//! Crontab parsing and translation of cron schedules, see crontab(5)
+//!
+//! A crontab holds environment assignments (`NAME=value`) and jobs. A job is
+//! five time fields (minute, hour, day of month, month, day of week) or an
+//! `@` alias, then the command. System crontabs such as `/etc/crontab` have
+//! an extra user column before the command. Assignments apply to the jobs
+//! that follow them.
+
+use crate::error::{MkunitError, Result};
+use crate::systemd::calendar::CalendarSpec;
+use std::fmt::Write;
+
+/// Aliases and the time fields they stand for
+const ALIASES: &[(&str, &str)] = &[
+    ("@yearly", "0 0 1 1 *"),
+    ("@annually", "0 0 1 1 *"),
+    ("@monthly", "0 0 1 * *"),
+    ("@weekly", "0 0 * * 0"),
+    ("@daily", "0 0 * * *"),
+    ("@midnight", "0 0 * * *"),
+    ("@hourly", "0 * * * *"),
+];
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+/// Cron weekday names, where 0 (and 7) is Sunday
+const CRON_WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+/// systemd weekday names, indexed like cron
+const WEEKDAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// When a job runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// `@reboot`: once when cron starts
+    Reboot,
+    /// Five time fields, with aliases already expanded
+    Fields {
+        minute: String,
+        hour: String,
+        day: String,
+        month: String,
+        weekday: String,
+    },
+}
+
+/// A single crontab job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronJob {
+    /// Line number in the file, for messages
+    pub line: usize,
+    pub schedule: Schedule,
+    /// User column of a system crontab
+    pub user: Option<String>,
+    /// Command as written, including any `%` stdin markers
+    pub command: String,
+    /// Environment assignments in effect for this job, in order
+    pub env: Vec<(String, String)>,
+}
+
+impl CronJob {
+    /// Value of an environment assignment in effect for this job
+    #[must_use]
+    pub fn env_value(&self, name: &str) -> Option<&str> {
+        self.env
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse the contents of a crontab
+///
+/// `system` selects the system crontab format with a user column.
+pub fn parse(content: &str, system: bool) -> Result<Vec<CronJob>> {
+    let mut jobs = Vec::new();
+    let mut env: Vec<(String, String)> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line_num = index + 1;
+        let trimmed = line.trim_start();
+        if trimmed.trim_end().is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some((key, value)) = parse_assignment(trimmed) {
+            env.push((key, value));
+            continue;
+        }
+
+        let invalid =
+            |reason: &str| MkunitError::invalid_argument(format!("Line {line_num}: {reason}"));
+
+        let (schedule, rest) = if trimmed.starts_with('@') {
+            let (alias, rest) = split_field(trimmed);
+            let schedule = if alias == "@reboot" {
+                Schedule::Reboot
+            } else {
+                let (_, fields) = ALIASES
+                    .iter()
+                    .find(|(name, _)| *name == alias)
+                    .ok_or_else(|| invalid(&format!("unknown alias '{alias}'")))?;
+                fields_schedule(fields).ok_or_else(|| invalid("invalid alias"))?
+            };
+            (schedule, rest)
+        } else {
+            let mut rest = trimmed;
+            let mut fields = Vec::with_capacity(5);
+            for _ in 0..5 {
+                let (field, after) = split_field(rest);
+                if field.is_empty() {
+                    return Err(invalid("expected five time fields and a command"));
+                }
+                fields.push(field);
+                rest = after;
+            }
+            let schedule = fields_schedule(&fields.join(" "))
+                .ok_or_else(|| invalid("expected five time fields and a command"))?;
+            (schedule, rest)
+        };
+
+        let (user, command) = if system {
+            let (user, command) = split_field(rest);
+            (Some(user.to_string()), command)
+        } else {
+            (None, rest)
+        };
+        let command = command.trim();
+        if command.is_empty() {
+            return Err(invalid("missing command"));
+        }
+
+        jobs.push(CronJob {
+            line: line_num,
+            schedule,
+            user,
+            command: command.to_string(),
+            env: env.clone(),
+        });
+    }
+
+    Ok(jobs)
+}
+
+/// Split off the first whitespace separated field
+fn split_field(input: &str) -> (&str, &str) {
+    let input = input.trim_start();
+    let end = input.find(char::is_whitespace).unwrap_or(input.len());
+    let (field, rest) = input.split_at(end);
+    (field, rest.trim_start())
+}
+
+fn fields_schedule(fields: &str) -> Option<Schedule> {
+    let mut parts = fields.split_whitespace().map(String::from);
+    Some(Schedule::Fields {
+        minute: parts.next()?,
+        hour: parts.next()?,
+        day: parts.next()?,
+        month: parts.next()?,
+        weekday: parts.next()?,
+    })
+}
+
+/// Parse `NAME = value`, with optional matching quotes around the value
+fn parse_assignment(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.split_once('=')?;
+    let key = key.trim();
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    // A job's first field can't contain '=' before any whitespace, but a
+    // command can; only treat it as an assignment if the name is plain
+    if !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    let value = value.trim();
+    let value = ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+        .unwrap_or(value);
+    Some((key.to_string(), value.to_string()))
+}
+
+/// Split a cron command at the first unescaped `%`
+///
+/// Returns the command with `\%` unescaped, and the text after the `%`
+/// that cron would feed to the command on standard input.
+#[must_use]
+pub fn split_command(command: &str) -> (String, Option<String>) {
+    let mut out = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.as_str().starts_with('%') => {
+                out.push('%');
+                chars.next();
+            }
+            '%' => return (out, Some(chars.as_str().to_string())),
+            _ => out.push(c),
+        }
+    }
+    (out, None)
+}
+
+/// A cron time field and its allowed values
+struct FieldRange {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    /// Names numbered from `min`, so `jan` is 1 and `sun` is 0
+    names: &'static [&'static str],
+}
+
+const MINUTE: FieldRange = FieldRange {
+    name: "minute",
+    min: 0,
+    max: 59,
+    names: &[],
+};
+const HOUR: FieldRange = FieldRange {
+    name: "hour",
+    min: 0,
+    max: 23,
+    names: &[],
+};
+const DAY: FieldRange = FieldRange {
+    name: "day of month",
+    min: 1,
+    max: 31,
+    names: &[],
+};
+const MONTH: FieldRange = FieldRange {
+    name: "month",
+    min: 1,
+    max: 12,
+    names: &MONTHS,
+};
+const WEEKDAY: FieldRange = FieldRange {
+    name: "day of week",
+    min: 0,
+    max: 7,
+    names: &CRON_WEEKDAYS,
+};
+
+/// One element of a comma separated field: a value, range or repetition
+struct CronItem {
+    start: u32,
+    stop: u32,
+    repeat: Option<u32>,
+    /// Whether `stop` was written or implied by `*` or `a/n`
+    open: bool,
+}
+
+impl FieldRange {
+    fn value(&self, text: &str) -> Result<u32> {
+        let lower = text.to_ascii_lowercase();
+        let named = self
+            .names
+            .iter()
+            .position(|n| *n == lower)
+            .and_then(|i| u32::try_from(i).ok())
+            .map(|i| i + self.min);
+        let value = match named {
+            Some(v) => v,
+            None => text.parse().map_err(|_| {
+                MkunitError::invalid_argument(format!("Invalid {} '{text}'", self.name))
+            })?,
+        };
+        if value < self.min || value > self.max {
+            return Err(MkunitError::invalid_argument(format!(
+                "{} {value} is out of range {}-{}",
+                self.name, self.min, self.max
+            )));
+        }
+        Ok(value)
+    }
+
+    fn items(&self, field: &str) -> Result<Vec<CronItem>> {
+        let invalid = |what: &str, text: &str| {
+            MkunitError::invalid_argument(format!(
+                "Invalid {what} '{text}' in {} '{field}'",
+                self.name
+            ))
+        };
+
+        field
+            .split(',')
+            .map(|item| {
+                let (range, repeat) = match item.split_once('/') {
+                    Some((range, repeat)) => {
+                        let value = repeat
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|r| *r > 0)
+                            .ok_or_else(|| invalid("step", repeat))?;
+                        (range, Some(value))
+                    }
+                    None => (item, None),
+                };
+
+                let (start, stop, open) = if range == "*" {
+                    (self.min, self.max, true)
+                } else if let Some((start, stop)) = range.split_once('-') {
+                    (self.value(start)?, self.value(stop)?, false)
+                } else {
+                    // `a/n` runs from a to the maximum
+                    let value = self.value(range)?;
+                    let open = repeat.is_some();
+                    (value, if open { self.max } else { value }, open)
+                };
+                if stop < start {
+                    return Err(invalid("range", range));
+                }
+
+                Ok(CronItem {
+                    start,
+                    stop,
+                    repeat,
+                    open,
+                })
+            })
+            .collect()
+    }
+
+    /// Translate a field into systemd syntax: lists stay lists, `a-b`
+    /// becomes `a..b` and `*/n` starts the repetition at the minimum
+    fn translate(&self, field: &str) -> Result<String> {
+        if field == "*" {
+            return Ok("*".to_string());
+        }
+
+        let mut out = Vec::new();
+        for item in self.items(field)? {
+            let mut text = format!("{:02}", item.start);
+            if item.stop != item.start && !(item.open && item.repeat.is_some()) {
+                let _ = write!(text, "..{:02}", item.stop);
+            }
+            if let Some(repeat) = item.repeat {
+                let _ = write!(text, "/{repeat}");
+            }
+            out.push(text);
+        }
+        Ok(out.join(","))
+    }
+
+    /// Expand a weekday field into the set of days, since systemd has no
+    /// repetition for weekdays
+    fn expand(&self, field: &str) -> Result<[bool; 7]> {
+        let mut days = [false; 7];
+        for item in self.items(field)? {
+            let repeat = item.repeat.unwrap_or(1) as usize;
+            for day in (item.start..=item.stop).step_by(repeat) {
+                days[day as usize % 7] = true;
+            }
+        }
+        Ok(days)
+    }
+}
+
+/// Render a set of weekdays, joining runs of three or more into ranges
+fn weekday_list(days: [bool; 7]) -> String {
+    // systemd weeks start on Monday
+    let order = [1, 2, 3, 4, 5, 6, 0];
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < order.len() {
+        if !days[order[i]] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i + 1 < order.len() && days[order[i + 1]] {
+            i += 1;
+        }
+        match i - start {
+            0 => parts.push(WEEKDAY_NAMES[order[start]].to_string()),
+            1 => parts.push(format!(
+                "{},{}",
+                WEEKDAY_NAMES[order[start]], WEEKDAY_NAMES[order[i]]
+            )),
+            _ => parts.push(format!(
+                "{}..{}",
+                WEEKDAY_NAMES[order[start]], WEEKDAY_NAMES[order[i]]
+            )),
+        }
+        i += 1;
+    }
+    parts.join(",")
+}
+
+/// Translate cron time fields into a normalized `OnCalendar=` expression
+///
+/// Fails when the schedule can't be expressed exactly. cron runs a job when
+/// either the day of month or the day of week matches if both are
+/// restricted, while systemd requires both to match.
+pub fn to_calendar(
+    minute: &str,
+    hour: &str,
+    day: &str,
+    month: &str,
+    weekday: &str,
+    time_zone: Option<&str>,
+) -> Result<String> {
+    if !day.starts_with('*') && !weekday.starts_with('*') {
+        return Err(MkunitError::invalid_argument(format!(
+            "cron runs this job when either the day of month ({day}) or the day of week \
+             ({weekday}) matches, which OnCalendar= can't express"
+        )));
+    }
+
+    let days = WEEKDAY.expand(weekday)?;
+    let weekdays = if days.iter().all(|d| *d) {
+        String::new()
+    } else {
+        format!("{} ", weekday_list(days))
+    };
+
+    let mut expr = format!(
+        "{weekdays}*-{}-{} {}:{}:00",
+        MONTH.translate(month)?,
+        DAY.translate(day)?,
+        HOUR.translate(hour)?,
+        MINUTE.translate(minute)?,
+    );
+    if let Some(tz) = time_zone {
+        expr.push(' ');
+        expr.push_str(tz);
+    }
+
+    Ok(CalendarSpec::parse(&expr)?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(fields: &str) -> String {
+        let f: Vec<&str> = fields.split_whitespace().collect();
+        to_calendar(f[0], f[1], f[2], f[3], f[4], None).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let content = "\
+# m h dom mon dow command
+MAILTO=admin@example.com
+PATH = \"/usr/local/bin:/usr/bin\"
+*/15 * * * * /usr/bin/check --quiet
+@reboot /usr/bin/warmup
+SHELL=/bin/bash
+@weekly backup.sh > /dev/null 2>&1
+";
+        let jobs = parse(content, false).unwrap();
+        assert_eq!(jobs.len(), 3);
+
+        assert_eq!(jobs[0].line, 4);
+        assert_eq!(jobs[0].command, "/usr/bin/check --quiet");
+        assert_eq!(jobs[0].env_value("PATH"), Some("/usr/local/bin:/usr/bin"));
+        assert_eq!(jobs[0].env_value("SHELL"), None);
+
+        assert_eq!(jobs[1].schedule, Schedule::Reboot);
+
+        let Schedule::Fields { weekday, .. } = &jobs[2].schedule else {
+            panic!("expected time fields");
+        };
+        assert_eq!(weekday, "0");
+        assert_eq!(jobs[2].env_value("SHELL"), Some("/bin/bash"));
+        assert_eq!(jobs[2].command, "backup.sh > /dev/null 2>&1");
+    }
+
+    #[test]
+    fn test_parse_system() {
+        let jobs = parse("17 * * * * root cd / && run-parts /etc/cron.hourly\n", true).unwrap();
+        assert_eq!(jobs[0].user.as_deref(), Some("root"));
+        assert_eq!(jobs[0].command, "cd / && run-parts /etc/cron.hourly");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("* * * *\n", false).is_err());
+        assert!(parse("@fortnightly /bin/true\n", false).is_err());
+        assert!(parse("0 0 * * * root\n", true).is_err());
+    }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command("date +\\%Y-\\%m-\\%d"),
+            ("date +%Y-%m-%d".to_string(), None)
+        );
+        assert_eq!(
+            split_command("mail -s hi root%body%more"),
+            ("mail -s hi root".to_string(), Some("body%more".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_to_calendar() {
+        assert_eq!(calendar("*/15 * * * *"), "*-*-* *:00/15:00");
+        assert_eq!(calendar("0 4 * * *"), "*-*-* 04:00:00");
+        assert_eq!(
+            calendar("30 8-18/2 * * 1-5"),
+            "Mon..Fri *-*-* 08..18/2:30:00"
+        );
+        assert_eq!(calendar("0 0 * * 0"), "Sun *-*-* 00:00:00");
+        assert_eq!(calendar("0 0 * * 6,7"), "Sat,Sun *-*-* 00:00:00");
+        assert_eq!(calendar("0 0 1,15 jan-mar *"), "*-01..03-01,15 00:00:00");
+        assert_eq!(calendar("5 0 * * */2"), "Tue,Thu,Sat,Sun *-*-* 00:05:00");
+        assert_eq!(calendar("0 12 */2 * 1"), "Mon *-*-01/2 12:00:00");
+        assert_eq!(
+            to_calendar("0", "9", "*", "*", "*", Some("UTC")).unwrap(),
+            "*-*-* 09:00:00 UTC"
+        );
+    }
+
+    #[test]
+    fn test_to_calendar_errors() {
+        // Day of month OR day of week
+        assert!(to_calendar("0", "0", "1", "*", "1", None).is_err());
+        assert!(to_calendar("60", "*", "*", "*", "*", None).is_err());
+        assert!(to_calendar("0", "5-1", "*", "*", "*", None).is_err());
+        assert!(to_calendar("*/0", "*", "*", "*", "*", None).is_err());
+        assert!(to_calendar("0", "0", "*", "foo", "*", None).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! Unit name escaping, as done by systemd-escape(1)
+//!
+//! Mount, automount and swap units must be named after the path they manage,
+//! escaped so that `/` becomes `-` and any byte that isn't allowed in a unit
+//! name becomes `\xNN`. `/mnt/my data` is managed by `mnt-my\x20data.mount`.
+
+use crate::error::{MkunitError, Result};
+use crate::systemd::UnitType;
+use std::fmt::Write;
+
+/// Characters that are kept as they are; everything else except `/` is hex-escaped
+fn is_plain(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b':' | b'_' | b'.')
+}
+
+fn push_hex(out: &mut String, b: u8) {
+    let _ = write!(out, "\\x{b:02x}");
+}
+
+/// Escape a string for use in a unit name (`systemd-escape`)
+#[must_use]
+pub fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut bytes = value.bytes();
+
+    // A leading dot would make a hidden file, as for a "/.snapshots" mount point
+    if value.starts_with('.') {
+        push_hex(&mut out, b'.');
+        bytes.next();
+    }
+
+    for b in bytes {
+        match b {
+            b'/' => out.push('-'),
+            b if is_plain(b) => out.push(char::from(b)),
+            b => push_hex(&mut out, b),
+        }
+    }
+
+    out
+}
+
+/// Escape a file system path for use in a unit name (`systemd-escape --path`)
+///
+/// Duplicate slashes and `.` components are dropped; `..` is rejected because
+/// it would make two different names refer to the same path.
+pub fn escape_path(path: &str) -> Result<String> {
+    let mut components = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                return Err(MkunitError::invalid_argument(format!(
+                    "Path '{path}' must not contain '..'"
+                )));
+            }
+            c => components.push(c),
+        }
+    }
+
+    if components.is_empty() {
+        return Ok("-".to_string());
+    }
+    Ok(escape(&components.join("/")))
+}
+
+/// Undo [`escape`] (`systemd-escape --unescape`)
+pub fn unescape(name: &str) -> Result<String> {
+    let invalid = || MkunitError::invalid_argument(format!("Invalid escape sequence in '{name}'"));
+
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        match b {
+            b'-' => bytes.push(b'/'),
+            b'\\' => {
+                let hex = match rest {
+                    [b'x', hi, lo, ..] => [*hi, *lo],
+                    _ => return Err(invalid()),
+                };
+                let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+                bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                rest = &rest[3..];
+            }
+            b => bytes.push(b),
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Undo [`escape_path`] (`systemd-escape --unescape --path`)
+pub fn unescape_path(name: &str) -> Result<String> {
+    if name.is_empty() {
+        return Err(MkunitError::invalid_argument("Empty unit name"));
+    }
+    if name == "-" {
+        return Ok("/".to_string());
+    }
+
+    let path = unescape(name)?;
+    let normalized = path
+        .split('/')
+        .all(|c| !c.is_empty() && c != "." && c != "..");
+    if !normalized {
+        return Err(MkunitError::invalid_argument(format!(
+            "'{name}' does not unescape to a normalized path"
+        )));
+    }
+    Ok(format!("/{path}"))
+}
+
+/// Full unit name for an absolute path (`/mnt/data` -> `mnt-data.mount`)
+pub fn path_unit_name(path: &str, unit_type: UnitType) -> Result<String> {
+    if !path.starts_with('/') {
+        return Err(MkunitError::invalid_argument(format!(
+            "'{path}' must be an absolute path"
+        )));
+    }
+    Ok(format!("{}.{}", escape_path(path)?, unit_type.extension()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("hello world"), "hello\\x20world");
+        assert_eq!(escape("a-b/c"), "a\\x2db-c");
+        assert_eq!(escape(".hidden"), "\\x2ehidden");
+        assert_eq!(escape("ü"), "\\xc3\\xbc");
+    }
+
+    #[test]
+    fn test_escape_path() {
+        assert_eq!(escape_path("/mnt/my data").unwrap(), "mnt-my\\x20data");
+        assert_eq!(escape_path("//mnt/./data/").unwrap(), "mnt-data");
+        assert_eq!(escape_path("/").unwrap(), "-");
+        assert_eq!(
+            escape_path("/var/lib/.snapshots").unwrap(),
+            "var-lib-.snapshots"
+        );
+        assert!(escape_path("/mnt/../etc").is_err());
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("mnt-my\\x20data").unwrap(), "mnt/my data");
+        assert_eq!(unescape_path("mnt-my\\x20data").unwrap(), "/mnt/my data");
+        assert_eq!(unescape_path("-").unwrap(), "/");
+        assert!(unescape("bad\\x2").is_err());
+        assert!(unescape("bad\\y20").is_err());
+        assert!(unescape_path("mnt--data").is_err());
+    }
+
+    #[test]
+    fn test_path_unit_name() {
+        assert_eq!(
+            path_unit_name("/mnt/data", UnitType::Mount).unwrap(),
+            "mnt-data.mount"
+        );
+        assert!(path_unit_name("mnt/data", UnitType::Mount).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn escape_round_trips(value in "[^\\x00]{1,40}") {
+            prop_assert_eq!(unescape(&escape(&value)).unwrap(), value);
+        }
+
+        #[test]
+        fn escape_path_round_trips(parts in prop::collection::vec("[^/\\x00]{1,10}", 1..5)) {
+            prop_assume!(parts.iter().all(|p| p != "." && p != ".."));
+            let path = format!("/{}", parts.join("/"));
+            prop_assert_eq!(unescape_path(&escape_path(&path).unwrap()).unwrap(), path);
+        }
+    }
+}
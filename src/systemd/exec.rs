@@ -0,0 +1,191 @@
+//! `Exec*=` commands and their special prefixes
+//!
+//! The executable in an `Exec*=` line may be preceded by prefix characters
+//! that change how systemd runs it. They are written in front of the command
+//! on the command line (`--exec-start-pre "-/usr/bin/migrate"`) and kept as
+//! explicit fields here rather than as part of the executable path.
+
+use crate::error::{MkunitError, Result};
+use crate::systemd::quote::{self, Expansion};
+
+/// Privileges the command runs with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Privilege {
+    /// Run with the unit's User=, Group= and sandboxing settings
+    #[default]
+    Normal,
+    /// `+`: run with full privileges, ignoring User=, Group= and sandboxing
+    Full,
+    /// `!`: apply sandboxing but skip the User= and Group= credential change
+    KeepCredentials,
+    /// `!!`: like `!`, but only on systems without ambient capabilities
+    AmbientFallback,
+}
+
+/// A parsed `Exec*=` command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecCommand {
+    /// `-`: a non-zero exit status is not treated as a failure
+    pub ignore_failure: bool,
+    /// `@`: the second word is passed to the process as `argv[0]`
+    pub custom_argv0: bool,
+    /// `:`: `$VAR` references are not expanded
+    pub no_env_expansion: bool,
+    pub privilege: Privilege,
+    /// Executable followed by its arguments
+    pub argv: Vec<String>,
+}
+
+impl ExecCommand {
+    /// Parse a shell-style command with optional leading prefix characters
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim_start();
+        let (mut command, rest) = Self::parse_prefixes(input)?;
+        command.argv = quote::split_shell(rest)?;
+        command.check()?;
+        Ok(command)
+    }
+
+    /// Parse the value of an existing `Exec*=` line
+    pub fn from_line(line: &str) -> Result<Self> {
+        let line = line.trim_start();
+        let (mut command, rest) = Self::parse_prefixes(line)?;
+        command.argv = quote::parse_exec(rest, Expansion::Systemd)?;
+        command.check()?;
+        Ok(command)
+    }
+
+    fn parse_prefixes(input: &str) -> Result<(Self, &str)> {
+        let mut command = Self {
+            ignore_failure: false,
+            custom_argv0: false,
+            no_env_expansion: false,
+            privilege: Privilege::Normal,
+            argv: Vec::new(),
+        };
+
+        let mut rest = input;
+        while let Some(c) = rest.chars().next() {
+            let duplicate = match c {
+                '-' => std::mem::replace(&mut command.ignore_failure, true),
+                '@' => std::mem::replace(&mut command.custom_argv0, true),
+                ':' => std::mem::replace(&mut command.no_env_expansion, true),
+                '+' | '!' => {
+                    command.privilege = match (command.privilege, c) {
+                        (Privilege::Normal, '+') => Privilege::Full,
+                        (Privilege::Normal, _) => Privilege::KeepCredentials,
+                        (Privilege::KeepCredentials, '!') => Privilege::AmbientFallback,
+                        _ => {
+                            return Err(MkunitError::invalid_argument(format!(
+                                "Command '{input}' combines conflicting '+' and '!' prefixes"
+                            )));
+                        }
+                    };
+                    false
+                }
+                _ => break,
+            };
+            if duplicate {
+                return Err(MkunitError::invalid_argument(format!(
+                    "Command '{input}' repeats the '{c}' prefix"
+                )));
+            }
+            rest = &rest[1..];
+        }
+
+        Ok((command, rest))
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.argv.is_empty() {
+            return Err(MkunitError::invalid_argument("Command is empty"));
+        }
+        if self.custom_argv0 && self.argv.len() < 2 {
+            return Err(MkunitError::invalid_argument(format!(
+                "The '@' prefix needs an argv[0] after '{}'",
+                self.argv[0]
+            )));
+        }
+        Ok(())
+    }
+
+    /// The executable that will be run
+    #[must_use]
+    pub fn executable(&self) -> &str {
+        &self.argv[0]
+    }
+
+    /// Prefix characters for this command
+    #[must_use]
+    pub fn prefix(&self) -> String {
+        let mut prefix = String::new();
+        if self.ignore_failure {
+            prefix.push('-');
+        }
+        if self.custom_argv0 {
+            prefix.push('@');
+        }
+        if self.no_env_expansion {
+            prefix.push(':');
+        }
+        match self.privilege {
+            Privilege::Normal => {}
+            Privilege::Full => prefix.push('+'),
+            Privilege::KeepCredentials => prefix.push('!'),
+            Privilege::AmbientFallback => prefix.push_str("!!"),
+        }
+        prefix
+    }
+
+    /// Render the command as an `Exec*=` value
+    #[must_use]
+    pub fn to_line(&self, expansion: Expansion) -> String {
+        format!(
+            "{}{}",
+            self.prefix(),
+            quote::exec_line(&self.argv, expansion)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prefixes() {
+        let cmd = ExecCommand::parse("-+/usr/bin/migrate --all").unwrap();
+        assert!(cmd.ignore_failure);
+        assert_eq!(cmd.privilege, Privilege::Full);
+        assert_eq!(cmd.argv, vec!["/usr/bin/migrate", "--all"]);
+        assert_eq!(cmd.to_line(Expansion::Systemd), "-+/usr/bin/migrate --all");
+
+        let cmd = ExecCommand::parse("@/usr/bin/app app-worker").unwrap();
+        assert!(cmd.custom_argv0);
+        assert_eq!(cmd.executable(), "/usr/bin/app");
+
+        let cmd = ExecCommand::parse("!!:/usr/bin/app $HOME").unwrap();
+        assert_eq!(cmd.privilege, Privilege::AmbientFallback);
+        assert_eq!(cmd.to_line(Expansion::Systemd), ":!!/usr/bin/app $HOME");
+
+        let cmd = ExecCommand::parse("/bin/kill -HUP $MAINPID").unwrap();
+        assert_eq!(cmd.prefix(), "");
+    }
+
+    #[test]
+    fn test_parse_invalid_prefixes() {
+        assert!(ExecCommand::parse("+!/usr/bin/app").is_err());
+        assert!(ExecCommand::parse("!!!/usr/bin/app").is_err());
+        assert!(ExecCommand::parse("--/usr/bin/app").is_err());
+        assert!(ExecCommand::parse("@/usr/bin/app").is_err());
+        assert!(ExecCommand::parse("-").is_err());
+    }
+
+    #[test]
+    fn test_from_line_round_trip() {
+        let cmd = ExecCommand::parse("!/usr/bin/app 'two words'").unwrap();
+        let line = cmd.to_line(Expansion::Systemd);
+        assert_eq!(line, "!/usr/bin/app \"two words\"");
+        assert_eq!(ExecCommand::from_line(&line).unwrap(), cmd);
+    }
+}
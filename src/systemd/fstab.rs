@@ -0,0 +1,187 @@
+//! `/etc/fstab` parsing, as read by systemd-fstab-generator
+//!
+//! Each line has up to six whitespace separated fields: source, mount point,
+//! filesystem type, options, dump frequency and fsck pass. Spaces inside a
+//! field are written as the octal escape `\040`.
+
+use crate::error::{MkunitError, Result};
+
+/// A single fstab entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FstabEntry {
+    /// Line number in the file, for messages
+    pub line: usize,
+    /// Source as written, e.g. `UUID=...` or `/dev/sda1`
+    pub spec: String,
+    /// Mount point, or `none`/`swap` for swap entries
+    pub file: String,
+    pub vfstype: String,
+    pub options: Vec<String>,
+    /// fsck pass, 0 when the filesystem isn't checked
+    pub passno: u32,
+}
+
+impl FstabEntry {
+    #[must_use]
+    pub fn is_swap(&self) -> bool {
+        self.vfstype == "swap"
+    }
+
+    /// Whether a flag option such as `noauto` is set
+    #[must_use]
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options.iter().any(|o| o == name)
+    }
+
+    /// Device node for the source, resolving `UUID=`, `LABEL=` and friends
+    #[must_use]
+    pub fn source(&self) -> String {
+        resolve_source(&self.spec)
+    }
+}
+
+/// Tag prefixes and the udev symlink directory they resolve to
+const SOURCE_TAGS: &[(&str, &str)] = &[
+    ("UUID=", "/dev/disk/by-uuid/"),
+    ("LABEL=", "/dev/disk/by-label/"),
+    ("PARTUUID=", "/dev/disk/by-partuuid/"),
+    ("PARTLABEL=", "/dev/disk/by-partlabel/"),
+];
+
+/// Turn a tagged source into the device node udev creates for it
+///
+/// Slashes and spaces in the tag value are hex-escaped like udev does, so
+/// `LABEL=my data` becomes `/dev/disk/by-label/my\x20data`.
+#[must_use]
+pub fn resolve_source(spec: &str) -> String {
+    for (tag, dir) in SOURCE_TAGS {
+        if let Some(value) = spec.strip_prefix(tag) {
+            let value = value.trim_matches('"');
+            let escaped = value.replace('/', "\\x2f").replace(' ', "\\x20");
+            return format!("{dir}{escaped}");
+        }
+    }
+    spec.to_string()
+}
+
+/// Parse the contents of an fstab file
+pub fn parse(content: &str) -> Result<Vec<FstabEntry>> {
+    let mut entries = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line_num = index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<String> = trimmed.split_whitespace().map(unescape_octal).collect();
+        if fields.len() < 2 {
+            return Err(MkunitError::invalid_argument(format!(
+                "Line {line_num}: expected at least a source and a mount point"
+            )));
+        }
+
+        let passno = match fields.get(5) {
+            Some(p) => p.parse().map_err(|_| {
+                MkunitError::invalid_argument(format!(
+                    "Line {line_num}: invalid fsck pass number '{p}'"
+                ))
+            })?,
+            None => 0,
+        };
+
+        entries.push(FstabEntry {
+            line: line_num,
+            spec: fields[0].clone(),
+            file: fields[1].clone(),
+            vfstype: fields.get(2).cloned().unwrap_or_else(|| "auto".to_string()),
+            options: fields
+                .get(3)
+                .map(|o| {
+                    o.split(',')
+                        .filter(|o| !o.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            passno,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Decode `\ooo` octal escapes, as used in fstab and mountinfo
+#[must_use]
+pub fn unescape_octal(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .filter(|d| bytes[i] == b'\\' && d.iter().all(|b| (b'0'..=b'7').contains(b)))
+            .and_then(|d| u8::from_str_radix(std::str::from_utf8(d).ok()?, 8).ok());
+        if let Some(b) = octal {
+            out.push(b);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let content = "\
+# <file system> <mount point> <type> <options> <dump> <pass>
+UUID=1234-abcd  /            ext4  errors=remount-ro  0  1
+/dev/sdb1       /mnt/my\\040data  xfs  noauto,nofail  0  2
+/swapfile       none         swap  sw,pri=5  0  0
+
+nas:/export     /mnt/nas
+";
+        let entries = parse(content).unwrap();
+        assert_eq!(entries.len(), 4);
+
+        assert_eq!(entries[0].line, 2);
+        assert_eq!(entries[0].source(), "/dev/disk/by-uuid/1234-abcd");
+        assert_eq!(entries[0].passno, 1);
+
+        assert_eq!(entries[1].file, "/mnt/my data");
+        assert!(entries[1].has_option("noauto"));
+        assert!(!entries[1].has_option("auto"));
+
+        assert!(entries[2].is_swap());
+        assert_eq!(entries[2].options, vec!["sw", "pri=5"]);
+
+        assert_eq!(entries[3].vfstype, "auto");
+        assert!(entries[3].options.is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("/dev/sda1\n").is_err());
+        assert!(parse("/dev/sda1 /mnt ext4 defaults 0 x\n").is_err());
+    }
+
+    #[test]
+    fn test_resolve_source() {
+        assert_eq!(
+            resolve_source("LABEL=my data"),
+            "/dev/disk/by-label/my\\x20data"
+        );
+        assert_eq!(
+            resolve_source("PARTUUID=\"0a1b\""),
+            "/dev/disk/by-partuuid/0a1b"
+        );
+        assert_eq!(resolve_source("tmpfs"), "tmpfs");
+    }
+}
@@ -0,0 +1,355 @@
+//! Socket listen addresses for `ListenStream=`, `ListenNetlink=` and friends,
+//! see systemd.socket(5)
+//!
+//! Addresses are parsed when the command line is read so a typo is reported
+//! before a unit is written. Stream, datagram and sequential packet sockets
+//! share the address syntax: a port, an IPv4 or bracketed IPv6 address with a
+//! port, a unix socket path, an abstract socket starting with `@`, or a
+//! `vsock:` address. Unix socket paths may start with a directory specifier
+//! such as `%t`.
+
+use crate::error::{MkunitError, Result};
+use crate::systemd::specifier;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::{Component, Path};
+use std::str::FromStr;
+
+/// Size of `sun_path` in `struct sockaddr_un`
+const UNIX_PATH_MAX: usize = 108;
+
+/// Netlink families systemd knows by name
+const NETLINK_FAMILIES: &[&str] = &[
+    "route",
+    "firewall",
+    "inet-diag",
+    "nflog",
+    "xfrm",
+    "selinux",
+    "iscsi",
+    "audit",
+    "fib-lookup",
+    "connector",
+    "netfilter",
+    "ip6-fw",
+    "dnrtmsg",
+    "kobject-uevent",
+    "generic",
+    "scsitransport",
+    "ecryptfs",
+    "rdma",
+    "crypto",
+];
+
+const EXAMPLES: &str = "e.g., 8080, 127.0.0.1:8080, [::1]:8080, /run/app.sock, @app";
+
+/// Address of a stream, datagram or sequential packet socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddress {
+    /// A port on all local addresses
+    Port(u16),
+    /// An IP address and port, optionally scoped to an interface
+    Inet {
+        ip: IpAddr,
+        port: u16,
+        interface: Option<String>,
+    },
+    /// A unix socket in the file system
+    Unix(String),
+    /// A unix socket in the abstract namespace, without the leading `@`
+    Abstract(String),
+    /// An `AF_VSOCK` context id and port
+    Vsock { cid: String, port: u32 },
+}
+
+impl ListenAddress {
+    /// Port of an IP socket
+    #[must_use]
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            Self::Port(port) | Self::Inet { port, .. } => Some(*port),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ListenAddress {
+    type Err = MkunitError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            MkunitError::invalid_argument(format!(
+                "Invalid listen address '{value}': {reason} ({EXAMPLES})"
+            ))
+        };
+
+        if value.starts_with('/') || specifier_dir(value).is_some() {
+            check_path(value, "socket path").map_err(|reason| invalid(&reason))?;
+            if value.len() >= UNIX_PATH_MAX {
+                return Err(invalid(&format!(
+                    "unix socket paths must be shorter than {UNIX_PATH_MAX} bytes"
+                )));
+            }
+            return Ok(Self::Unix(value.to_string()));
+        }
+        if let Some(name) = value.strip_prefix('@') {
+            if name.is_empty() || value.len() > UNIX_PATH_MAX {
+                return Err(invalid(&format!(
+                    "abstract socket names must be 1 to {} bytes",
+                    UNIX_PATH_MAX - 1
+                )));
+            }
+            return Ok(Self::Abstract(name.to_string()));
+        }
+        if let Some(rest) = value.strip_prefix("vsock:") {
+            let (cid, port) = rest
+                .rsplit_once(':')
+                .ok_or_else(|| invalid("expected vsock:CID:PORT"))?;
+            let port = port.parse().map_err(|_| invalid("invalid vsock port"))?;
+            let cid_valid = cid.is_empty() || cid.bytes().all(|b| b.is_ascii_digit());
+            if !cid_valid {
+                return Err(invalid("the vsock context id must be a number"));
+            }
+            return Ok(Self::Vsock {
+                cid: cid.to_string(),
+                port,
+            });
+        }
+        if value.bytes().all(|b| b.is_ascii_digit()) {
+            return Ok(Self::Port(parse_port(value).map_err(|r| invalid(&r))?));
+        }
+
+        // "[::1]:80%eth0" or "127.0.0.1:80%eth0"
+        let (address, interface) = match value.split_once('%') {
+            Some((address, interface)) if !interface.is_empty() => {
+                (address, Some(interface.to_string()))
+            }
+            Some(_) => return Err(invalid("empty interface after '%'")),
+            None => (value, None),
+        };
+        let (ip, port) = if let Some(rest) = address.strip_prefix('[') {
+            let (host, port) = rest
+                .split_once("]:")
+                .ok_or_else(|| invalid("IPv6 addresses are written as [address]:port"))?;
+            let ip: IpAddr = host.parse().map_err(|_| invalid("invalid IPv6 address"))?;
+            if ip.is_ipv4() {
+                return Err(invalid("IPv4 addresses are written without brackets"));
+            }
+            (ip, port)
+        } else {
+            let (host, port) = address
+                .rsplit_once(':')
+                .ok_or_else(|| invalid("expected a port, address:port or a path"))?;
+            if host.contains(':') {
+                return Err(invalid("IPv6 addresses are written as [address]:port"));
+            }
+            let ip: IpAddr = host
+                .parse()
+                .map_err(|_| invalid(&format!("'{host}' is not an IP address")))?;
+            (ip, port)
+        };
+
+        Ok(Self::Inet {
+            ip,
+            port: parse_port(port).map_err(|r| invalid(&r))?,
+            interface,
+        })
+    }
+}
+
+impl fmt::Display for ListenAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Port(port) => write!(f, "{port}"),
+            Self::Inet {
+                ip,
+                port,
+                interface,
+            } => {
+                match ip {
+                    IpAddr::V4(ip) => write!(f, "{ip}:{port}")?,
+                    IpAddr::V6(ip) => write!(f, "[{ip}]:{port}")?,
+                }
+                match interface {
+                    Some(interface) => write!(f, "%{interface}"),
+                    None => Ok(()),
+                }
+            }
+            Self::Unix(path) => f.write_str(path),
+            Self::Abstract(name) => write!(f, "@{name}"),
+            Self::Vsock { cid, port } => write!(f, "vsock:{cid}:{port}"),
+        }
+    }
+}
+
+fn parse_port(value: &str) -> std::result::Result<u16, String> {
+    match value.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(port),
+        _ => Err(format!("port '{value}' is not between 1 and 65535")),
+    }
+}
+
+/// The specifier a path starts with, such as `t` for `%t/app.sock`
+///
+/// Directory specifiers like `%t` resolve to an absolute path, so the rest of
+/// the path has to start with `/`.
+fn specifier_dir(path: &str) -> Option<char> {
+    let mut chars = path.chars();
+    if chars.next() != Some('%') {
+        return None;
+    }
+    let code = chars.next()?;
+    let known = code != '%' && specifier::lookup(code).is_some();
+    (known && chars.as_str().starts_with('/')).then_some(code)
+}
+
+/// Check that a path is absolute and normalized
+fn check_path(path: &str, what: &str) -> std::result::Result<(), String> {
+    let path = if specifier_dir(path).is_some() {
+        &path[2..]
+    } else {
+        path
+    };
+    let normalized = Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::RootDir | Component::Normal(_)));
+    if !path.starts_with('/') {
+        return Err(format!("the {what} must be absolute"));
+    }
+    if !normalized || path.contains("//") || (path.len() > 1 && path.ends_with('/')) {
+        return Err(format!("the {what} must not contain '.', '..' or '//'"));
+    }
+    Ok(())
+}
+
+/// Check the path of a `ListenFIFO=`
+pub fn fifo_path(value: &str) -> Result<String> {
+    check_path(value, "FIFO path").map_err(|reason| {
+        MkunitError::invalid_argument(format!("Invalid FIFO path '{value}': {reason}"))
+    })?;
+    Ok(value.to_string())
+}
+
+/// Check a `ListenNetlink=` value: a family name or number and an optional group
+pub fn netlink(value: &str) -> Result<String> {
+    let invalid = |reason: &str| {
+        MkunitError::invalid_argument(format!(
+            "Invalid netlink socket '{value}': {reason} (e.g., 'kobject-uevent 1', 'route')"
+        ))
+    };
+
+    let mut words = value.split_whitespace();
+    let family = words.next().ok_or_else(|| invalid("empty"))?;
+    let known = NETLINK_FAMILIES.contains(&family) || family.parse::<u32>().is_ok();
+    if !known {
+        return Err(invalid(&format!(
+            "unknown family '{family}', expected one of {}",
+            NETLINK_FAMILIES.join(", ")
+        )));
+    }
+
+    match (words.next(), words.next()) {
+        (None, _) => Ok(family.to_string()),
+        (Some(group), None) => {
+            let group: u32 = group
+                .parse()
+                .map_err(|_| invalid("the multicast group must be a number"))?;
+            Ok(format!("{family} {group}"))
+        }
+        (Some(_), Some(_)) => Err(invalid("expected a family and an optional group")),
+    }
+}
+
+/// Check a `FileDescriptorName=`: printable ASCII without `:`, at most 255 characters
+pub fn fd_name(value: &str) -> Result<String> {
+    let valid = !value.is_empty()
+        && value.len() <= 255
+        && value.bytes().all(|b| b.is_ascii_graphic() && b != b':');
+    if !valid {
+        return Err(MkunitError::invalid_argument(format!(
+            "Invalid file descriptor name '{value}'. Use up to 255 printable characters \
+             without spaces or ':'"
+        )));
+    }
+    Ok(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> ListenAddress {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(parse("8080"), ListenAddress::Port(8080));
+        assert_eq!(parse("127.0.0.1:53").port(), Some(53));
+        assert_eq!(parse("[::1]:8080").to_string(), "[::1]:8080");
+        assert_eq!(parse("[fe80::1]:80%eth0").to_string(), "[fe80::1]:80%eth0");
+        assert_eq!(parse("[0:0::1]:80").to_string(), "[::1]:80");
+        assert_eq!(
+            parse("/run/app.sock"),
+            ListenAddress::Unix("/run/app.sock".to_string())
+        );
+        assert_eq!(parse("@app"), ListenAddress::Abstract("app".to_string()));
+        assert_eq!(parse("vsock:2:1234").to_string(), "vsock:2:1234");
+        assert_eq!(parse("/run/app.sock").port(), None);
+        assert_eq!(
+            parse("%t/app.sock"),
+            ListenAddress::Unix("%t/app.sock".to_string())
+        );
+        assert_eq!(parse("%T/app/app.sock").to_string(), "%T/app/app.sock");
+    }
+
+    #[test]
+    fn test_parse_address_errors() {
+        for value in [
+            "0",
+            "65536",
+            "localhost:80",
+            "::1:80",
+            "[127.0.0.1]:80",
+            "[::1]",
+            "127.0.0.1:80%",
+            "run/app.sock",
+            "/run/../app.sock",
+            "/run//app.sock",
+            "%t",
+            "%tapp.sock",
+            "%t/../app.sock",
+            "%z/app.sock",
+            "@",
+            "vsock:x:1",
+        ] {
+            assert!(value.parse::<ListenAddress>().is_err(), "{value}");
+        }
+
+        let long = format!("/run/{}", "a".repeat(UNIX_PATH_MAX));
+        let err = long.parse::<ListenAddress>().unwrap_err().to_string();
+        assert!(err.contains("shorter than 108 bytes"), "{err}");
+    }
+
+    #[test]
+    fn test_netlink() {
+        assert_eq!(netlink("kobject-uevent 1").unwrap(), "kobject-uevent 1");
+        assert_eq!(netlink("route").unwrap(), "route");
+        assert_eq!(netlink("16").unwrap(), "16");
+        assert!(netlink("").is_err());
+        assert!(netlink("uevent 1").is_err());
+        assert!(netlink("route all").is_err());
+        assert!(netlink("route 1 2").is_err());
+    }
+
+    #[test]
+    fn test_fifo_and_fd_name() {
+        assert!(fifo_path("/run/app.fifo").is_ok());
+        assert!(fifo_path("app.fifo").is_err());
+        assert!(fifo_path("%t/app.fifo").is_ok());
+        assert_eq!(fd_name("http").unwrap(), "http");
+        assert!(fd_name("a:b").is_err());
+        assert!(fd_name("with space").is_err());
+        assert!(fd_name(&"x".repeat(256)).is_err());
+    }
+}
@@ -1,10 +1,25 @@
+pub mod calendar;
+pub mod crontab;
+pub mod escape;
+pub mod exec;
+pub mod fstab;
+pub mod listen;
+pub mod mountinfo;
 pub mod paths;
+pub mod ports;
+pub mod quote;
+pub mod resource;
+pub mod schedule;
+pub mod specifier;
+pub mod timespan;
 pub mod version;
 
 pub use paths::*;
 
 use crate::error::{MkunitError, Result};
+use std::collections::HashMap;
 use std::process::Command;
+use std::sync::OnceLock;
 
 /// Run systemctl command
 pub fn systemctl(args: &[&str], system: bool) -> Result<String> {
@@ -89,6 +104,43 @@ pub fn unit_status(name: &str, system: bool) -> Result<String> {
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
+/// First version whose `systemctl show` takes `--timestamp=unix`
+const UNIX_TIMESTAMP_SINCE: u32 = 251;
+
+/// Read properties of a loaded unit with `systemctl show`
+///
+/// Timestamps are returned as `@<seconds since the epoch>` by systemd 251 and
+/// newer, in the default `Mon 2026-03-02 12:00:00 UTC` form before that, or
+/// empty when unset.
+pub fn show_properties(
+    name: &str,
+    properties: &[&str],
+    system: bool,
+) -> Result<HashMap<String, String>> {
+    static UNIX_TIMESTAMPS: OnceLock<bool> = OnceLock::new();
+    let unix_timestamps = *UNIX_TIMESTAMPS.get_or_init(|| {
+        version::detect_version().is_ok_and(|v| v.supports_feature(UNIX_TIMESTAMP_SINCE))
+    });
+
+    let property = format!("--property={}", properties.join(","));
+    let mut args = vec!["show", property.as_str(), name];
+    if unix_timestamps {
+        args.insert(1, "--timestamp=unix");
+    }
+    let output = systemctl(&args, system)?;
+    Ok(parse_properties(&output))
+}
+
+/// Parse `Key=Value` lines from `systemctl show`
+#[must_use]
+pub fn parse_properties(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
 /// Check if a unit is active
 pub fn is_unit_active(name: &str, system: bool) -> bool {
     systemctl(&["is-active", "--quiet", name], system).is_ok()
@@ -0,0 +1,86 @@
+//! Mount table from `/proc/self/mountinfo`
+//!
+//! Used to find paths a unit depends on that live on a separate mount, so
+//! that `RequiresMountsFor=` can order the unit after that mount.
+
+use crate::systemd::fstab::unescape_octal;
+use std::fs;
+use std::path::Path;
+
+/// Kernel API filesystems that are always mounted before any unit starts
+const API_MOUNTS: &[&str] = &["/proc", "/sys", "/dev", "/run"];
+
+/// Mount points of the running system
+#[derive(Debug, Default)]
+pub struct MountTable {
+    mount_points: Vec<String>,
+}
+
+impl MountTable {
+    /// Read the current mount table, empty if it isn't available
+    #[must_use]
+    pub fn current() -> Self {
+        fs::read_to_string("/proc/self/mountinfo")
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// Parse mountinfo contents, where the mount point is the fifth field
+    #[must_use]
+    pub fn parse(content: &str) -> Self {
+        let mount_points = content
+            .lines()
+            .filter_map(|line| line.split(' ').nth(4))
+            .map(unescape_octal)
+            .collect();
+        Self { mount_points }
+    }
+
+    /// The mount a path lives on, if that isn't the root filesystem
+    ///
+    /// Paths under the kernel API filesystems are ignored since systemd
+    /// mounts them before anything else.
+    #[must_use]
+    pub fn separate_mount(&self, path: &str) -> Option<&str> {
+        let path = Path::new(path);
+        if !path.is_absolute() {
+            return None;
+        }
+        if API_MOUNTS.iter().any(|api| path.starts_with(api)) {
+            return None;
+        }
+
+        self.mount_points
+            .iter()
+            .filter(|mp| mp.as_str() != "/" && path.starts_with(mp.as_str()))
+            .max_by_key(|mp| mp.len())
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOUNTINFO: &str = "\
+22 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw
+23 22 0:21 / /proc rw,nosuid shared:12 - proc proc rw
+24 22 8:3 / /srv rw,relatime shared:2 - xfs /dev/sda3 rw
+25 24 8:4 / /srv/app\\040data rw,relatime shared:3 - xfs /dev/sda4 rw
+26 22 0:25 / /run rw,nosuid shared:5 - tmpfs tmpfs rw
+";
+
+    #[test]
+    fn test_separate_mount() {
+        let table = MountTable::parse(MOUNTINFO);
+        assert_eq!(table.separate_mount("/srv/www"), Some("/srv"));
+        assert_eq!(
+            table.separate_mount("/srv/app data/bin/run"),
+            Some("/srv/app data")
+        );
+        assert_eq!(table.separate_mount("/srvx/app"), None);
+        assert_eq!(table.separate_mount("/usr/bin/python3"), None);
+        assert_eq!(table.separate_mount("/run/app.env"), None);
+        assert_eq!(table.separate_mount("relative/path"), None);
+    }
+}
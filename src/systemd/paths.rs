@@ -14,10 +14,26 @@ pub enum UnitType {
     Path,
     Socket,
     Mount,
+    Automount,
+    Swap,
     Target,
+    Slice,
 }
 
 impl UnitType {
+    /// Every supported type, in the order units are looked up by name
+    pub const ALL: [Self; 9] = [
+        Self::Service,
+        Self::Timer,
+        Self::Path,
+        Self::Socket,
+        Self::Mount,
+        Self::Automount,
+        Self::Swap,
+        Self::Target,
+        Self::Slice,
+    ];
+
     #[must_use]
     #[allow(clippy::trivially_copy_pass_by_ref)]
     pub fn extension(&self) -> &'static str {
@@ -27,7 +43,10 @@ impl UnitType {
             Self::Path => "path",
             Self::Socket => "socket",
             Self::Mount => "mount",
+            Self::Automount => "automount",
+            Self::Swap => "swap",
             Self::Target => "target",
+            Self::Slice => "slice",
         }
     }
 
@@ -39,7 +58,10 @@ impl UnitType {
             "path" => Some(Self::Path),
             "socket" => Some(Self::Socket),
             "mount" => Some(Self::Mount),
+            "automount" => Some(Self::Automount),
+            "swap" => Some(Self::Swap),
             "target" => Some(Self::Target),
+            "slice" => Some(Self::Slice),
             _ => None,
         }
     }
@@ -90,6 +112,89 @@ pub fn unit_path(name: &str, unit_type: UnitType, system: bool) -> Result<PathBu
     Ok(dir.join(filename))
 }
 
+/// Get the file name of a template unit (`myapp` or `myapp@` -> `myapp@.service`)
+///
+/// A type suffix in `name` (`myapp@.timer`) takes precedence over `unit_type`.
+#[must_use]
+pub fn template_unit_name(name: &str, unit_type: UnitType) -> String {
+    if let Some((prefix, ext)) = name.split_once("@.") {
+        if UnitType::from_extension(ext).is_some() {
+            return format!("{prefix}@.{ext}");
+        }
+    }
+    let prefix = name.trim_end_matches('@');
+    format!("{prefix}@.{}", unit_type.extension())
+}
+
+/// Get the unit name of an instance of a template (`myapp@.service` + `web` -> `myapp@web.service`)
+#[must_use]
+pub fn instance_unit_name(template: &str, instance: &str) -> String {
+    match template.split_once("@.") {
+        Some((prefix, ext)) => format!("{prefix}@{instance}.{ext}"),
+        None => format!("{}@{instance}", template.trim_end_matches('@')),
+    }
+}
+
+/// Check that an instance name only uses characters valid in unit names
+pub fn validate_instance(instance: &str) -> Result<()> {
+    let valid = !instance.is_empty()
+        && instance
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ":_.\\-@".contains(c));
+    if !valid {
+        return Err(MkunitError::invalid_argument(format!(
+            "Invalid instance name '{instance}'. Escape it with systemd-escape first"
+        )));
+    }
+    Ok(())
+}
+
+/// List instances of a template that are enabled in the unit directory
+///
+/// Enabled instances are symlinks in `*.wants/` or `*.requires/` directories.
+pub fn list_instances(template: &str, system: bool) -> Result<Vec<String>> {
+    let dir = unit_dir(system)?;
+    let Some((prefix, ext)) = template.split_once("@.") else {
+        return Ok(Vec::new());
+    };
+    let name_prefix = format!("{prefix}@");
+    let name_suffix = format!(".{ext}");
+
+    let mut instances = Vec::new();
+    if !dir.exists() {
+        return Ok(instances);
+    }
+
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let is_dep_dir = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e == "wants" || e == "requires");
+        if !is_dep_dir || !path.is_dir() {
+            continue;
+        }
+
+        for link in fs::read_dir(&path)? {
+            let file_name = link?.file_name();
+            let Some(name) = file_name.to_str() else {
+                continue;
+            };
+            let instance = name
+                .strip_prefix(&name_prefix)
+                .and_then(|rest| rest.strip_suffix(&name_suffix));
+            if let Some(instance) = instance {
+                if !instance.is_empty() && !instances.iter().any(|i| i == instance) {
+                    instances.push(instance.to_string());
+                }
+            }
+        }
+    }
+
+    instances.sort();
+    Ok(instances)
+}
+
 /// Get the full path for any unit file (auto-detect type from name)
 #[allow(dead_code)]
 pub fn unit_path_any(name: &str, system: bool) -> Result<PathBuf> {
@@ -103,14 +208,7 @@ pub fn unit_path_any(name: &str, system: bool) -> Result<PathBuf> {
     }
 
     // Try common extensions
-    for unit_type in &[
-        UnitType::Service,
-        UnitType::Timer,
-        UnitType::Path,
-        UnitType::Socket,
-        UnitType::Mount,
-        UnitType::Target,
-    ] {
+    for unit_type in UnitType::ALL {
         let path = dir.join(format!("{}.{}", name, unit_type.extension()));
         if path.exists() {
             return Ok(path);
@@ -159,14 +257,7 @@ fn find_unit_in_dir(name: &str, dir: &Path) -> Option<PathBuf> {
     }
 
     // Try common extensions
-    for unit_type in &[
-        UnitType::Service,
-        UnitType::Timer,
-        UnitType::Path,
-        UnitType::Socket,
-        UnitType::Mount,
-        UnitType::Target,
-    ] {
+    for unit_type in UnitType::ALL {
         let path = dir.join(format!("{}.{}", name, unit_type.extension()));
         if path.exists() {
             return Some(path);
@@ -208,6 +299,7 @@ pub fn list_units(system: bool) -> Result<Vec<PathBuf>> {
 }
 
 /// Check if a unit was created by mkunit
+#[allow(clippy::map_unwrap_or)]
 pub fn is_mkunit_created(path: &PathBuf) -> bool {
     fs::read_to_string(path)
         .map(|content| content.contains(MKUNIT_MARKER))
@@ -235,9 +327,36 @@ mod tests {
     fn test_unit_type_from_extension() {
         assert_eq!(UnitType::from_extension("service"), Some(UnitType::Service));
         assert_eq!(UnitType::from_extension("timer"), Some(UnitType::Timer));
+        assert_eq!(UnitType::from_extension("slice"), Some(UnitType::Slice));
+        assert_eq!(
+            UnitType::from_extension("automount"),
+            Some(UnitType::Automount)
+        );
         assert_eq!(UnitType::from_extension("invalid"), None);
     }
 
+    #[test]
+    fn test_template_unit_names() {
+        assert_eq!(
+            template_unit_name("myapp", UnitType::Service),
+            "myapp@.service"
+        );
+        assert_eq!(
+            template_unit_name("myapp@", UnitType::Service),
+            "myapp@.service"
+        );
+        assert_eq!(
+            template_unit_name("myapp@.timer", UnitType::Service),
+            "myapp@.timer"
+        );
+        assert_eq!(
+            instance_unit_name("myapp@.service", "web"),
+            "myapp@web.service"
+        );
+        assert!(validate_instance("eu-west_1").is_ok());
+        assert!(validate_instance("my data").is_err());
+    }
+
     #[test]
     fn test_system_unit_dir() {
         assert_eq!(system_unit_dir(), PathBuf::from("/etc/systemd/system"));
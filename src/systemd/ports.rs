@@ -0,0 +1,239 @@
+//! Bound IP sockets from `/proc/net/{tcp,tcp6,udp,udp6}`
+//!
+//! Used to warn before a socket unit asks for a port another process already
+//! holds. Addresses in these files are hex dumps of the kernel's network byte
+//! order values, printed as native-endian 32-bit words.
+
+use crate::systemd::listen::ListenAddress;
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// `TCP_LISTEN` in the `st` column
+const TCP_LISTEN: &str = "0A";
+
+/// Lowest port an unprivileged process may bind unless the sysctl says otherwise
+const DEFAULT_UNPRIVILEGED_PORT_START: u16 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp => write!(f, "TCP"),
+            Self::Udp => write!(f, "UDP"),
+        }
+    }
+}
+
+/// A listening TCP socket or a bound UDP socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundSocket {
+    pub protocol: Protocol,
+    pub ip: IpAddr,
+    pub port: u16,
+}
+
+impl fmt::Display for BoundSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ip {
+            IpAddr::V4(ip) => write!(f, "{} {ip}:{}", self.protocol, self.port),
+            IpAddr::V6(ip) => write!(f, "{} [{ip}]:{}", self.protocol, self.port),
+        }
+    }
+}
+
+/// Sockets bound on the running system
+#[derive(Debug)]
+pub struct PortTable {
+    sockets: Vec<BoundSocket>,
+    /// `net.ipv4.ip_unprivileged_port_start`
+    pub unprivileged_port_start: u16,
+}
+
+impl Default for PortTable {
+    fn default() -> Self {
+        Self {
+            sockets: Vec::new(),
+            unprivileged_port_start: DEFAULT_UNPRIVILEGED_PORT_START,
+        }
+    }
+}
+
+impl PortTable {
+    /// Read the bound sockets, leaving out tables that aren't available
+    #[must_use]
+    pub fn current() -> Self {
+        let mut table = Self::default();
+        if let Some(start) = fs::read_to_string("/proc/sys/net/ipv4/ip_unprivileged_port_start")
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+        {
+            table.unprivileged_port_start = start;
+        }
+
+        for (file, protocol) in [
+            ("/proc/net/tcp", Protocol::Tcp),
+            ("/proc/net/tcp6", Protocol::Tcp),
+            ("/proc/net/udp", Protocol::Udp),
+            ("/proc/net/udp6", Protocol::Udp),
+        ] {
+            if let Ok(content) = fs::read_to_string(file) {
+                table.add(protocol, &content);
+            }
+        }
+        table
+    }
+
+    /// Add the sockets from one `/proc/net` table
+    ///
+    /// Only listening TCP sockets count; connections use ports too, but don't
+    /// stop another socket from listening.
+    pub fn add(&mut self, protocol: Protocol, content: &str) {
+        for line in content.lines().skip(1) {
+            let mut fields = line.split_whitespace().skip(1);
+            let (Some(local), Some(state)) = (fields.next(), fields.nth(1)) else {
+                continue;
+            };
+            if protocol == Protocol::Tcp && state != TCP_LISTEN {
+                continue;
+            }
+            if let Some((ip, port)) = parse_address(local) {
+                self.sockets.push(BoundSocket { protocol, ip, port });
+            }
+        }
+    }
+
+    /// A bound socket that would stop `address` from being bound
+    #[must_use]
+    pub fn conflict(&self, protocol: Protocol, address: &ListenAddress) -> Option<&BoundSocket> {
+        let (ip, port) = match address {
+            ListenAddress::Port(port) => (IpAddr::V6(Ipv6Addr::UNSPECIFIED), *port),
+            ListenAddress::Inet { ip, port, .. } => (*ip, *port),
+            _ => return None,
+        };
+        self.sockets
+            .iter()
+            .find(|s| s.protocol == protocol && s.port == port && overlaps(s.ip, ip))
+    }
+}
+
+/// Whether sockets on the two addresses compete for the same port
+///
+/// A wildcard covers every address of its family; an IPv6 wildcard is
+/// dual-stack and covers IPv4 as well.
+fn overlaps(a: IpAddr, b: IpAddr) -> bool {
+    match (a, b) {
+        (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+            a == b || a.is_unspecified() || b.is_unspecified()
+        }
+        (IpAddr::V4(_), IpAddr::V6(v6)) | (IpAddr::V6(v6), IpAddr::V4(_)) => v6.is_unspecified(),
+    }
+}
+
+/// Parse `0100007F:1F90` or the 32-digit IPv6 form
+fn parse_address(field: &str) -> Option<(IpAddr, u16)> {
+    let (ip, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let mut octets = Vec::with_capacity(16);
+    for chunk in ip.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        octets.extend_from_slice(&word.to_ne_bytes());
+    }
+    let ip = match octets.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3])),
+        16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(octets).ok()?)),
+        _ => return None,
+    };
+    Some((ip, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    /// Format an address the way the kernel does on this machine
+    fn hex(ip: IpAddr) -> String {
+        let octets = match ip {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+        octets.chunks(4).fold(String::new(), |mut hex, c| {
+            let _ = write!(hex, "{:08X}", u32::from_ne_bytes([c[0], c[1], c[2], c[3]]));
+            hex
+        })
+    }
+
+    fn line(ip: &str, port: u16, state: &str) -> String {
+        let ip: IpAddr = ip.parse().unwrap();
+        format!(
+            "   0: {}:{port:04X} 00000000:0000 {state} 00000000:00000000 00:00000000 00000000  1000        0 1234",
+            hex(ip)
+        )
+    }
+
+    fn table() -> PortTable {
+        let header = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode";
+        let tcp = [
+            header.to_string(),
+            line("127.0.0.1", 8080, "0A"),
+            line("10.0.0.5", 5432, "01"),
+            line("::", 443, "0A"),
+        ]
+        .join("\n");
+        let udp = [header.to_string(), line("0.0.0.0", 53, "07")].join("\n");
+
+        let mut table = PortTable::default();
+        table.add(Protocol::Tcp, &tcp);
+        table.add(Protocol::Udp, &udp);
+        table
+    }
+
+    fn conflict(protocol: Protocol, address: &str) -> Option<String> {
+        table()
+            .conflict(protocol, &address.parse().unwrap())
+            .map(ToString::to_string)
+    }
+
+    #[test]
+    fn test_parse_address() {
+        let ip: IpAddr = "192.168.1.20".parse().unwrap();
+        assert_eq!(parse_address(&format!("{}:0050", hex(ip))), Some((ip, 80)));
+        let ip: IpAddr = "fe80::1".parse().unwrap();
+        assert_eq!(parse_address(&format!("{}:01BB", hex(ip))), Some((ip, 443)));
+        assert_eq!(parse_address("nonsense"), None);
+    }
+
+    #[test]
+    fn test_conflict() {
+        assert_eq!(
+            conflict(Protocol::Tcp, "8080"),
+            Some("TCP 127.0.0.1:8080".to_string())
+        );
+        assert_eq!(
+            conflict(Protocol::Tcp, "0.0.0.0:8080"),
+            Some("TCP 127.0.0.1:8080".to_string())
+        );
+        assert_eq!(conflict(Protocol::Tcp, "127.0.0.2:8080"), None);
+        assert_eq!(conflict(Protocol::Tcp, "[::1]:8080"), None);
+        // The IPv6 wildcard is dual-stack
+        assert_eq!(
+            conflict(Protocol::Tcp, "192.168.1.1:443"),
+            Some("TCP [::]:443".to_string())
+        );
+        // Established connections and other protocols don't count
+        assert_eq!(conflict(Protocol::Tcp, "5432"), None);
+        assert_eq!(conflict(Protocol::Udp, "8080"), None);
+        assert_eq!(
+            conflict(Protocol::Udp, "53"),
+            Some("UDP 0.0.0.0:53".to_string())
+        );
+        assert_eq!(conflict(Protocol::Tcp, "/run/app.sock"), None);
+    }
+}
@@ -0,0 +1,446 @@
+//! Quoting and escaping of command lines and environment assignments
+//!
+//! systemd parses `ExecStart=` and `Environment=` values in three layers:
+//! specifiers (`%i`, `%%`) are resolved on the raw line, the line is split
+//! into words honouring quotes and C-style escapes, and for command lines
+//! `$VAR` references are expanded when the command is run. The functions here
+//! produce values that survive all three layers, and parse them back.
+
+use crate::error::{MkunitError, Result};
+use std::fmt::Write;
+
+/// How `%` and `$` in a value are treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expansion {
+    /// Escape `%` and `$` so the value reaches the process unchanged
+    Literal,
+    /// Leave `%` specifiers and `$VAR` references for systemd to expand
+    Systemd,
+}
+
+/// Characters systemd treats as word separators
+fn is_separator(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r')
+}
+
+/// Quote a single argument for use in an `Exec*=` command line
+#[must_use]
+pub fn quote_word(word: &str, expansion: Expansion) -> String {
+    // A bare semicolon is a command separator; `\;` passes it literally
+    if word == ";" {
+        return "\\;".to_string();
+    }
+
+    let needs_quotes = word.is_empty() || word.chars().any(|c| is_separator(c) || c == '\'');
+
+    let mut out = String::with_capacity(word.len() + 2);
+    if needs_quotes {
+        out.push('"');
+    }
+    for c in word.chars() {
+        match c {
+            '%' if expansion == Expansion::Literal => out.push_str("%%"),
+            '$' if expansion == Expansion::Literal => out.push_str("$$"),
+            ' ' => out.push(' '),
+            _ => push_escaped(&mut out, c),
+        }
+    }
+    if needs_quotes {
+        out.push('"');
+    }
+    out
+}
+
+/// Build an `Exec*=` command line from an argument vector
+#[must_use]
+pub fn exec_line(argv: &[String], expansion: Expansion) -> String {
+    argv.iter()
+        .map(|word| quote_word(word, expansion))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Build a quoted `Environment=` assignment
+///
+/// Environment values are never subject to `$` expansion, but `%` is still a
+/// specifier and is always escaped.
+pub fn env_assignment(key: &str, value: &str) -> Result<String> {
+    validate_env_name(key)?;
+
+    let mut out = String::with_capacity(key.len() + value.len() + 3);
+    out.push('"');
+    out.push_str(key);
+    out.push('=');
+    for c in value.chars() {
+        match c {
+            '%' => out.push_str("%%"),
+            ' ' => out.push(' '),
+            _ => push_escaped(&mut out, c),
+        }
+    }
+    out.push('"');
+    Ok(out)
+}
+
+/// Parse a `KEY=VALUE` argument and quote it for `Environment=`
+pub fn env_from_pair(pair: &str) -> Result<String> {
+    let (key, value) = pair.split_once('=').ok_or_else(|| {
+        MkunitError::invalid_argument(format!("Environment variable '{pair}' must be KEY=VALUE"))
+    })?;
+    env_assignment(key, value)
+}
+
+/// Check that a name is a valid environment variable name
+pub fn validate_env_name(key: &str) -> Result<()> {
+    let mut chars = key.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    if !valid_start || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(MkunitError::invalid_argument(format!(
+            "Invalid environment variable name '{key}'"
+        )));
+    }
+    Ok(())
+}
+
+/// Escape a character that is special inside or outside quotes
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '"' => out.push_str("\\\""),
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        c if c.is_control() => {
+            let code = u32::from(c);
+            if code < 0x80 {
+                let _ = write!(out, "\\x{code:02x}");
+            } else {
+                let _ = write!(out, "\\u{code:04x}");
+            }
+        }
+        c => out.push(c),
+    }
+}
+
+/// Split a shell-style command string into arguments
+///
+/// Supports single quotes, double quotes and backslash escapes the way a
+/// POSIX shell does, without any expansion.
+pub fn split_shell(input: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if is_separator(c) => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(unterminated(input)),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(unterminated(input)),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(unterminated(input)),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                match chars.next() {
+                    Some('\n') => {}
+                    Some(c) => current.push(c),
+                    None => current.push('\\'),
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(current);
+    }
+
+    Ok(words)
+}
+
+/// Parse an `Exec*=` command line back into an argument vector
+pub fn parse_exec(line: &str, expansion: Expansion) -> Result<Vec<String>> {
+    let line = if expansion == Expansion::Literal {
+        unescape_specifiers(line)
+    } else {
+        line.to_string()
+    };
+
+    let mut words = Vec::new();
+    let mut rest = line.as_str();
+    loop {
+        rest = rest.trim_start_matches(is_separator);
+        if rest.is_empty() {
+            break;
+        }
+
+        let token_end = rest.find(is_separator).unwrap_or(rest.len());
+        if &rest[..token_end] == ";" {
+            return Err(MkunitError::invalid_argument(
+                "Multiple commands separated by ';' are not supported",
+            ));
+        }
+        if &rest[..token_end] == "\\;" {
+            words.push(";".to_string());
+            rest = &rest[2..];
+            continue;
+        }
+
+        let (word, remaining) = extract_word(rest)?;
+        words.push(if expansion == Expansion::Literal {
+            word.replace("$$", "$")
+        } else {
+            word
+        });
+        rest = remaining;
+    }
+
+    Ok(words)
+}
+
+/// Parse an `Environment=` value into key/value pairs
+pub fn parse_environment(line: &str) -> Result<Vec<(String, String)>> {
+    let line = unescape_specifiers(line);
+
+    let mut pairs = Vec::new();
+    let mut rest = line.as_str();
+    loop {
+        rest = rest.trim_start_matches(is_separator);
+        if rest.is_empty() {
+            break;
+        }
+
+        let (word, remaining) = extract_word(rest)?;
+        let (key, value) = word.split_once('=').ok_or_else(|| {
+            MkunitError::invalid_argument(format!("Invalid environment assignment '{word}'"))
+        })?;
+        validate_env_name(key)?;
+        pairs.push((key.to_string(), value.to_string()));
+        rest = remaining;
+    }
+
+    Ok(pairs)
+}
+
+/// Resolve `%%` to `%`, leaving other specifiers untouched
+fn unescape_specifiers(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if c == '%' {
+            if let Some(&next) = chars.peek() {
+                chars.next();
+                if next != '%' {
+                    out.push(next);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Extract one word the way systemd does: quotes anywhere, C escapes everywhere
+fn extract_word(input: &str) -> Result<(String, &str)> {
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = input.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (None, c) if is_separator(c) => return Ok((word, &input[i..])),
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (_, '\\') => {
+                let (_, next) = chars.next().ok_or_else(|| {
+                    MkunitError::invalid_argument("Trailing backslash in command line")
+                })?;
+                word.push(unescape_char(next, &mut chars)?);
+            }
+            (_, c) => word.push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(unterminated(input));
+    }
+
+    Ok((word, ""))
+}
+
+/// Decode the C-style escape sequence starting after a backslash
+fn unescape_char(c: char, chars: &mut std::str::CharIndices<'_>) -> Result<char> {
+    let decoded = match c {
+        'a' => '\u{7}',
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'n' => '\n',
+        'r' => '\r',
+        's' => ' ',
+        't' => '\t',
+        'v' => '\u{b}',
+        '\\' | '"' | '\'' | ';' => c,
+        'x' => read_code(chars, 2, 16)?,
+        'u' => read_code(chars, 4, 16)?,
+        'U' => read_code(chars, 8, 16)?,
+        '0'..='7' => {
+            let rest = read_digits(chars, 2)?;
+            let code = u32::from_str_radix(&format!("{c}{rest}"), 8)
+                .map_err(|_| invalid_escape(&format!("\\{c}{rest}")))?;
+            char::from_u32(code).ok_or_else(|| invalid_escape(&format!("\\{c}{rest}")))?
+        }
+        other => return Err(invalid_escape(&format!("\\{other}"))),
+    };
+    Ok(decoded)
+}
+
+fn read_digits(chars: &mut std::str::CharIndices<'_>, count: usize) -> Result<String> {
+    let digits: String = chars.by_ref().take(count).map(|(_, c)| c).collect();
+    if digits.chars().count() != count {
+        return Err(invalid_escape(&digits));
+    }
+    Ok(digits)
+}
+
+fn read_code(chars: &mut std::str::CharIndices<'_>, count: usize, radix: u32) -> Result<char> {
+    let digits = read_digits(chars, count)?;
+    u32::from_str_radix(&digits, radix)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| invalid_escape(&digits))
+}
+
+fn invalid_escape(seq: &str) -> MkunitError {
+    MkunitError::invalid_argument(format!("Invalid escape sequence '{seq}'"))
+}
+
+fn unterminated(input: &str) -> MkunitError {
+    MkunitError::invalid_argument(format!("Unterminated quote in '{input}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_quote_word_plain() {
+        assert_eq!(
+            quote_word("/usr/bin/app", Expansion::Literal),
+            "/usr/bin/app"
+        );
+        assert_eq!(quote_word("", Expansion::Literal), "\"\"");
+    }
+
+    #[test]
+    fn test_quote_word_special_characters() {
+        assert_eq!(quote_word("a b", Expansion::Literal), "\"a b\"");
+        assert_eq!(
+            quote_word("say \"hi\"", Expansion::Literal),
+            "\"say \\\"hi\\\"\""
+        );
+        assert_eq!(quote_word("50%", Expansion::Literal), "50%%");
+        assert_eq!(quote_word("$HOME", Expansion::Literal), "$$HOME");
+        assert_eq!(quote_word("$HOME", Expansion::Systemd), "$HOME");
+        assert_eq!(quote_word(";", Expansion::Literal), "\\;");
+    }
+
+    #[test]
+    fn test_env_assignment() {
+        assert_eq!(env_assignment("FOO", "bar").unwrap(), "\"FOO=bar\"");
+        assert_eq!(
+            env_assignment("MSG", "100% \"done\"").unwrap(),
+            "\"MSG=100%% \\\"done\\\"\""
+        );
+        assert!(env_assignment("1BAD", "x").is_err());
+        assert!(env_from_pair("NOVALUE").is_err());
+    }
+
+    #[test]
+    fn test_split_shell() {
+        assert_eq!(
+            split_shell(r#"/bin/sh -c 'echo $HOME' "a \"b\"" c\ d"#).unwrap(),
+            vec!["/bin/sh", "-c", "echo $HOME", "a \"b\"", "c d"]
+        );
+        assert!(split_shell("echo 'unterminated").is_err());
+    }
+
+    #[test]
+    fn test_parse_exec() {
+        assert_eq!(
+            parse_exec(r#"/bin/echo "a b" c\x41 50%% $$X"#, Expansion::Literal).unwrap(),
+            vec!["/bin/echo", "a b", "cA", "50%", "$X"]
+        );
+        assert!(parse_exec("/bin/a ; /bin/b", Expansion::Literal).is_err());
+    }
+
+    #[test]
+    fn test_parse_environment() {
+        assert_eq!(
+            parse_environment(r#""A=1 2" B=x%%"#).unwrap(),
+            vec![
+                ("A".to_string(), "1 2".to_string()),
+                ("B".to_string(), "x%".to_string())
+            ]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_exec_round_trip(argv in prop::collection::vec(any::<String>(), 1..6)) {
+            prop_assume!(argv.iter().all(|w| !w.contains('\0')));
+            for expansion in [Expansion::Literal, Expansion::Systemd] {
+                let line = exec_line(&argv, expansion);
+                prop_assert_eq!(parse_exec(&line, expansion).unwrap(), argv.clone());
+            }
+        }
+
+        #[test]
+        fn prop_env_round_trip(key in "[A-Za-z_][A-Za-z0-9_]{0,10}", value in any::<String>()) {
+            prop_assume!(!value.contains('\0'));
+            let assignment = env_assignment(&key, &value).unwrap();
+            prop_assert_eq!(parse_environment(&assignment).unwrap(), vec![(key, value)]);
+        }
+
+        #[test]
+        fn prop_shell_split_of_exec_line(argv in prop::collection::vec("[a-z0-9 ]{1,8}", 1..5)) {
+            let line = exec_line(&argv, Expansion::Systemd);
+            prop_assert_eq!(split_shell(&line).unwrap(), argv);
+        }
+    }
+}
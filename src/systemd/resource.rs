@@ -0,0 +1,455 @@
+//! Resource control values (`MemoryMax=`, `CPUQuota=`, `TasksMax=`, ...)
+//!
+//! Values are parsed when the command line is read so a typo is reported
+//! before a unit is written, and are rendered back in the canonical form
+//! systemd documents in systemd.resource-control(5).
+
+use crate::error::{MkunitError, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Binary size suffixes understood by systemd, largest first
+const SIZE_SUFFIXES: [(char, u64); 5] = [
+    ('E', 1 << 60),
+    ('P', 1 << 50),
+    ('T', 1 << 40),
+    ('G', 1 << 30),
+    ('M', 1 << 20),
+];
+
+/// Smallest suffix, only used when parsing
+const KIBI: (char, u64) = ('K', 1 << 10);
+
+/// A percentage with two decimal places, stored in hundredths of a percent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Percent(u32);
+
+impl Percent {
+    /// Parse `50%` or `12.5%`; `None` if the value has no `%` suffix
+    fn parse(value: &str) -> Option<Result<Self>> {
+        let number = value.strip_suffix('%')?;
+        let invalid = || MkunitError::invalid_argument(format!("Invalid percentage '{value}'"));
+
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() || fraction.len() > 2 {
+            return Some(Err(invalid()));
+        }
+        let whole: u32 = match whole.parse() {
+            Ok(w) => w,
+            Err(_) => return Some(Err(invalid())),
+        };
+        let fraction: u32 = match format!("{fraction:0<2}").parse() {
+            Ok(f) => f,
+            Err(_) => return Some(Err(invalid())),
+        };
+
+        Some(
+            whole
+                .checked_mul(100)
+                .and_then(|w| w.checked_add(fraction))
+                .map(Self)
+                .ok_or_else(invalid),
+        )
+    }
+
+    /// Parse a percentage that must not exceed 100%
+    fn parse_bounded(value: &str) -> Option<Result<Self>> {
+        Self::parse(value).map(|p| {
+            p.and_then(|p| {
+                if p.0 > 100 * 100 {
+                    Err(MkunitError::invalid_argument(format!(
+                        "Percentage '{value}' is above 100%"
+                    )))
+                } else {
+                    Ok(p)
+                }
+            })
+        })
+    }
+}
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (whole, fraction) = (self.0 / 100, self.0 % 100);
+        match fraction {
+            0 => write!(f, "{whole}%"),
+            f10 if f10 % 10 == 0 => write!(f, "{whole}.{}%", f10 / 10),
+            _ => write!(f, "{whole}.{fraction:02}%"),
+        }
+    }
+}
+
+/// A memory limit (`MemoryMax=`, `MemoryHigh=`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteLimit {
+    Bytes(u64),
+    /// Percentage of physical memory
+    Percent(Percent),
+    Infinity,
+}
+
+impl FromStr for ByteLimit {
+    type Err = MkunitError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        if value == "infinity" {
+            return Ok(Self::Infinity);
+        }
+        if let Some(percent) = Percent::parse_bounded(value) {
+            return percent.map(Self::Percent);
+        }
+        parse_bytes(value).map(Self::Bytes)
+    }
+}
+
+impl fmt::Display for ByteLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(bytes) => write!(f, "{}", format_bytes(*bytes)),
+            Self::Percent(p) => write!(f, "{p}"),
+            Self::Infinity => write!(f, "infinity"),
+        }
+    }
+}
+
+/// Parse a byte size with an optional binary suffix (`512M`, `1.5G`)
+pub fn parse_bytes(value: &str) -> Result<u64> {
+    let invalid = || {
+        MkunitError::invalid_argument(format!(
+            "Invalid size '{value}', expected bytes with an optional K, M, G or T suffix"
+        ))
+    };
+
+    let (number, multiplier) = match value.chars().last() {
+        Some(c) if c.is_ascii_alphabetic() => {
+            let suffix = c.to_ascii_uppercase();
+            let multiplier = SIZE_SUFFIXES
+                .iter()
+                .chain(std::iter::once(&KIBI))
+                .find(|(s, _)| *s == suffix)
+                .map(|(_, m)| *m)
+                .ok_or_else(invalid)?;
+            (&value[..value.len() - 1], multiplier)
+        }
+        _ => (value, 1),
+    };
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let whole: u64 = whole.parse().map_err(|_| invalid())?;
+    let mut bytes = whole.checked_mul(multiplier).ok_or_else(invalid)?;
+
+    // Fractions like 1.5G are only meaningful with a suffix
+    if !fraction.is_empty() {
+        if multiplier == 1 {
+            return Err(invalid());
+        }
+        let digits = u32::try_from(fraction.len()).map_err(|_| invalid())?;
+        let scale = 10u64.checked_pow(digits).ok_or_else(invalid)?;
+        let numerator: u64 = fraction.parse().map_err(|_| invalid())?;
+        let extra = u128::from(numerator) * u128::from(multiplier) / u128::from(scale);
+        bytes = u64::try_from(extra)
+            .ok()
+            .and_then(|e| bytes.checked_add(e))
+            .ok_or_else(invalid)?;
+    }
+
+    Ok(bytes)
+}
+
+/// Format a byte count with the largest suffix that represents it exactly
+#[must_use]
+pub fn format_bytes(bytes: u64) -> String {
+    for (suffix, multiplier) in SIZE_SUFFIXES.iter().chain(std::iter::once(&KIBI)) {
+        if bytes >= *multiplier && bytes.is_multiple_of(*multiplier) {
+            return format!("{}{suffix}", bytes / multiplier);
+        }
+    }
+    bytes.to_string()
+}
+
+/// A CPU quota relative to one CPU (`CPUQuota=200%` is two full CPUs)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuQuota(Percent);
+
+impl FromStr for CpuQuota {
+    type Err = MkunitError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let percent = Percent::parse(value).ok_or_else(|| {
+            MkunitError::invalid_argument(format!(
+                "Invalid CPU quota '{value}', expected a percentage such as 50% or 200%"
+            ))
+        })??;
+        if percent.0 == 0 {
+            return Err(MkunitError::invalid_argument("CPU quota must be above 0%"));
+        }
+        Ok(Self(percent))
+    }
+}
+
+impl fmt::Display for CpuQuota {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A relative weight (`CPUWeight=`, `IOWeight=`), 1 to 10000 with 100 as the default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weight(u16);
+
+impl FromStr for Weight {
+    type Err = MkunitError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.parse::<u16>() {
+            Ok(w) if (1..=10000).contains(&w) => Ok(Self(w)),
+            _ => Err(MkunitError::invalid_argument(format!(
+                "Invalid weight '{value}', expected a number from 1 to 10000"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Weight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A task limit (`TasksMax=`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TasksLimit {
+    Count(u64),
+    /// Percentage of the system-wide task limit
+    Percent(Percent),
+    Infinity,
+}
+
+impl FromStr for TasksLimit {
+    type Err = MkunitError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        if value == "infinity" {
+            return Ok(Self::Infinity);
+        }
+        if let Some(percent) = Percent::parse_bounded(value) {
+            return percent.map(Self::Percent);
+        }
+        match value.parse::<u64>() {
+            Ok(n) if n > 0 => Ok(Self::Count(n)),
+            _ => Err(MkunitError::invalid_argument(format!(
+                "Invalid task limit '{value}', expected a count, percentage or 'infinity'"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for TasksLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Count(n) => write!(f, "{n}"),
+            Self::Percent(p) => write!(f, "{p}"),
+            Self::Infinity => write!(f, "infinity"),
+        }
+    }
+}
+
+/// A file descriptor limit (`LimitNOFILE=`), either `N` or `SOFT:HARD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileLimit {
+    /// `None` is `infinity`
+    soft: Option<u64>,
+    hard: Option<u64>,
+}
+
+impl FromStr for FileLimit {
+    type Err = MkunitError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let parse_one = |part: &str| -> Result<Option<u64>> {
+            if part == "infinity" {
+                return Ok(None);
+            }
+            part.parse::<u64>().map(Some).map_err(|_| {
+                MkunitError::invalid_argument(format!(
+                    "Invalid file limit '{value}', expected a number, 'infinity' or SOFT:HARD"
+                ))
+            })
+        };
+
+        let (soft, hard) = if let Some((soft, hard)) = value.split_once(':') {
+            (parse_one(soft)?, parse_one(hard)?)
+        } else {
+            let limit = parse_one(value)?;
+            (limit, limit)
+        };
+
+        let soft_above_hard = match (soft, hard) {
+            (Some(s), Some(h)) => s > h,
+            (None, Some(_)) => true,
+            _ => false,
+        };
+        if soft_above_hard {
+            return Err(MkunitError::invalid_argument(format!(
+                "Soft file limit in '{value}' is above the hard limit"
+            )));
+        }
+
+        Ok(Self { soft, hard })
+    }
+}
+
+impl fmt::Display for FileLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show =
+            |limit: Option<u64>| limit.map_or_else(|| "infinity".to_string(), |n| n.to_string());
+        if self.soft == self.hard {
+            write!(f, "{}", show(self.soft))
+        } else {
+            write!(f, "{}:{}", show(self.soft), show(self.hard))
+        }
+    }
+}
+
+/// Normalize a slice name, adding the `.slice` suffix if it is missing
+pub fn slice_name(name: &str) -> Result<String> {
+    let stem = name.strip_suffix(".slice").unwrap_or(name);
+    let name = format!("{stem}.slice");
+
+    let valid = !stem.is_empty()
+        && !stem.starts_with('-')
+        && !stem.ends_with('-')
+        && !stem.contains("--")
+        && stem
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ":_.\\-".contains(c));
+    if !valid {
+        return Err(MkunitError::invalid_argument(format!(
+            "Invalid slice name '{name}'. Nested slices are separated by single dashes, \
+             e.g. 'apps-web.slice'"
+        )));
+    }
+
+    Ok(name)
+}
+
+/// Preset limits offered when creating a service interactively
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceProfile {
+    None,
+    Small,
+    Medium,
+    Large,
+}
+
+/// Limits applied by a [`ResourceProfile`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileLimits {
+    pub memory_high: ByteLimit,
+    pub memory_max: ByteLimit,
+    pub cpu_quota: CpuQuota,
+    pub tasks_max: TasksLimit,
+}
+
+impl ResourceProfile {
+    pub const ALL: [Self; 4] = [Self::None, Self::Small, Self::Medium, Self::Large];
+
+    /// Label shown in the selection prompt
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::None => "No limits",
+            Self::Small => "Small (512M memory, half a CPU, 256 tasks)",
+            Self::Medium => "Medium (2G memory, 2 CPUs, 1024 tasks)",
+            Self::Large => "Large (8G memory, 4 CPUs, 4096 tasks)",
+        }
+    }
+
+    /// The limits for this profile, `None` for no limits
+    #[must_use]
+    pub fn limits(self) -> Option<ProfileLimits> {
+        let (max, cpu, tasks) = match self {
+            Self::None => return None,
+            Self::Small => (512 << 20, 50, 256),
+            Self::Medium => (2 << 30, 200, 1024),
+            Self::Large => (8 << 30, 400, 4096),
+        };
+
+        Some(ProfileLimits {
+            // Start reclaiming before the hard limit is hit
+            memory_high: ByteLimit::Bytes(max / 4 * 3),
+            memory_max: ByteLimit::Bytes(max),
+            cpu_quota: CpuQuota(Percent(cpu * 100)),
+            tasks_max: TasksLimit::Count(tasks),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(parse_bytes("1024").unwrap(), 1024);
+        assert_eq!(parse_bytes("512M").unwrap(), 512 << 20);
+        assert_eq!(parse_bytes("1.5G").unwrap(), 3 << 29);
+        assert_eq!(parse_bytes("4k").unwrap(), 4096);
+        assert!(parse_bytes("12X").is_err());
+        assert!(parse_bytes("1.5").is_err());
+        assert!(parse_bytes("G").is_err());
+        assert!(parse_bytes("-1M").is_err());
+    }
+
+    #[test]
+    fn test_byte_limit_display() {
+        assert_eq!("1536M".parse::<ByteLimit>().unwrap().to_string(), "1536M");
+        assert_eq!("1.5G".parse::<ByteLimit>().unwrap().to_string(), "1536M");
+        assert_eq!("2048M".parse::<ByteLimit>().unwrap().to_string(), "2G");
+        assert_eq!("1000".parse::<ByteLimit>().unwrap().to_string(), "1000");
+        assert_eq!("12.5%".parse::<ByteLimit>().unwrap().to_string(), "12.5%");
+        assert_eq!(
+            "infinity".parse::<ByteLimit>().unwrap(),
+            ByteLimit::Infinity
+        );
+        assert!("150%".parse::<ByteLimit>().is_err());
+    }
+
+    #[test]
+    fn test_cpu_quota() {
+        assert_eq!("200%".parse::<CpuQuota>().unwrap().to_string(), "200%");
+        assert_eq!("0.25%".parse::<CpuQuota>().unwrap().to_string(), "0.25%");
+        assert!("0%".parse::<CpuQuota>().is_err());
+        assert!("50".parse::<CpuQuota>().is_err());
+        assert!("1.234%".parse::<CpuQuota>().is_err());
+    }
+
+    #[test]
+    fn test_weights_and_limits() {
+        assert!("100".parse::<Weight>().is_ok());
+        assert!("0".parse::<Weight>().is_err());
+        assert!("10001".parse::<Weight>().is_err());
+
+        assert_eq!("512".parse::<TasksLimit>().unwrap(), TasksLimit::Count(512));
+        assert!("0".parse::<TasksLimit>().is_err());
+
+        assert_eq!("65536".parse::<FileLimit>().unwrap().to_string(), "65536");
+        assert_eq!(
+            "1024:infinity".parse::<FileLimit>().unwrap().to_string(),
+            "1024:infinity"
+        );
+        assert!("4096:1024".parse::<FileLimit>().is_err());
+    }
+
+    #[test]
+    fn test_slice_name() {
+        assert_eq!(slice_name("apps").unwrap(), "apps.slice");
+        assert_eq!(slice_name("apps-web.slice").unwrap(), "apps-web.slice");
+        assert!(slice_name("apps--web").is_err());
+        assert!(slice_name("-apps").is_err());
+        assert!(slice_name("my apps").is_err());
+    }
+}
@@ -0,0 +1,499 @@
+//! Plain English schedules for `timer --every`
+//!
+//! Phrases like `weekday at 9am`, `first monday of the month at 02:30` or
+//! `15 minutes` are turned into a calendar expression for `OnCalendar=`, or
+//! into an interval for `OnUnitActiveSec=`. A leading `every` is optional.
+//! [`describe`] goes the other way for the shapes `parse` produces, and
+//! [`parse_at`] reads the single point in time for `mkunit at`.
+
+use crate::error::{MkunitError, Result};
+use crate::systemd::calendar::CalendarSpec;
+use crate::systemd::timespan::Timespan;
+use jiff::civil::DateTime;
+use jiff::Zoned;
+
+const AT_EXAMPLES: &str =
+    "e.g., '2h', 'in 30 minutes', '03:00', 'tomorrow 9am', '2026-12-24 18:00'";
+const EXAMPLES: &str = "e.g., 'weekday at 9am', '15 minutes', 'first monday of the month at 02:30'";
+
+/// Weekday spellings, in systemd order starting on Monday
+const WEEKDAYS: [(&str, &[&str]); 7] = [
+    ("Mon", &["mon", "monday"]),
+    ("Tue", &["tue", "tues", "tuesday"]),
+    ("Wed", &["wed", "wednesday"]),
+    ("Thu", &["thu", "thurs", "thursday"]),
+    ("Fri", &["fri", "friday"]),
+    ("Sat", &["sat", "saturday"]),
+    ("Sun", &["sun", "sunday"]),
+];
+
+/// Ordinal words, for `first monday` and `15th of the month`
+const ORDINALS: [&str; 5] = ["first", "second", "third", "fourth", "fifth"];
+
+/// Words that don't change the meaning of a phrase
+const FILLER: &[&str] = &["every", "each", "on", "the", "and", "of", "in"];
+
+/// A parsed schedule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// Normalized calendar expression for `OnCalendar=`
+    Calendar(String),
+    /// Time between runs, for `OnUnitActiveSec=`
+    Interval(Timespan),
+}
+
+/// Parse a plain English schedule
+pub fn parse(input: &str) -> Result<Schedule> {
+    let invalid = |reason: &str| {
+        MkunitError::invalid_argument(format!(
+            "Cannot understand schedule '{input}': {reason} ({EXAMPLES})"
+        ))
+    };
+
+    let text = input.trim().to_lowercase();
+    let text = text.strip_prefix("every ").unwrap_or(&text).trim();
+    if text.is_empty() {
+        return Err(invalid("empty"));
+    }
+
+    // "15 minutes", "2h", "1h 30min", but not "15th of the month"
+    let first_word = text.split_whitespace().next().unwrap_or_default();
+    if text.starts_with(|c: char| c.is_ascii_digit())
+        && !text.contains(" at ")
+        && ordinal_number(first_word).is_none()
+    {
+        let span: Timespan = text.parse().map_err(|_| invalid("unknown interval"))?;
+        if span.is_zero() {
+            return Err(invalid("the interval must be longer than zero"));
+        }
+        return Ok(Schedule::Interval(span));
+    }
+
+    match text {
+        "minute" | "minutely" => return calendar("*-*-* *:*:00", &invalid),
+        "hour" | "hourly" => return calendar("*-*-* *:00:00", &invalid),
+        _ => {}
+    }
+
+    let (days, time) = if let Some(time) = text.strip_prefix("at ") {
+        ("", time)
+    } else if let Some((days, time)) = text.rsplit_once(" at ") {
+        (days, time)
+    } else {
+        (text, "midnight")
+    };
+
+    let time = parse_time(time).ok_or_else(|| invalid(&format!("unknown time '{time}'")))?;
+    let days = parse_days(days).ok_or_else(|| invalid(&format!("unknown days '{days}'")))?;
+    calendar(&format!("{days} {time}"), &invalid)
+}
+
+/// Check and normalize a calendar expression built from a phrase
+fn calendar(expr: &str, invalid: &dyn Fn(&str) -> MkunitError) -> Result<Schedule> {
+    let spec = CalendarSpec::parse(expr.trim()).map_err(|e| invalid(&e.to_string()))?;
+    Ok(Schedule::Calendar(spec.to_string()))
+}
+
+/// Parse `9am`, `9:30 pm`, `14:05`, `noon` or `midnight` into `HH:MM:SS`
+fn parse_time(text: &str) -> Option<String> {
+    let text: String = text.split_whitespace().collect();
+    match text.as_str() {
+        "midnight" => return Some("00:00:00".to_string()),
+        "noon" | "midday" => return Some("12:00:00".to_string()),
+        _ => {}
+    }
+
+    let (clock, offset) = if let Some(clock) = text.strip_suffix("am") {
+        (clock, Some(0))
+    } else if let Some(clock) = text.strip_suffix("pm") {
+        (clock, Some(12))
+    } else {
+        (text.as_str(), None)
+    };
+
+    let mut parts = clock.split(':');
+    let mut hour: u32 = parts.next()?.parse().ok()?;
+    let minute: u32 = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    let second: u32 = parts.next().map_or(Some(0), |s| s.parse().ok())?;
+    if parts.next().is_some() || minute > 59 || second > 59 {
+        return None;
+    }
+
+    if let Some(offset) = offset {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        hour = hour % 12 + offset;
+    } else if clock.len() < 3 {
+        // A bare number like "9" is ambiguous without am/pm or minutes
+        return None;
+    }
+    if hour > 23 {
+        return None;
+    }
+    Some(format!("{hour:02}:{minute:02}:{second:02}"))
+}
+
+/// Parse the day part of a phrase into `[WEEKDAYS] DATE`
+fn parse_days(text: &str) -> Option<String> {
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty() && !FILLER.contains(w))
+        .collect();
+
+    let simple = match words.as_slice() {
+        [] | ["day" | "days" | "daily"] => Some("*-*-*"),
+        ["weekday" | "weekdays"] => Some("Mon..Fri *-*-*"),
+        ["weekend" | "weekends"] => Some("Sat,Sun *-*-*"),
+        ["week" | "weekly"] => Some("Mon *-*-*"),
+        ["month" | "monthly"] => Some("*-*-01"),
+        ["year" | "yearly" | "annually"] => Some("*-01-01"),
+        ["last", "day", "month"] => Some("*-*~01"),
+        _ => None,
+    };
+    if let Some(days) = simple {
+        return Some(days.to_string());
+    }
+
+    match words.as_slice() {
+        // "first monday of the month", "last friday of every month"
+        [ordinal, day, "month"] if weekday(day).is_some() => {
+            let day = weekday(day)?;
+            if *ordinal == "last" {
+                return Some(format!("{day} *-*~01..07"));
+            }
+            let n = ordinal_number(ordinal).filter(|n| *n <= 4)?;
+            let first = (n - 1) * 7 + 1;
+            Some(format!("{day} *-*-{first:02}..{:02}", first + 6))
+        }
+        // "15th of the month", "1st of every month"
+        [ordinal, "month"] | [ordinal, "day", "month"] => {
+            let n = ordinal_number(ordinal).filter(|n| *n <= 31)?;
+            Some(format!("*-*-{n:02}"))
+        }
+        // "monday", "mon and thu", "tuesdays, fridays"
+        _ => {
+            let days = words
+                .iter()
+                .map(|w| weekday(w))
+                .collect::<Option<Vec<_>>>()?;
+            Some(format!("{} *-*-*", days.join(",")))
+        }
+    }
+}
+
+/// systemd name of a weekday, accepting plurals like `mondays`
+fn weekday(word: &str) -> Option<&'static str> {
+    let singular = word.strip_suffix('s').unwrap_or(word);
+    WEEKDAYS
+        .iter()
+        .find(|(_, names)| names.contains(&word) || names.contains(&singular))
+        .map(|(name, _)| *name)
+}
+
+/// Number of an ordinal like `first`, `2nd` or `15th`
+fn ordinal_number(word: &str) -> Option<u32> {
+    if let Some(index) = ORDINALS.iter().position(|o| *o == word) {
+        return u32::try_from(index + 1).ok();
+    }
+    let digits = word
+        .strip_suffix("st")
+        .or_else(|| word.strip_suffix("nd"))
+        .or_else(|| word.strip_suffix("rd"))
+        .or_else(|| word.strip_suffix("th"))?;
+    digits.parse().ok().filter(|n| *n > 0)
+}
+
+/// Parse a single point in time after `now`
+///
+/// Accepts a delay (`2h`, `in 30 minutes`, `now + 1 day`), a clock time with
+/// an optional `today`, `tonight` or `tomorrow` (`03:00`, `tomorrow 9am`), or
+/// a date (`2026-12-24 18:00`). A clock time without a day that has already
+/// passed today means tomorrow.
+pub fn parse_at(input: &str, now: &Zoned) -> Result<Zoned> {
+    let invalid = |reason: &str| {
+        MkunitError::invalid_argument(format!(
+            "Cannot understand time '{input}': {reason} ({AT_EXAMPLES})"
+        ))
+    };
+
+    let text = input.trim().to_lowercase();
+    let text = text.strip_prefix("at ").unwrap_or(&text).trim();
+    if text.is_empty() {
+        return Err(invalid("empty"));
+    }
+
+    let delay = ["in ", "now +", "now+", "+"]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix));
+    let when = if let Some(delay) = delay {
+        let span: Timespan = delay.trim().parse().map_err(|_| invalid("unknown delay"))?;
+        after(now, span).ok_or_else(|| invalid("the delay is too long"))?
+    } else if let Some(when) = clock_time(text, now) {
+        when
+    } else if let Ok(date) = text.parse::<DateTime>() {
+        date.to_zoned(now.time_zone().clone())
+            .map_err(|e| invalid(&e.to_string()))?
+    } else if let Ok(span) = text.parse::<Timespan>() {
+        after(now, span).ok_or_else(|| invalid("the delay is too long"))?
+    } else {
+        return Err(invalid("unknown time"));
+    };
+
+    if when <= *now {
+        return Err(invalid("that is in the past"));
+    }
+    Ok(when)
+}
+
+fn after(now: &Zoned, span: Timespan) -> Option<Zoned> {
+    now.checked_add(span.to_duration()?).ok()
+}
+
+/// `03:00`, `9am tomorrow`, `tonight at 11pm`, `today noon`
+fn clock_time(text: &str, now: &Zoned) -> Option<Zoned> {
+    let mut day = None;
+    let mut clock = Vec::new();
+    for word in text.split_whitespace() {
+        match word {
+            "today" | "tonight" | "tomorrow" if day.is_none() => day = Some(word),
+            "at" => {}
+            _ => clock.push(word),
+        }
+    }
+
+    let time = parse_time(&clock.join(" "))?;
+    let mut parts = time.split(':').map(|p| p.parse::<i8>().ok());
+    let (hour, minute, second) = (parts.next()??, parts.next()??, parts.next()??);
+
+    let mut date = now.date();
+    if day == Some("tomorrow") {
+        date = date.tomorrow().ok()?;
+    }
+    let when = date
+        .at(hour, minute, second, 0)
+        .to_zoned(now.time_zone().clone())
+        .ok()?;
+
+    // "03:00 tonight" is the coming night, like a bare "03:00"
+    if when <= *now && matches!(day, None | Some("tonight")) {
+        return when.tomorrow().ok();
+    }
+    Some(when)
+}
+
+/// Describe a calendar expression in plain English
+///
+/// Falls back to the normalized expression when it has no simple wording.
+#[must_use]
+pub fn describe(spec: &CalendarSpec) -> String {
+    let normalized = spec.to_string();
+    describe_normalized(&normalized).unwrap_or(normalized)
+}
+
+fn describe_normalized(expr: &str) -> Option<String> {
+    let tokens: Vec<&str> = expr.split(' ').collect();
+    let (weekdays, date, time) = match tokens.as_slice() {
+        [date, time] => (None, *date, *time),
+        [weekdays, date, time] if weekdays.starts_with(char::is_alphabetic) => {
+            (Some(*weekdays), *date, *time)
+        }
+        // Anything with a time zone keeps its expression
+        _ => return None,
+    };
+
+    match (weekdays, date, time) {
+        (None, "*-*-*", "*:*:00") => return Some("every minute".to_string()),
+        (None, "*-*-*", "*:00:00") => return Some("every hour".to_string()),
+        _ => {}
+    }
+    if date == "*-*-*" && weekdays.is_none() {
+        let repeat = |part: Option<&str>| part?.strip_prefix("00/")?.parse::<u32>().ok();
+        if let Some(n) = repeat(time.strip_prefix("*:").and_then(|t| t.strip_suffix(":00"))) {
+            return Some(format!("every {n} minutes"));
+        }
+        if let Some(n) = repeat(time.strip_suffix(":00:00")) {
+            return Some(format!("every {n} hours"));
+        }
+    }
+
+    let (hour, minute, second) = match time.split(':').collect::<Vec<_>>().as_slice() {
+        [h, m, s]
+            if [h, m, s]
+                .iter()
+                .all(|p| p.bytes().all(|b| b.is_ascii_digit())) =>
+        {
+            (*h, *m, *s)
+        }
+        _ => return None,
+    };
+    let at = if second == "00" {
+        format!("{hour}:{minute}")
+    } else {
+        format!("{hour}:{minute}:{second}")
+    };
+
+    let days = match (weekdays, date) {
+        (None, "*-*-*") => "every day".to_string(),
+        (None, "*-01-01") => "every year on 1 January".to_string(),
+        (None, "*-*~01") => "last day of the month".to_string(),
+        (Some("Mon..Fri"), "*-*-*") => "weekdays".to_string(),
+        (Some("Sat,Sun"), "*-*-*") => "weekends".to_string(),
+        (Some(days), "*-*-*") => format!("every {days}"),
+        (Some(day), "*-*~01..07") if !day.contains([',', '.']) => {
+            format!("last {day} of the month")
+        }
+        (Some(day), date) if !day.contains([',', '.']) => {
+            let range = date.strip_prefix("*-*-")?;
+            let (first, last) = range.split_once("..")?;
+            let first: usize = first.parse().ok()?;
+            let last: usize = last.parse().ok()?;
+            if last != first + 6 || first % 7 != 1 {
+                return None;
+            }
+            format!("{} {day} of the month", ORDINALS.get(first / 7)?)
+        }
+        (None, date) => {
+            let day: u32 = date.strip_prefix("*-*-")?.parse().ok()?;
+            format!("{} of the month", ordinal_suffix(day))
+        }
+        _ => return None,
+    };
+
+    Some(format!("{days} at {at}"))
+}
+
+/// `1st`, `2nd`, `11th`, `23rd`
+fn ordinal_suffix(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar_of(input: &str) -> String {
+        match parse(input).unwrap() {
+            Schedule::Calendar(expr) => expr,
+            Schedule::Interval(span) => panic!("expected a calendar, got {span}"),
+        }
+    }
+
+    #[test]
+    fn test_calendar_phrases() {
+        assert_eq!(calendar_of("weekday at 9am"), "Mon..Fri *-*-* 09:00:00");
+        assert_eq!(calendar_of("every day at 14:30"), "*-*-* 14:30:00");
+        assert_eq!(calendar_of("at noon"), "*-*-* 12:00:00");
+        assert_eq!(
+            calendar_of("Weekends at 10:15 PM"),
+            "Sat,Sun *-*-* 22:15:00"
+        );
+        assert_eq!(
+            calendar_of("mon, wed and fri at 6pm"),
+            "Mon,Wed,Fri *-*-* 18:00:00"
+        );
+        assert_eq!(calendar_of("sundays"), "Sun *-*-* 00:00:00");
+        assert_eq!(calendar_of("hour"), "*-*-* *:00:00");
+        assert_eq!(calendar_of("month"), "*-*-01 00:00:00");
+        assert_eq!(
+            calendar_of("first monday of the month at 02:30"),
+            "Mon *-*-01..07 02:30:00"
+        );
+        assert_eq!(
+            calendar_of("third thursday of every month at 12am"),
+            "Thu *-*-15..21 00:00:00"
+        );
+        assert_eq!(
+            calendar_of("last friday of the month at 5pm"),
+            "Fri *-*~01..07 17:00:00"
+        );
+        assert_eq!(calendar_of("15th of the month at 8:00"), "*-*-15 08:00:00");
+        assert_eq!(calendar_of("last day of the month"), "*-*~01 00:00:00");
+    }
+
+    #[test]
+    fn test_intervals() {
+        assert_eq!(
+            parse("15 minutes").unwrap(),
+            Schedule::Interval("15min".parse().unwrap())
+        );
+        assert_eq!(
+            parse("every 2h").unwrap(),
+            Schedule::Interval("2h".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let describe_expr = |expr: &str| describe(&CalendarSpec::parse(expr).unwrap());
+        assert_eq!(describe_expr("Mon..Fri 09:00"), "weekdays at 09:00");
+        assert_eq!(describe_expr("daily"), "every day at 00:00");
+        assert_eq!(describe_expr("hourly"), "every hour");
+        assert_eq!(describe_expr("Sat,Sun 10:15:30"), "weekends at 10:15:30");
+        assert_eq!(describe_expr("Mon,Thu 18:00"), "every Mon,Thu at 18:00");
+        assert_eq!(
+            describe_expr("Tue *-*-08..14 02:30"),
+            "second Tue of the month at 02:30"
+        );
+        assert_eq!(
+            describe_expr("Fri *-*~01..07 17:00"),
+            "last Fri of the month at 17:00"
+        );
+        assert_eq!(describe_expr("*-*-22 06:00"), "22nd of the month at 06:00");
+        assert_eq!(describe_expr("*:0/15"), "every 15 minutes");
+        assert_eq!(describe_expr("0/6:00"), "every 6 hours");
+        assert_eq!(describe_expr("*:5/15"), "*-*-* *:05/15:00");
+        assert_eq!(describe_expr("daily UTC"), "*-*-* 00:00:00 UTC");
+
+        // Everything parse produces reads back
+        for phrase in [
+            "weekday at 9am",
+            "first monday of the month at 02:30",
+            "11th of the month",
+        ] {
+            let Schedule::Calendar(expr) = parse(phrase).unwrap() else {
+                panic!("expected a calendar");
+            };
+            assert!(!describe_expr(&expr).contains('*'), "{phrase}");
+        }
+    }
+
+    #[test]
+    fn test_parse_at() {
+        let now: Zoned = "2026-03-02T22:15:00[UTC]".parse().unwrap();
+        let at = |input: &str| parse_at(input, &now).unwrap().to_string();
+        assert_eq!(at("2h"), "2026-03-03T00:15:00+00:00[UTC]");
+        assert_eq!(at("in 30 minutes"), "2026-03-02T22:45:00+00:00[UTC]");
+        assert_eq!(at("now + 1 day"), "2026-03-03T22:15:00+00:00[UTC]");
+        assert_eq!(at("23:00"), "2026-03-02T23:00:00+00:00[UTC]");
+        assert_eq!(at("03:00 tonight"), "2026-03-03T03:00:00+00:00[UTC]");
+        assert_eq!(at("at 9am"), "2026-03-03T09:00:00+00:00[UTC]");
+        assert_eq!(at("tomorrow noon"), "2026-03-03T12:00:00+00:00[UTC]");
+        assert_eq!(at("2026-12-24 18:00"), "2026-12-24T18:00:00+00:00[UTC]");
+
+        assert!(parse_at("", &now).is_err());
+        assert!(parse_at("today 9am", &now).is_err());
+        assert!(parse_at("2025-01-01 00:00", &now).is_err());
+        assert!(parse_at("in 5 fortnights", &now).is_err());
+        assert!(parse_at("whenever", &now).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("0 minutes").is_err());
+        assert!(parse("5 fortnights").is_err());
+        assert!(parse("someday at 9am").is_err());
+        assert!(parse("weekday at 13pm").is_err());
+        assert!(parse("day at 9").is_err());
+        assert!(parse("fifth monday of the month").is_err());
+        assert!(parse("32nd of the month").is_err());
+    }
+}
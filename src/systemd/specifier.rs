@@ -0,0 +1,554 @@
+//! Unit file specifiers (`%i`, `%h`, `%n`, ...)
+//!
+//! Specifiers are resolved by systemd when a unit is loaded. An unknown
+//! specifier makes the whole setting invalid, so the table here is used both
+//! to validate unit files and to preview what values will expand to.
+
+use crate::systemd::escape;
+use crate::systemd::version::SystemdVersion;
+use std::env;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+
+/// How a specifier resolves for the system and user service managers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeRule {
+    /// Resolves to the same value for both managers
+    Same,
+    /// Resolves differently; describes the system and user values
+    PerManager {
+        system: &'static str,
+        user: &'static str,
+    },
+}
+
+/// A known specifier
+#[derive(Debug, Clone, Copy)]
+pub struct Specifier {
+    pub code: char,
+    pub meaning: &'static str,
+    pub scope: ScopeRule,
+    /// Only meaningful in instantiated template units; empty elsewhere
+    pub instance_only: bool,
+    /// First systemd version that understands the specifier, 0 if it predates
+    /// every supported version
+    pub since: u32,
+}
+
+const fn spec(code: char, meaning: &'static str, since: u32) -> Specifier {
+    Specifier {
+        code,
+        meaning,
+        scope: ScopeRule::Same,
+        instance_only: false,
+        since,
+    }
+}
+
+const fn per_manager(
+    code: char,
+    meaning: &'static str,
+    system: &'static str,
+    user: &'static str,
+    since: u32,
+) -> Specifier {
+    Specifier {
+        code,
+        meaning,
+        scope: ScopeRule::PerManager { system, user },
+        instance_only: false,
+        since,
+    }
+}
+
+const fn instance(code: char, meaning: &'static str) -> Specifier {
+    Specifier {
+        code,
+        meaning,
+        scope: ScopeRule::Same,
+        instance_only: true,
+        since: 0,
+    }
+}
+
+/// All specifiers understood by systemd, see systemd.unit(5)
+pub const SPECIFIERS: &[Specifier] = &[
+    spec('a', "Architecture", 0),
+    spec('A', "Operating system image version", 248),
+    spec('b', "Boot ID", 0),
+    spec('B', "Operating system build ID", 248),
+    per_manager(
+        'C',
+        "Cache directory root",
+        "/var/cache",
+        "$XDG_CACHE_HOME",
+        236,
+    ),
+    per_manager(
+        'd',
+        "Credentials directory",
+        "/run/credentials/<unit>",
+        "$XDG_RUNTIME_DIR/credentials/<unit>",
+        249,
+    ),
+    per_manager(
+        'D',
+        "Shared data directory root",
+        "/usr/share",
+        "$XDG_DATA_HOME",
+        256,
+    ),
+    per_manager(
+        'E',
+        "Configuration directory root",
+        "/etc",
+        "$XDG_CONFIG_HOME",
+        236,
+    ),
+    spec('f', "Unescaped filename", 0),
+    per_manager('g', "User group", "root", "group of the user manager", 249),
+    per_manager('G', "User GID", "0", "GID of the user manager", 249),
+    per_manager('h', "User home directory", "/root", "$HOME", 0),
+    spec('H', "Host name", 0),
+    instance('i', "Instance name"),
+    instance('I', "Unescaped instance name"),
+    spec('j', "Final component of the prefix", 236),
+    spec('J', "Unescaped final component of the prefix", 236),
+    spec('l', "Short host name", 249),
+    per_manager(
+        'L',
+        "Log directory root",
+        "/var/log",
+        "$XDG_STATE_HOME/log",
+        236,
+    ),
+    spec('m', "Machine ID", 0),
+    spec('M', "Operating system image identifier", 248),
+    spec('n', "Full unit name", 0),
+    spec('N', "Full unit name without type suffix", 236),
+    spec('o', "Operating system ID", 248),
+    spec('p', "Prefix name", 0),
+    spec('P', "Unescaped prefix name", 0),
+    spec('q', "Pretty host name", 249),
+    per_manager('s', "User shell", "/bin/sh", "$SHELL", 0),
+    per_manager(
+        'S',
+        "State directory root",
+        "/var/lib",
+        "$XDG_STATE_HOME",
+        236,
+    ),
+    per_manager('t', "Runtime directory root", "/run", "$XDG_RUNTIME_DIR", 0),
+    spec('T', "Directory for temporary files", 236),
+    per_manager('u', "User name", "root", "name of the user manager", 0),
+    per_manager('U', "User UID", "0", "UID of the user manager", 0),
+    spec('v', "Kernel release", 0),
+    spec(
+        'V',
+        "Directory for larger and persistent temporary files",
+        236,
+    ),
+    spec('w', "Operating system version ID", 248),
+    spec('W', "Operating system variant ID", 248),
+    spec('y', "Path to the unit fragment", 249),
+    spec('Y', "Directory of the unit fragment", 249),
+    spec('%', "Single percent sign", 0),
+];
+
+/// Look up a specifier by its code character
+#[must_use]
+pub fn lookup(code: char) -> Option<&'static Specifier> {
+    SPECIFIERS.iter().find(|s| s.code == code)
+}
+
+/// Known specifiers used in a value, in order of first use
+#[must_use]
+pub fn used(value: &str) -> Vec<&'static Specifier> {
+    let mut found: Vec<&'static Specifier> = Vec::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        if let Some(spec) = chars.next().and_then(lookup) {
+            if spec.code != '%' && !found.iter().any(|s| s.code == spec.code) {
+                found.push(spec);
+            }
+        }
+    }
+
+    found
+}
+
+impl std::fmt::Display for Specifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "%{}  {}", self.code, self.meaning)?;
+        if let ScopeRule::PerManager { system, user } = self.scope {
+            write!(f, " (system: {system}, user: {user})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A problem with the specifiers used in a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecifierIssue {
+    /// Not a specifier systemd knows; the setting will be rejected
+    Unknown(char),
+    /// A `%` at the end of the value
+    Trailing,
+    /// Needs a newer systemd than the one installed
+    TooNew { code: char, since: u32 },
+    /// Instance specifier used in a unit that is not a template
+    NotInstance(char),
+}
+
+impl SpecifierIssue {
+    /// Whether systemd will refuse the setting outright
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Self::NotInstance(_))
+    }
+}
+
+impl std::fmt::Display for SpecifierIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown(c) => write!(f, "Unknown specifier '%{c}' (use '%%' for a literal '%')"),
+            Self::Trailing => write!(f, "Trailing '%' (use '%%' for a literal '%')"),
+            Self::TooNew { code, since } => {
+                write!(f, "Specifier '%{code}' requires systemd {since} or newer")
+            }
+            Self::NotInstance(c) => write!(
+                f,
+                "Specifier '%{c}' is empty outside template units (name@.type)"
+            ),
+        }
+    }
+}
+
+/// Check every specifier in a value
+#[must_use]
+pub fn check(
+    value: &str,
+    is_template: bool,
+    version: Option<&SystemdVersion>,
+) -> Vec<SpecifierIssue> {
+    let mut issues = Vec::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        let Some(code) = chars.next() else {
+            issues.push(SpecifierIssue::Trailing);
+            break;
+        };
+        match lookup(code) {
+            None => issues.push(SpecifierIssue::Unknown(code)),
+            Some(spec) => {
+                if let Some(v) = version {
+                    if !v.supports_feature(spec.since) {
+                        issues.push(SpecifierIssue::TooNew {
+                            code,
+                            since: spec.since,
+                        });
+                    }
+                }
+                if spec.instance_only && !is_template {
+                    issues.push(SpecifierIssue::NotInstance(code));
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+/// Everything needed to resolve specifiers for one unit
+#[derive(Debug, Clone)]
+pub struct ExpandContext {
+    /// Full unit name, e.g. `foo@bar.service`
+    pub unit_name: String,
+    /// Path of the unit file, for `%y` and `%Y`
+    pub unit_path: Option<String>,
+    pub system: bool,
+}
+
+impl ExpandContext {
+    fn name_without_suffix(&self) -> &str {
+        self.unit_name
+            .rsplit_once('.')
+            .map_or(self.unit_name.as_str(), |(n, _)| n)
+    }
+
+    fn prefix(&self) -> &str {
+        let name = self.name_without_suffix();
+        name.split_once('@').map_or(name, |(p, _)| p)
+    }
+
+    fn instance(&self) -> &str {
+        self.name_without_suffix()
+            .split_once('@')
+            .map_or("", |(_, i)| i)
+    }
+
+    /// Resolve a single specifier, `None` if it is unknown
+    #[must_use]
+    pub fn resolve(&self, code: char) -> Option<String> {
+        let value = match code {
+            '%' => "%".to_string(),
+            'a' => architecture().to_string(),
+            'A' => os_release("IMAGE_VERSION"),
+            'b' => read_trimmed("/proc/sys/kernel/random/boot_id").replace('-', ""),
+            'B' => os_release("BUILD_ID"),
+            'C' => self.dir("/var/cache", "XDG_CACHE_HOME", ".cache"),
+            'd' => format!("{}/credentials/{}", self.runtime_dir(), self.unit_name),
+            'D' => self.dir("/usr/share", "XDG_DATA_HOME", ".local/share"),
+            'E' => self.dir("/etc", "XDG_CONFIG_HOME", ".config"),
+            'f' => {
+                let source = if self.instance().is_empty() {
+                    self.prefix()
+                } else {
+                    self.instance()
+                };
+                escape::unescape_path(source).unwrap_or_else(|_| format!("/{source}"))
+            }
+            'g' => self.user_value("root", group_name),
+            'G' => self.user_value("0", || owner_ids().1.to_string()),
+            'h' => self.user_value("/root", || env::var("HOME").unwrap_or_default()),
+            'H' => hostname(),
+            'i' => self.instance().to_string(),
+            'I' => lenient_unescape(self.instance()),
+            'j' => self.prefix().rsplit('-').next().unwrap_or("").to_string(),
+            'J' => lenient_unescape(self.prefix().rsplit('-').next().unwrap_or("")),
+            'l' => hostname().split('.').next().unwrap_or("").to_string(),
+            'L' => {
+                if self.system {
+                    "/var/log".to_string()
+                } else {
+                    format!("{}/log", self.dir("", "XDG_STATE_HOME", ".local/state"))
+                }
+            }
+            'm' => read_trimmed("/etc/machine-id"),
+            'M' => os_release("IMAGE_ID"),
+            'n' => self.unit_name.clone(),
+            'N' => self.name_without_suffix().to_string(),
+            'o' => os_release("ID"),
+            'p' => self.prefix().to_string(),
+            'P' => lenient_unescape(self.prefix()),
+            'q' => pretty_hostname(),
+            's' => self.user_value("/bin/sh", || {
+                env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+            }),
+            'S' => self.dir("/var/lib", "XDG_STATE_HOME", ".local/state"),
+            't' => self.runtime_dir(),
+            'T' => env::var("TMPDIR").unwrap_or_else(|_| "/tmp".to_string()),
+            'u' => self.user_value("root", || {
+                env::var("USER")
+                    .or_else(|_| env::var("LOGNAME"))
+                    .unwrap_or_default()
+            }),
+            'U' => self.user_value("0", || owner_ids().0.to_string()),
+            'v' => read_trimmed("/proc/sys/kernel/osrelease"),
+            'V' => env::var("TMPDIR").unwrap_or_else(|_| "/var/tmp".to_string()),
+            'w' => os_release("VERSION_ID"),
+            'W' => os_release("VARIANT_ID"),
+            'y' => self.unit_path.clone().unwrap_or_default(),
+            'Y' => self
+                .unit_path
+                .as_deref()
+                .and_then(|p| p.rsplit_once('/'))
+                .map(|(dir, _)| dir.to_string())
+                .unwrap_or_default(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn user_value(&self, system_value: &str, user_value: impl FnOnce() -> String) -> String {
+        if self.system {
+            system_value.to_string()
+        } else {
+            user_value()
+        }
+    }
+
+    fn dir(&self, system_dir: &str, xdg_var: &str, home_relative: &str) -> String {
+        if self.system {
+            return system_dir.to_string();
+        }
+        env::var(xdg_var)
+            .unwrap_or_else(|_| format!("{}/{home_relative}", env::var("HOME").unwrap_or_default()))
+    }
+
+    fn runtime_dir(&self) -> String {
+        if self.system {
+            return "/run".to_string();
+        }
+        env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| format!("/run/user/{}", owner_ids().0))
+    }
+}
+
+/// Replace every specifier in a value; unknown specifiers are left untouched
+#[must_use]
+pub fn expand(value: &str, ctx: &ExpandContext) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        if let Some(code) = chars.next() {
+            if let Some(resolved) = ctx.resolve(code) {
+                out.push_str(&resolved);
+            } else {
+                out.push('%');
+                out.push(code);
+            }
+        } else {
+            out.push('%');
+        }
+    }
+
+    out
+}
+
+/// Unescape a unit name part, keeping it as is if it isn't validly escaped
+fn lenient_unescape(name: &str) -> String {
+    escape::unescape(name).unwrap_or_else(|_| name.to_string())
+}
+
+fn read_trimmed(path: &str) -> String {
+    fs::read_to_string(path)
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn hostname() -> String {
+    read_trimmed("/proc/sys/kernel/hostname")
+}
+
+fn pretty_hostname() -> String {
+    let pretty = fs::read_to_string("/etc/machine-info")
+        .ok()
+        .and_then(|content| key_value(&content, "PRETTY_HOSTNAME"));
+    pretty.unwrap_or_else(|| hostname().split('.').next().unwrap_or("").to_string())
+}
+
+fn os_release(key: &str) -> String {
+    fs::read_to_string("/etc/os-release")
+        .or_else(|_| fs::read_to_string("/usr/lib/os-release"))
+        .ok()
+        .and_then(|content| key_value(&content, key))
+        .unwrap_or_default()
+}
+
+/// Read a `KEY=value` entry from an os-release style file
+fn key_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key).then(|| v.trim().trim_matches(['"', '\'']).to_string())
+    })
+}
+
+/// UID and GID of the current process
+fn owner_ids() -> (u32, u32) {
+    fs::metadata("/proc/self").map_or((0, 0), |m| (m.uid(), m.gid()))
+}
+
+fn group_name() -> String {
+    let gid = owner_ids().1.to_string();
+    fs::read_to_string("/etc/group")
+        .ok()
+        .and_then(|content| {
+            content.lines().find_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                (fields.nth(1)? == gid).then(|| name.to_string())
+            })
+        })
+        .unwrap_or(gid)
+}
+
+/// Architecture name as systemd spells it
+fn architecture() -> &'static str {
+    match env::consts::ARCH {
+        "x86_64" => "x86-64",
+        "aarch64" => "arm64",
+        "powerpc64" if cfg!(target_endian = "little") => "ppc64-le",
+        "powerpc64" => "ppc64",
+        "loongarch64" => "loongarch64",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(unit_name: &str, system: bool) -> ExpandContext {
+        ExpandContext {
+            unit_name: unit_name.to_string(),
+            unit_path: Some(format!("/etc/systemd/system/{unit_name}")),
+            system,
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup('i').unwrap().meaning, "Instance name");
+        assert!(lookup('z').is_none());
+    }
+
+    #[test]
+    fn test_check_unknown_and_trailing() {
+        assert_eq!(check("100%", false, None), vec![SpecifierIssue::Trailing]);
+        assert_eq!(
+            check("100%x", false, None),
+            vec![SpecifierIssue::Unknown('x')]
+        );
+        assert!(check("50%% of %n", false, None).is_empty());
+    }
+
+    #[test]
+    fn test_check_instance_and_version() {
+        assert_eq!(
+            check("/srv/%i", false, None),
+            vec![SpecifierIssue::NotInstance('i')]
+        );
+        assert!(check("/srv/%i", true, None).is_empty());
+        // %f falls back to the prefix outside templates
+        assert!(check("%f", false, None).is_empty());
+
+        let old = SystemdVersion {
+            major: 249,
+            full_version: "systemd 249".to_string(),
+        };
+        assert_eq!(
+            check("%D/app", false, Some(&old)),
+            vec![SpecifierIssue::TooNew {
+                code: 'D',
+                since: 256
+            }]
+        );
+    }
+
+    #[test]
+    fn test_expand_unit_names() {
+        let c = ctx("backup@srv-data.service", true);
+        assert_eq!(
+            expand("%n %N %p %i", &c),
+            "backup@srv-data.service backup@srv-data backup srv-data"
+        );
+        assert_eq!(expand("%I %f", &c), "srv/data /srv/data");
+        assert_eq!(expand("%h %t 100%%", &c), "/root /run 100%");
+        assert_eq!(expand("%Y", &c), "/etc/systemd/system");
+        assert_eq!(expand("%z", &c), "%z");
+        assert_eq!(expand("%f", &ctx("srv-data.mount", true)), "/srv/data");
+    }
+}
@@ -0,0 +1,232 @@
+//! Time spans such as `RestartSec=` and `OnBootSec=`, see systemd.time(7)
+//!
+//! A span is one or more numbers with a unit, like `1h 30min` or `2weeks`;
+//! a number without a unit is in seconds. Spans are stored in microseconds
+//! and printed the way systemd normalizes them.
+
+use crate::error::{MkunitError, Result};
+use jiff::SignedDuration;
+use std::fmt;
+use std::str::FromStr;
+
+const USEC_PER_SEC: u64 = 1_000_000;
+const USEC_PER_MINUTE: u64 = 60 * USEC_PER_SEC;
+const USEC_PER_HOUR: u64 = 60 * USEC_PER_MINUTE;
+const USEC_PER_DAY: u64 = 24 * USEC_PER_HOUR;
+const USEC_PER_WEEK: u64 = 7 * USEC_PER_DAY;
+/// 30.44 days, as used by systemd
+const USEC_PER_MONTH: u64 = 2_629_800 * USEC_PER_SEC;
+/// 365.25 days, as used by systemd
+const USEC_PER_YEAR: u64 = 31_557_600 * USEC_PER_SEC;
+
+/// Unit names accepted when parsing, and their length in microseconds
+const UNITS: &[(&[&str], u64)] = &[
+    (&["us", "usec", "µs"], 1),
+    (&["ms", "msec"], 1000),
+    (&["s", "sec", "second", "seconds"], USEC_PER_SEC),
+    (&["m", "min", "minute", "minutes"], USEC_PER_MINUTE),
+    (&["h", "hr", "hour", "hours"], USEC_PER_HOUR),
+    (&["d", "day", "days"], USEC_PER_DAY),
+    (&["w", "week", "weeks"], USEC_PER_WEEK),
+    (&["M", "month", "months"], USEC_PER_MONTH),
+    (&["y", "year", "years"], USEC_PER_YEAR),
+];
+
+/// Units used when printing, largest first
+const FORMAT_UNITS: &[(&str, u64)] = &[
+    ("y", USEC_PER_YEAR),
+    ("month", USEC_PER_MONTH),
+    ("w", USEC_PER_WEEK),
+    ("d", USEC_PER_DAY),
+    ("h", USEC_PER_HOUR),
+    ("min", USEC_PER_MINUTE),
+    ("s", USEC_PER_SEC),
+    ("ms", 1000),
+    ("us", 1),
+];
+
+/// A parsed time span
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timespan {
+    usec: u64,
+}
+
+impl Timespan {
+    pub const INFINITY: Self = Self { usec: u64::MAX };
+
+    #[must_use]
+    pub fn is_zero(self) -> bool {
+        self.usec == 0
+    }
+
+    /// The span as a duration, `None` for infinity
+    #[must_use]
+    pub fn to_duration(self) -> Option<SignedDuration> {
+        if self.usec == Self::INFINITY.usec {
+            return None;
+        }
+        i64::try_from(self.usec)
+            .ok()
+            .map(SignedDuration::from_micros)
+    }
+}
+
+impl FromStr for Timespan {
+    type Err = MkunitError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            MkunitError::invalid_argument(format!(
+                "Invalid time span '{value}': {reason} (e.g., 30s, 5min, 1h 30min, 2weeks)"
+            ))
+        };
+
+        let input = value.trim();
+        if input == "infinity" {
+            return Ok(Self::INFINITY);
+        }
+        if input.is_empty() {
+            return Err(invalid("empty"));
+        }
+
+        let mut total: u64 = 0;
+        let mut rest = input;
+        while !rest.is_empty() {
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(rest.len());
+            let (number, after) = rest.split_at(digits);
+            if number.is_empty() {
+                return Err(invalid("expected a number"));
+            }
+            let after = after.trim_start();
+            let unit_len = after
+                .find(|c: char| !c.is_alphabetic())
+                .unwrap_or(after.len());
+            let (unit, after) = after.split_at(unit_len);
+
+            let multiplier = if unit.is_empty() {
+                USEC_PER_SEC
+            } else {
+                UNITS
+                    .iter()
+                    .find(|(names, _)| names.contains(&unit))
+                    .map(|(_, usec)| *usec)
+                    .ok_or_else(|| invalid(&format!("unknown unit '{unit}'")))?
+            };
+
+            let amount = scale(number, multiplier).ok_or_else(|| invalid("invalid number"))?;
+            total = total
+                .checked_add(amount)
+                .filter(|&t| t != u64::MAX)
+                .ok_or_else(|| invalid("too large"))?;
+            rest = after.trim_start();
+        }
+
+        Ok(Self { usec: total })
+    }
+}
+
+/// Multiply a decimal number like `1.5` by a unit length without floats
+fn scale(number: &str, multiplier: u64) -> Option<u64> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    if fraction.contains('.') {
+        return None;
+    }
+
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let mut amount = whole.checked_mul(multiplier)?;
+
+    // Digits beyond microsecond precision are dropped
+    let mut scale = multiplier;
+    for digit in fraction.bytes() {
+        scale /= 10;
+        if scale == 0 {
+            break;
+        }
+        amount = amount.checked_add(u64::from(digit - b'0') * scale)?;
+    }
+    Some(amount)
+}
+
+impl fmt::Display for Timespan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.usec == u64::MAX {
+            return f.write_str("infinity");
+        }
+        if self.usec == 0 {
+            return f.write_str("0");
+        }
+
+        let mut rest = self.usec;
+        let mut first = true;
+        for (name, size) in FORMAT_UNITS {
+            if rest < *size {
+                continue;
+            }
+            if !first {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}{name}", rest / size)?;
+            rest %= size;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn normalize(value: &str) -> String {
+        value.parse::<Timespan>().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(normalize("90"), "1min 30s");
+        assert_eq!(normalize("1h 30min"), "1h 30min");
+        assert_eq!(normalize("1h30m"), "1h 30min");
+        assert_eq!(normalize("2weeks"), "2w");
+        assert_eq!(normalize("500ms"), "500ms");
+        assert_eq!(normalize("1.5s"), "1s 500ms");
+        assert_eq!(normalize("0.5h"), "30min");
+        assert_eq!(normalize("3 days"), "3d");
+        assert_eq!(normalize("1y"), "1y");
+        assert_eq!(normalize("0"), "0");
+        assert_eq!(normalize("infinity"), "infinity");
+        assert_eq!(
+            "5min".parse::<Timespan>().unwrap(),
+            Timespan {
+                usec: 300 * USEC_PER_SEC
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("".parse::<Timespan>().is_err());
+        assert!("5 parsecs".parse::<Timespan>().is_err());
+        assert!("-5s".parse::<Timespan>().is_err());
+        assert!("1..5s".parse::<Timespan>().is_err());
+        assert!("min".parse::<Timespan>().is_err());
+        assert!("999999999999y".parse::<Timespan>().is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn normalized_form_round_trips(usec in 0..u64::MAX / 2) {
+            let span = Timespan { usec };
+            prop_assert_eq!(span.to_string().parse::<Timespan>().unwrap(), span);
+        }
+    }
+}
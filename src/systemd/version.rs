@@ -68,6 +68,7 @@ fn parse_version(output: &str) -> Result<SystemdVersion> {
 
 /// Check if systemd is available on this system
 #[must_use]
+#[allow(clippy::map_unwrap_or)]
 pub fn is_systemd_available() -> bool {
     Command::new("systemctl")
         .arg("--version")
@@ -9,7 +9,10 @@ const TIMER_TEMPLATE: &str = include_str!("../../templates/timer.unit");
 const PATH_TEMPLATE: &str = include_str!("../../templates/path.unit");
 const SOCKET_TEMPLATE: &str = include_str!("../../templates/socket.unit");
 const MOUNT_TEMPLATE: &str = include_str!("../../templates/mount.unit");
+const AUTOMOUNT_TEMPLATE: &str = include_str!("../../templates/automount.unit");
+const SWAP_TEMPLATE: &str = include_str!("../../templates/swap.unit");
 const TARGET_TEMPLATE: &str = include_str!("../../templates/target.unit");
+const SLICE_TEMPLATE: &str = include_str!("../../templates/slice.unit");
 
 /// Template registry for rendering unit files
 pub struct Templates {
@@ -28,7 +31,10 @@ impl Templates {
         handlebars.register_template_string("path", PATH_TEMPLATE)?;
         handlebars.register_template_string("socket", SOCKET_TEMPLATE)?;
         handlebars.register_template_string("mount", MOUNT_TEMPLATE)?;
+        handlebars.register_template_string("automount", AUTOMOUNT_TEMPLATE)?;
+        handlebars.register_template_string("swap", SWAP_TEMPLATE)?;
         handlebars.register_template_string("target", TARGET_TEMPLATE)?;
+        handlebars.register_template_string("slice", SLICE_TEMPLATE)?;
 
         Ok(Self { handlebars })
     }
@@ -60,9 +66,21 @@ impl Templates {
         self.render_with_marker("mount", data)
     }
 
+    pub fn render_automount(&self, data: &AutomountData) -> Result<String> {
+        self.render_with_marker("automount", data)
+    }
+
+    pub fn render_swap(&self, data: &SwapData) -> Result<String> {
+        self.render_with_marker("swap", data)
+    }
+
     pub fn render_target(&self, data: &TargetData) -> Result<String> {
         self.render_with_marker("target", data)
     }
+
+    pub fn render_slice(&self, data: &SliceData) -> Result<String> {
+        self.render_with_marker("slice", data)
+    }
 }
 
 impl Default for Templates {
@@ -103,17 +121,47 @@ pub struct ServiceData {
     pub after: Option<String>,
     pub wants: Option<String>,
     pub requires: Option<String>,
+    /// Quoted paths whose mounts must be up before the service starts
+    pub requires_mounts_for: Option<String>,
     pub service_type: String,
-    pub exec: String,
+    /// `Exec*=` command lines already quoted for systemd, see [`crate::systemd::exec`]
+    pub exec_start_pre: Vec<String>,
+    pub exec: Vec<String>,
+    pub exec_start_post: Vec<String>,
+    pub exec_reload: Vec<String>,
+    pub exec_stop: Vec<String>,
+    pub exec_stop_post: Vec<String>,
     pub workdir: Option<String>,
     pub user: Option<String>,
     pub group: Option<String>,
+    /// `socket` for services started per connection
+    pub standard_input: Option<String>,
+    pub standard_error: Option<String>,
     pub restart: String,
-    pub restart_sec: u32,
+    pub restart_sec: String,
+    pub timeout_start_sec: Option<String>,
+    pub timeout_stop_sec: Option<String>,
+    /// Quoted `Environment=` assignments, see [`crate::systemd::quote`]
     pub env: Vec<String>,
     pub env_file: Option<String>,
     pub hardening: bool,
-    pub wanted_by: String,
+    pub resources: ResourceData,
+    /// Install target, `None` for services started by a timer or path unit
+    pub wanted_by: Option<String>,
+    pub default_instance: Option<String>,
+}
+
+/// Resource control settings for a service, already in systemd syntax
+#[derive(Debug, Default, Serialize)]
+pub struct ResourceData {
+    pub slice: Option<String>,
+    pub memory_high: Option<String>,
+    pub memory_max: Option<String>,
+    pub cpu_quota: Option<String>,
+    pub cpu_weight: Option<String>,
+    pub io_weight: Option<String>,
+    pub tasks_max: Option<String>,
+    pub limit_nofile: Option<String>,
 }
 
 impl Default for ServiceData {
@@ -123,51 +171,79 @@ impl Default for ServiceData {
             after: Some("network.target".to_string()),
             wants: None,
             requires: None,
+            requires_mounts_for: None,
             service_type: "simple".to_string(),
-            exec: String::new(),
+            exec_start_pre: Vec::new(),
+            exec: Vec::new(),
+            exec_start_post: Vec::new(),
+            exec_reload: Vec::new(),
+            exec_stop: Vec::new(),
+            exec_stop_post: Vec::new(),
             workdir: None,
             user: None,
             group: None,
+            standard_input: None,
+            standard_error: None,
             restart: "on-failure".to_string(),
-            restart_sec: 5,
+            restart_sec: "5s".to_string(),
+            timeout_start_sec: None,
+            timeout_stop_sec: None,
             env: Vec::new(),
             env_file: None,
             hardening: false,
-            wanted_by: "default.target".to_string(),
+            resources: ResourceData::default(),
+            wanted_by: Some("default.target".to_string()),
+            default_instance: None,
         }
     }
 }
 
 /// Data for timer unit template
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Serialize)]
 pub struct TimerData {
     pub description: String,
-    pub on_calendar: Option<String>,
-    pub on_boot: Option<String>,
-    pub on_startup: Option<String>,
-    pub on_active: Option<String>,
-    pub on_unit_active: Option<String>,
-    pub on_unit_inactive: Option<String>,
+    pub on_calendar: Vec<String>,
+    pub on_boot: Vec<String>,
+    pub on_startup: Vec<String>,
+    pub on_active: Vec<String>,
+    pub on_unit_active: Vec<String>,
+    pub on_unit_inactive: Vec<String>,
+    pub on_clock_change: bool,
+    pub on_timezone_change: bool,
     pub persistent: bool,
     pub randomize_delay: Option<String>,
+    pub fixed_random_delay: bool,
+    pub accuracy: Option<String>,
+    pub wake_system: bool,
+    /// `true` or `false`, omitted to keep the systemd default
+    pub remain_after_elapse: Option<String>,
     pub unit: String,
     pub wanted_by: String,
+    pub default_instance: Option<String>,
 }
 
 impl Default for TimerData {
     fn default() -> Self {
         Self {
             description: String::new(),
-            on_calendar: None,
-            on_boot: None,
-            on_startup: None,
-            on_active: None,
-            on_unit_active: None,
-            on_unit_inactive: None,
+            on_calendar: Vec::new(),
+            on_boot: Vec::new(),
+            on_startup: Vec::new(),
+            on_active: Vec::new(),
+            on_unit_active: Vec::new(),
+            on_unit_inactive: Vec::new(),
+            on_clock_change: false,
+            on_timezone_change: false,
             persistent: false,
             randomize_delay: None,
+            fixed_random_delay: false,
+            accuracy: None,
+            wake_system: false,
+            remain_after_elapse: None,
             unit: String::new(),
             wanted_by: "timers.target".to_string(),
+            default_instance: None,
         }
     }
 }
@@ -184,6 +260,7 @@ pub struct PathData {
     pub make_directory: bool,
     pub unit: String,
     pub wanted_by: String,
+    pub default_instance: Option<String>,
 }
 
 impl Default for PathData {
@@ -198,42 +275,118 @@ impl Default for PathData {
             make_directory: false,
             unit: String::new(),
             wanted_by: "default.target".to_string(),
+            default_instance: None,
         }
     }
 }
 
 /// Data for socket unit template
 #[derive(Debug, Serialize, Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct SocketData {
     pub description: String,
-    pub listen_stream: Option<String>,
-    pub listen_datagram: Option<String>,
-    pub listen_fifo: Option<String>,
+    pub listen_stream: Vec<String>,
+    pub listen_datagram: Vec<String>,
+    pub listen_sequential_packet: Vec<String>,
+    pub listen_fifo: Vec<String>,
+    pub listen_netlink: Vec<String>,
+    pub socket_user: Option<String>,
+    pub socket_group: Option<String>,
+    pub socket_mode: Option<String>,
+    pub file_descriptor_name: Option<String>,
+    pub reuse_port: bool,
+    /// `default`, `both` or `ipv6-only`
+    pub bind_ipv6_only: Option<String>,
+    pub backlog: Option<u32>,
+    pub no_delay: bool,
+    pub keep_alive: bool,
     pub accept: bool,
     pub max_connections: Option<u32>,
     pub unit: Option<String>,
+    pub default_instance: Option<String>,
 }
 
 /// Data for mount unit template
 #[derive(Debug, Serialize)]
 pub struct MountData {
     pub description: String,
+    pub requires: Option<String>,
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub wants: Option<String>,
     pub what: String,
     pub r#where: String,
     pub fs_type: Option<String>,
     pub options: Option<String>,
-    pub wanted_by: String,
+    pub timeout_sec: Option<String>,
+    /// `None` leaves out [Install], for mounts started by an automount
+    pub wanted_by: Option<String>,
 }
 
 impl Default for MountData {
     fn default() -> Self {
         Self {
             description: String::new(),
+            requires: None,
+            after: None,
+            before: None,
+            wants: None,
             what: String::new(),
             r#where: String::new(),
             fs_type: None,
             options: None,
-            wanted_by: "multi-user.target".to_string(),
+            timeout_sec: None,
+            wanted_by: Some("multi-user.target".to_string()),
+        }
+    }
+}
+
+/// Data for automount unit template
+#[derive(Debug, Serialize)]
+pub struct AutomountData {
+    pub description: String,
+    pub r#where: String,
+    pub timeout_idle_sec: Option<String>,
+    pub directory_mode: Option<String>,
+    pub extra_options: Option<String>,
+    pub wanted_by: String,
+}
+
+impl Default for AutomountData {
+    fn default() -> Self {
+        Self {
+            description: String::new(),
+            r#where: String::new(),
+            timeout_idle_sec: None,
+            directory_mode: None,
+            extra_options: None,
+            wanted_by: "local-fs.target".to_string(),
+        }
+    }
+}
+
+/// Data for swap unit template
+#[derive(Debug, Serialize)]
+pub struct SwapData {
+    pub description: String,
+    pub what: String,
+    /// Kept as a string so that priority 0 is still rendered
+    pub priority: Option<String>,
+    pub options: Option<String>,
+    pub timeout_sec: Option<String>,
+    /// `None` leaves out [Install], for swap that is only activated on demand
+    pub wanted_by: Option<String>,
+}
+
+impl Default for SwapData {
+    fn default() -> Self {
+        Self {
+            description: String::new(),
+            what: String::new(),
+            priority: None,
+            options: None,
+            timeout_sec: None,
+            wanted_by: Some("swap.target".to_string()),
         }
     }
 }
@@ -260,6 +413,14 @@ impl Default for TargetData {
     }
 }
 
+/// Data for slice unit template
+#[derive(Debug, Default, Serialize)]
+pub struct SliceData {
+    pub description: String,
+    /// Only the cgroup limits apply; `slice` and `limit_nofile` are ignored
+    pub resources: ResourceData,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,7 +430,7 @@ mod tests {
         let templates = Templates::new().unwrap();
         let data = ServiceData {
             description: "Test service".to_string(),
-            exec: "/usr/bin/test".to_string(),
+            exec: vec!["/usr/bin/test".to_string()],
             ..Default::default()
         };
 
@@ -277,6 +438,14 @@ mod tests {
         assert!(result.contains(MKUNIT_MARKER));
         assert!(result.contains("Description=Test service"));
         assert!(result.contains("ExecStart=/usr/bin/test"));
+        assert!(result.contains("WantedBy=default.target"));
+
+        let triggered = ServiceData {
+            wanted_by: None,
+            ..data
+        };
+        let result = templates.render_service(&triggered).unwrap();
+        assert!(!result.contains("[Install]"));
     }
 
     #[test]
@@ -284,13 +453,13 @@ mod tests {
         let templates = Templates::new().unwrap();
         let data = TimerData {
             description: "Test timer".to_string(),
-            on_calendar: Some("daily".to_string()),
+            on_calendar: vec!["daily".to_string(), "Sat 12:00".to_string()],
             unit: "test.service".to_string(),
             ..Default::default()
         };
 
         let result = templates.render_timer(&data).unwrap();
-        assert!(result.contains("OnCalendar=daily"));
+        assert!(result.contains("OnCalendar=daily\nOnCalendar=Sat 12:00"));
         assert!(result.contains("Unit=test.service"));
     }
 
@@ -5,6 +5,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
 
 /// Initialize color support based on environment and flags
+#[allow(clippy::map_unwrap_or)]
 pub fn init(no_color_flag: bool) {
     let should_disable = no_color_flag
         || env::var("NO_COLOR").is_ok()
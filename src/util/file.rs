@@ -0,0 +1,182 @@
+use crate::error::{MkunitError, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::{chown, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// Permission bits for unit files
+pub const UNIT_FILE_MODE: u32 = 0o644;
+
+/// Convert an IO error into a permission error if that is what it is
+///
+/// The hint depends on the target: system unit directories need root, any
+/// other path (user units, `--output`) is a matter of its own permissions.
+fn map_permission_error(err: io::Error, path: &Path, root_owned: bool) -> MkunitError {
+    if err.kind() != io::ErrorKind::PermissionDenied {
+        return MkunitError::IoError(err);
+    }
+    let hint = if root_owned {
+        "System units are written by root; re-run with sudo".to_string()
+    } else {
+        let dir = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        format!("Check the permissions of {}", dir.display())
+    };
+    MkunitError::permission_denied_with_hint(format!("cannot write to {}", path.display()), hint)
+}
+
+/// Content of a file about to be replaced or removed, `None` if it doesn't exist
+///
+/// Any other error is returned: a file that can't be read must not be
+/// recorded as new, or undoing the change would delete it for good.
+pub fn read_previous(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => Err(
+            MkunitError::permission_denied(format!("cannot read {}", path.display())),
+        ),
+        Err(e) => Err(MkunitError::Other(format!(
+            "Cannot read {}: {e}",
+            path.display()
+        ))),
+    }
+}
+
+/// Build the temporary path used while writing `path`
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("unit");
+    let temp_name = format!(".{file_name}.{}.tmp", std::process::id());
+    path.with_file_name(temp_name)
+}
+
+/// Make sure the directory for `path` exists and that we can create files in it
+pub fn check_writable(path: &Path, root_owned: bool) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    fs::create_dir_all(dir).map_err(|e| map_permission_error(e, dir, root_owned))?;
+
+    // Probe with a real file: mode bits alone don't account for root or ACLs
+    let probe = temp_path_for(path);
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .map_err(|e| map_permission_error(e, dir, root_owned))?;
+    fs::remove_file(&probe)?;
+
+    Ok(())
+}
+
+/// Write a file atomically by writing to a temporary file and renaming it into place
+///
+/// The file is created with mode 0644. When `root_owned` is set the file is
+/// also chowned to root:root before the rename, as expected for system units.
+pub fn atomic_write(path: &Path, content: &[u8], root_owned: bool) -> Result<()> {
+    check_writable(path, root_owned)?;
+
+    let temp_path = temp_path_for(path);
+    let result = write_temp(&temp_path, content, root_owned)
+        .and_then(|()| fs::rename(&temp_path, path).map_err(MkunitError::IoError));
+
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+    result.map_err(|e| match e {
+        MkunitError::IoError(io) => map_permission_error(io, path, root_owned),
+        other => other,
+    })?;
+
+    // Persist the rename itself; failure here is not worth aborting over
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            dir.sync_all().ok();
+        }
+    }
+
+    Ok(())
+}
+
+fn write_temp(temp_path: &Path, content: &[u8], root_owned: bool) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(temp_path)?;
+
+    file.write_all(content)?;
+    file.set_permissions(fs::Permissions::from_mode(UNIT_FILE_MODE))?;
+    if root_owned {
+        chown(temp_path, Some(0), Some(0))?;
+    }
+    file.sync_all()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_atomic_write_creates_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("test.service");
+
+        atomic_write(&path, b"[Unit]\n", false).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[Unit]\n");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, UNIT_FILE_MODE);
+    }
+
+    #[test]
+    fn test_atomic_write_replaces_without_leftovers() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("test.service");
+        fs::write(&path, "old").unwrap();
+
+        atomic_write(&path, b"new", false).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_read_previous() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("test.service");
+        assert_eq!(read_previous(&path).unwrap(), None);
+
+        fs::write(&path, "[Unit]\n").unwrap();
+        assert_eq!(read_previous(&path).unwrap().as_deref(), Some("[Unit]\n"));
+
+        // Not UTF-8, so it can't be kept for undo
+        fs::write(&path, [0xff, 0xfe]).unwrap();
+        assert!(read_previous(&path).is_err());
+    }
+
+    #[test]
+    fn test_permission_hint() {
+        let denied = || io::Error::from(io::ErrorKind::PermissionDenied);
+        let hint = |err: MkunitError| match err {
+            MkunitError::PermissionDenied { hint, .. } => hint.unwrap_or_default(),
+            other => panic!("unexpected error {other}"),
+        };
+
+        let system = Path::new("/etc/systemd/system/app.service");
+        assert!(hint(map_permission_error(denied(), system, true)).contains("sudo"));
+        let output = Path::new("/srv/units/app.service");
+        assert_eq!(
+            hint(map_permission_error(denied(), output, false)),
+            "Check the permissions of /srv/units"
+        );
+        assert!(matches!(
+            map_permission_error(io::Error::from(io::ErrorKind::NotFound), output, false),
+            MkunitError::IoError(_)
+        ));
+    }
+}
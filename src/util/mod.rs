@@ -1,3 +1,4 @@
 pub mod color;
 pub mod editor;
+pub mod file;
 pub mod prompt;
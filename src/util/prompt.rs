@@ -62,7 +62,6 @@ pub fn prompt_optional_empty(prompt: &str, no_interactive: bool) -> Result<Optio
 }
 
 /// Prompt for selection from a list of options
-#[allow(dead_code)]
 pub fn prompt_select(
     prompt: &str,
     options: &[&str],
@@ -118,9 +118,233 @@ fn test_timer_dry_run() {
         .assert()
         .success()
         .stdout(predicate::str::contains("OnCalendar=daily"))
+        .stdout(predicate::str::contains("Normalized form: *-*-* 00:00:00"))
         .stdout(predicate::str::contains("Unit=test-timer.service"));
 }
 
+#[test]
+fn test_timer_invalid_calendar() {
+    mkunit()
+        .args([
+            "timer",
+            "test-timer",
+            "--on-calendar",
+            "Mon..Fir 09:00",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown weekday 'Fir'"));
+}
+
+#[test]
+fn test_timer_timespan() {
+    mkunit()
+        .args([
+            "timer",
+            "test-timer",
+            "--on-boot",
+            "90",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("OnBootSec=1min 30s"));
+
+    mkunit()
+        .args([
+            "timer",
+            "test-timer",
+            "--on-boot",
+            "5 parsecs",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown unit 'parsecs'"));
+}
+
+#[test]
+fn test_timer_every() {
+    mkunit()
+        .args([
+            "timer",
+            "report",
+            "--every",
+            "first monday of the month at 02:30",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "OnCalendar=Mon *-*-01..07 02:30:00",
+        ));
+
+    mkunit()
+        .args([
+            "timer",
+            "poll",
+            "--every",
+            "15 minutes",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("OnActiveSec=15min"))
+        .stdout(predicate::str::contains("OnUnitActiveSec=15min"));
+
+    mkunit()
+        .args([
+            "timer",
+            "poll",
+            "--every",
+            "now and then",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Cannot understand schedule"));
+}
+
+#[test]
+fn test_timer_exec_creates_service() {
+    let temp_dir = TempDir::new().unwrap();
+    let timer_path = temp_dir.path().join("backup.timer");
+
+    mkunit()
+        .args([
+            "timer",
+            "backup",
+            "--on-calendar",
+            "daily",
+            "--exec",
+            "/bin/true --all",
+            "--env",
+            "MODE=full",
+            "--output",
+            timer_path.to_str().unwrap(),
+            "--no-interactive",
+        ])
+        .assert()
+        .success();
+
+    let service = std::fs::read_to_string(temp_dir.path().join("backup.service")).unwrap();
+    assert!(service.contains("Type=oneshot"));
+    assert!(service.contains("ExecStart=/bin/true --all"));
+    assert!(service.contains("Environment=\"MODE=full\""));
+    assert!(!service.contains("[Install]"));
+
+    let timer = std::fs::read_to_string(&timer_path).unwrap();
+    assert!(timer.contains("Unit=backup.service"));
+}
+
+#[test]
+fn test_timer_exec_removes_service_on_failure() {
+    let temp_dir = TempDir::new().unwrap();
+    // The timer can't be written over a directory
+    let timer_path = temp_dir.path().join("backup.timer");
+    std::fs::create_dir(&timer_path).unwrap();
+
+    mkunit()
+        .args([
+            "timer",
+            "backup",
+            "--on-calendar",
+            "daily",
+            "--exec",
+            "/bin/true",
+            "--output",
+            timer_path.to_str().unwrap(),
+            "--no-interactive",
+        ])
+        .assert()
+        .failure();
+
+    assert!(!temp_dir.path().join("backup.service").exists());
+}
+
+#[test]
+fn test_path_exec_requires_service_unit() {
+    mkunit()
+        .args([
+            "path",
+            "watch",
+            "--path-changed",
+            "/tmp/in",
+            "--unit",
+            "other.target",
+            "--exec",
+            "/bin/true",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--exec creates a service"));
+}
+
+#[test]
+fn test_timer_multiple_triggers() {
+    mkunit()
+        .args([
+            "timer",
+            "sync",
+            "--on-calendar",
+            "Mon..Fri 08:00",
+            "--on-calendar",
+            "Sat 10:00",
+            "--on-boot",
+            "5min",
+            "--on-clock-change",
+            "--randomize-delay",
+            "10min",
+            "--fixed-random-delay",
+            "--accuracy",
+            "1s",
+            "--wake-system",
+            "--remain-after-elapse",
+            "false",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "OnCalendar=Mon..Fri 08:00\nOnCalendar=Sat 10:00",
+        ))
+        .stdout(predicate::str::contains("OnBootSec=5min"))
+        .stdout(predicate::str::contains("OnClockChange=true"))
+        .stdout(predicate::str::contains("FixedRandomDelay=true"))
+        .stdout(predicate::str::contains("AccuracySec=1s"))
+        .stdout(predicate::str::contains("WakeSystem=true"))
+        .stdout(predicate::str::contains("RemainAfterElapse=false"));
+}
+
+#[test]
+fn test_calendar() {
+    mkunit()
+        .args(["calendar", "Sat,Sun 10:00 UTC", "-n", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Normalized form: Sat,Sun *-*-* 10:00:00 UTC",
+        ))
+        .stdout(predicate::str::contains("Next elapse: "))
+        .stdout(predicate::str::contains("Iter. #2: "));
+
+    mkunit()
+        .args(["calendar", "*-02-30"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Next elapse: never"));
+}
+
 #[test]
 fn test_timer_with_persistent() {
     mkunit()
@@ -174,12 +398,174 @@ fn test_socket_dry_run() {
         .stdout(predicate::str::contains("ListenStream=8080"));
 }
 
+#[test]
+fn test_socket_privileged_port_warning() {
+    mkunit()
+        .args([
+            "socket",
+            "web",
+            "--listen-stream",
+            "80",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ListenStream=80"))
+        .stderr(predicate::str::contains("privileged port"));
+
+    mkunit()
+        .args([
+            "socket",
+            "web",
+            "--listen-stream",
+            "80",
+            "--system",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("privileged port").not());
+}
+
+#[test]
+fn test_socket_multiple_listeners() {
+    mkunit()
+        .args([
+            "socket",
+            "web",
+            "--listen-stream",
+            "0.0.0.0:80",
+            "--listen-stream",
+            "[::]:80",
+            "--listen-sequential-packet",
+            "/run/web.seqpacket",
+            "--listen-netlink",
+            "kobject-uevent 1",
+            "--socket-mode",
+            "660",
+            "--reuse-port",
+            "--bind-ipv6-only",
+            "ipv6-only",
+            "--backlog",
+            "128",
+            "--keep-alive",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "ListenStream=0.0.0.0:80\nListenStream=[::]:80\n",
+        ))
+        .stdout(predicate::str::contains(
+            "ListenSequentialPacket=/run/web.seqpacket",
+        ))
+        .stdout(predicate::str::contains("ListenNetlink=kobject-uevent 1"))
+        .stdout(predicate::str::contains("SocketMode=0660"))
+        .stdout(predicate::str::contains("ReusePort=true"))
+        .stdout(predicate::str::contains("BindIPv6Only=ipv6-only"))
+        .stdout(predicate::str::contains("Backlog=128"))
+        .stdout(predicate::str::contains("KeepAlive=true"));
+}
+
+#[test]
+fn test_socket_accept_creates_template_service() {
+    mkunit()
+        .args([
+            "socket",
+            "echo",
+            "--listen-stream",
+            "7777",
+            "--accept",
+            "--max-connections",
+            "32",
+            "--exec",
+            "/bin/cat",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("echo@.service"))
+        .stdout(predicate::str::contains("StandardInput=socket"))
+        .stdout(predicate::str::contains("StandardError=journal"))
+        .stdout(predicate::str::contains("Accept=true"))
+        .stdout(predicate::str::contains("MaxConnections=32"))
+        .stdout(predicate::str::contains("Service=").not());
+
+    mkunit()
+        .args([
+            "socket",
+            "web",
+            "--listen-stream",
+            "8080",
+            "--exec",
+            "/bin/true",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web.service"))
+        .stdout(predicate::str::contains("Type=simple"))
+        .stdout(predicate::str::contains("StandardInput").not());
+}
+
+#[test]
+fn test_socket_accept_conflicts() {
+    for args in [
+        vec!["--listen-stream", "80", "--accept", "--unit", "web.service"],
+        vec!["--listen-datagram", "514", "--accept"],
+        vec!["--listen-stream", "80", "--max-connections", "3"],
+        vec![
+            "--listen-stream",
+            "80",
+            "--exec",
+            "/bin/a",
+            "--exec",
+            "/bin/b",
+        ],
+    ] {
+        mkunit()
+            .args(["socket", "web"])
+            .args(args)
+            .args(["--dry-run", "--no-interactive"])
+            .assert()
+            .failure();
+    }
+}
+
+#[test]
+fn test_socket_invalid_listeners() {
+    for (option, value) in [
+        ("--listen-stream", "70000"),
+        ("--listen-stream", "::1:80"),
+        ("--listen-datagram", "run/app.sock"),
+        ("--listen-fifo", "/run/../app.fifo"),
+        ("--listen-netlink", "uevent"),
+    ] {
+        mkunit()
+            .args([
+                "socket",
+                "bad",
+                option,
+                value,
+                "--dry-run",
+                "--no-interactive",
+            ])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid"));
+    }
+}
+
 #[test]
 fn test_mount_dry_run() {
     mkunit()
         .args([
             "mount",
-            "test-mount",
             "--what",
             "/dev/sda1",
             "--where",
@@ -193,7 +579,126 @@ fn test_mount_dry_run() {
         .success()
         .stdout(predicate::str::contains("What=/dev/sda1"))
         .stdout(predicate::str::contains("Where=/mnt/data"))
-        .stdout(predicate::str::contains("Type=ext4"));
+        .stdout(predicate::str::contains("Type=ext4"))
+        .stdout(predicate::str::contains("mnt-data.mount"));
+}
+
+#[test]
+fn test_mount_resolves_uuid() {
+    mkunit()
+        .args([
+            "mount",
+            "--what",
+            "UUID=00000000-dead-beef",
+            "--where",
+            "/mnt/missing",
+            "--dry-run",
+            "--no-interactive",
+            "--no-color",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "What=/dev/disk/by-uuid/00000000-dead-beef",
+        ))
+        .stderr(predicate::str::contains("does not exist"));
+}
+
+#[test]
+fn test_mount_name_mismatch() {
+    mkunit()
+        .args([
+            "mount",
+            "test-mount",
+            "--what",
+            "/dev/sda1",
+            "--where",
+            "/mnt/data",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must be named 'mnt-data.mount'"));
+}
+
+#[test]
+fn test_escape() {
+    mkunit()
+        .args(["escape", "--path", "/mnt/my data", "--suffix", "mount"])
+        .assert()
+        .success()
+        .stdout("mnt-my\\x20data.mount\n");
+
+    mkunit()
+        .args(["escape", "--unescape", "--path", "mnt-my\\x20data"])
+        .assert()
+        .success()
+        .stdout("/mnt/my data\n");
+
+    mkunit()
+        .args(["escape", "--template", "backup@.service", "home/user"])
+        .assert()
+        .success()
+        .stdout("backup@home-user.service\n");
+}
+
+#[test]
+fn test_mount_with_automount_dry_run() {
+    mkunit()
+        .args([
+            "mount",
+            "mnt-nas",
+            "--what",
+            "nas:/export",
+            "--where",
+            "/mnt/nas",
+            "--type",
+            "nfs",
+            "--automount",
+            "--timeout-idle-sec",
+            "10min",
+            "--dry-run",
+            "--no-interactive",
+            "--no-color",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("mnt-nas.mount"))
+        .stdout(predicate::str::contains("mnt-nas.automount"))
+        .stdout(predicate::str::contains("[Automount]"))
+        .stdout(predicate::str::contains("TimeoutIdleSec=10min"))
+        .stdout(predicate::str::contains("After=network-online.target"))
+        .stdout(predicate::str::contains("Wants=network-online.target"))
+        .stdout(predicate::str::contains("WantedBy=remote-fs.target"))
+        .stdout(predicate::str::contains("WantedBy=multi-user.target").not());
+}
+
+#[test]
+fn test_swap_dry_run() {
+    mkunit()
+        .args([
+            "swap",
+            "swapfile",
+            "--what",
+            "/swapfile",
+            "--priority",
+            "10",
+            "--create-file",
+            "2G",
+            "--dry-run",
+            "--no-interactive",
+            "--no-color",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Would create swapfile: /swapfile (2G",
+        ))
+        .stdout(predicate::str::contains("Would run: mkswap /swapfile"))
+        .stdout(predicate::str::contains("What=/swapfile"))
+        .stdout(predicate::str::contains("Priority=10"))
+        .stdout(predicate::str::contains("WantedBy=swap.target"));
 }
 
 #[test]
@@ -241,6 +746,7 @@ fn test_service_output_to_file() {
 }
 
 #[test]
+#[allow(clippy::needless_raw_string_hashes)]
 fn test_validate_valid_file() {
     let temp_dir = TempDir::new().unwrap();
     let unit_path = temp_dir.path().join("valid.service");
@@ -263,10 +769,48 @@ WantedBy=default.target
     mkunit()
         .args(["validate", unit_path.to_str().unwrap()])
         .assert()
-        .success();
+        .success();
+}
+
+#[test]
+fn test_validate_generated_resource_limits() {
+    let temp_dir = TempDir::new().unwrap();
+    let unit_path = temp_dir.path().join("limited.service");
+
+    mkunit()
+        .args([
+            "service",
+            "limited",
+            "--exec",
+            "/bin/true",
+            "--cpu-quota",
+            "50%",
+            "--memory-high",
+            "60%",
+            "--memory-max",
+            "80%",
+            "--tasks-max",
+            "10%",
+            "--output",
+            unit_path.to_str().unwrap(),
+            "--no-interactive",
+        ])
+        .assert()
+        .success();
+
+    let content = std::fs::read_to_string(&unit_path).unwrap();
+    assert!(content.contains("CPUQuota=50%"));
+    assert!(content.contains("MemoryMax=80%"));
+
+    mkunit()
+        .args(["validate", unit_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("specifier").not());
 }
 
 #[test]
+#[allow(clippy::needless_raw_string_hashes)]
 fn test_validate_invalid_file() {
     let temp_dir = TempDir::new().unwrap();
     let unit_path = temp_dir.path().join("invalid.service");
@@ -287,6 +831,84 @@ no_section_content=bad
         .failure();
 }
 
+#[test]
+fn test_validate_mount_name() {
+    let temp_dir = TempDir::new().unwrap();
+    let unit_path = temp_dir.path().join("data.mount");
+
+    std::fs::write(
+        &unit_path,
+        r"[Mount]
+What=/dev/sda1
+Where=/mnt/data
+",
+    )
+    .unwrap();
+
+    mkunit()
+        .args(["validate", unit_path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("mnt-data.mount"));
+}
+
+#[test]
+fn test_import_fstab_dry_run() {
+    let temp_dir = TempDir::new().unwrap();
+    let fstab = temp_dir.path().join("fstab");
+
+    std::fs::write(
+        &fstab,
+        "UUID=1234 / ext4 defaults 0 1\n\
+         proc /proc proc defaults 0 0\n\
+         LABEL=data /mnt/data ext4 nofail,x-systemd.requires=foo.service 0 0\n\
+         /swapfile none swap sw,pri=5,x-systemd.before=bar.service 0 0\n",
+    )
+    .unwrap();
+
+    mkunit()
+        .args(["import", "fstab", fstab.to_str().unwrap()])
+        .args(["--dry-run", "--no-color"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("mnt-data.mount"))
+        .stdout(predicate::str::contains("What=/dev/disk/by-label/data"))
+        .stdout(predicate::str::contains("WantedBy=local-fs.target"))
+        .stdout(predicate::str::contains("swapfile.swap"))
+        .stdout(predicate::str::contains("Priority=5"))
+        .stdout(predicate::str::contains("Requires=foo.service"))
+        .stdout(predicate::str::contains("After=foo.service"))
+        .stdout(predicate::str::contains("proc.mount").not())
+        .stderr(predicate::str::contains(
+            "x-systemd.before=bar.service is not translated",
+        ));
+}
+
+#[test]
+fn test_import_cron_dry_run() {
+    mkunit()
+        .args(["import", "cron", "-", "--dry-run", "--no-color"])
+        .write_stdin(
+            "PATH=/usr/local/bin:/usr/bin\n\
+             */15 * * * * /usr/local/bin/check --quiet\n\
+             @weekly /usr/bin/rotate\n\
+             0 3 1 * 1 /usr/bin/report\n",
+        )
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("cron-check.timer"))
+        .stdout(predicate::str::contains("OnCalendar=*-*-* *:00/15:00"))
+        .stdout(predicate::str::contains("OnCalendar=Sun *-*-* 00:00:00"))
+        .stdout(predicate::str::contains("Type=oneshot"))
+        .stdout(predicate::str::contains(
+            "Environment=\"PATH=/usr/local/bin:/usr/bin\"",
+        ))
+        .stderr(predicate::str::contains("Line 4: "))
+        .stderr(predicate::str::contains(
+            "1 cron jobs could not be imported",
+        ));
+}
+
 #[test]
 fn test_completions_bash() {
     mkunit()
@@ -395,6 +1017,7 @@ fn test_link_invalid_extension() {
 }
 
 #[test]
+#[allow(clippy::needless_raw_string_hashes)]
 fn test_link_dry_run() {
     let temp_dir = TempDir::new().unwrap();
     let unit_file = temp_dir.path().join("test.service");
@@ -420,3 +1043,391 @@ WantedBy=default.target
         .success()
         .stdout(predicate::str::contains("Would create symlink"));
 }
+
+#[test]
+fn test_history_and_undo() {
+    let temp_dir = TempDir::new().unwrap();
+    let home = temp_dir.path();
+    let unit_file = home.join(".config/systemd/user/undo-me.service");
+
+    mkunit()
+        .env("HOME", home)
+        .env("XDG_STATE_HOME", home.join("state"))
+        .args([
+            "service",
+            "undo-me",
+            "--exec",
+            "/usr/bin/true",
+            "--no-interactive",
+        ])
+        .assert()
+        .success();
+    assert!(unit_file.exists());
+
+    mkunit()
+        .env("HOME", home)
+        .env("XDG_STATE_HOME", home.join("state"))
+        .arg("history")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("create undo-me.service"));
+
+    mkunit()
+        .env("HOME", home)
+        .env("XDG_STATE_HOME", home.join("state"))
+        .args(["undo", "--force"])
+        .assert()
+        .success();
+    assert!(!unit_file.exists());
+}
+
+#[test]
+fn test_timers_json() {
+    let temp_dir = TempDir::new().unwrap();
+    let home = temp_dir.path();
+    let unit_dir = home.join(".config/systemd/user");
+    std::fs::create_dir_all(&unit_dir).unwrap();
+    std::fs::write(
+        unit_dir.join("backup.timer"),
+        "[Timer]\nOnCalendar=Mon..Fri 09:00\nOnBootSec=15min\n",
+    )
+    .unwrap();
+    std::fs::write(
+        unit_dir.join("backup.service"),
+        "[Service]\nExecStart=/bin/true\n",
+    )
+    .unwrap();
+    std::fs::write(unit_dir.join("sync@.timer"), "[Timer]\nOnCalendar=hourly\n").unwrap();
+
+    mkunit()
+        .env("HOME", home)
+        .args(["timers", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"timer\": \"backup.timer\""))
+        .stdout(predicate::str::contains("\"unit\": \"backup.service\""))
+        .stdout(predicate::str::contains("\"weekdays at 09:00\""))
+        .stdout(predicate::str::contains("\"15min after boot\""))
+        .stdout(predicate::str::contains("backup.service\"").count(1))
+        .stdout(predicate::str::contains("sync@").not());
+}
+
+#[test]
+fn test_at_dry_run() {
+    let temp_dir = TempDir::new().unwrap();
+    mkunit()
+        .env("HOME", temp_dir.path())
+        .args([
+            "at",
+            "in 2 hours",
+            "--name",
+            "cache",
+            "--dry-run",
+            "--",
+            "/bin/echo",
+            "50% done",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("at-cache.service"))
+        .stdout(predicate::str::contains(
+            "ExecStart=/bin/echo \"50%% done\"",
+        ))
+        .stdout(predicate::str::contains("ExecStopPost=-/bin/rm -f "))
+        .stdout(predicate::str::contains("RemainAfterElapse=false"))
+        .stdout(predicate::str::contains("Unit=at-cache.service"))
+        .stdout(predicate::str::contains("enable at-cache.timer"));
+
+    mkunit()
+        .env("HOME", temp_dir.path())
+        .args(["at", "2h", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No command given"));
+
+    mkunit()
+        .env("HOME", temp_dir.path())
+        .args(["at", "yesterday", "--dry-run", "--", "/bin/true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Cannot understand time"));
+}
+
+#[test]
+fn test_at_list_and_cancel() {
+    let temp_dir = TempDir::new().unwrap();
+    let home = temp_dir.path();
+    let unit_dir = home.join(".config/systemd/user");
+    std::fs::create_dir_all(&unit_dir).unwrap();
+    std::fs::write(
+        unit_dir.join("at-report.timer"),
+        "# Generated by mkunit\n[Timer]\nOnCalendar=2099-01-01 09:00:00 UTC\n",
+    )
+    .unwrap();
+    std::fs::write(
+        unit_dir.join("at-report.service"),
+        "# Generated by mkunit\n[Service]\nExecStart=/usr/bin/report\n",
+    )
+    .unwrap();
+
+    mkunit()
+        .env("HOME", home)
+        .args(["at", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("at-report"))
+        .stdout(predicate::str::contains("2099-01-01 09:00:00 UTC"))
+        .stdout(predicate::str::contains("/usr/bin/report"));
+
+    mkunit()
+        .env("HOME", home)
+        .args(["at", "--cancel", "report", "--dry-run", "--no-interactive"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would remove").count(2));
+
+    mkunit()
+        .env("HOME", home)
+        .args(["at", "--cancel", "missing", "--no-interactive"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No pending job at-missing"));
+}
+
+#[test]
+fn test_service_quoting() {
+    mkunit()
+        .args([
+            "service",
+            "quoted",
+            "--exec",
+            "/bin/echo 'hello world' 50%",
+            "--literal",
+            "--env",
+            "MSG=say \"hi\"",
+            "--dry-run",
+            "--no-interactive",
+            "--no-color",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "ExecStart=/bin/echo \"hello world\" 50%%",
+        ))
+        .stdout(predicate::str::contains(
+            "Environment=\"MSG=say \\\"hi\\\"\"",
+        ));
+}
+
+#[test]
+fn test_service_exec_directives() {
+    mkunit()
+        .args([
+            "service",
+            "lifecycle",
+            "--type",
+            "oneshot",
+            "--exec",
+            "/bin/true",
+            "--exec",
+            "/bin/echo done",
+            "--exec-start-pre",
+            "-+/usr/bin/migrate --all",
+            "--exec-reload",
+            "/bin/kill -HUP $MAINPID",
+            "--exec-stop-post",
+            "/bin/rm -f /tmp/lifecycle.lock",
+            "--dry-run",
+            "--no-interactive",
+            "--no-color",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "ExecStartPre=-+/usr/bin/migrate --all",
+        ))
+        .stdout(predicate::str::contains(
+            "ExecStart=/bin/true\nExecStart=/bin/echo done",
+        ))
+        .stdout(predicate::str::contains(
+            "ExecReload=/bin/kill -HUP $MAINPID",
+        ))
+        .stdout(predicate::str::contains(
+            "ExecStopPost=/bin/rm -f /tmp/lifecycle.lock",
+        ));
+}
+
+#[test]
+fn test_service_multiple_exec_requires_oneshot() {
+    mkunit()
+        .args([
+            "service",
+            "lifecycle",
+            "--exec",
+            "/bin/true",
+            "--exec",
+            "/bin/false",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("oneshot"));
+}
+
+#[test]
+fn test_service_resource_controls() {
+    mkunit()
+        .args([
+            "service",
+            "limited",
+            "--exec",
+            "/bin/true",
+            "--memory-high",
+            "768M",
+            "--memory-max",
+            "1024M",
+            "--cpu-quota",
+            "150%",
+            "--tasks-max",
+            "512",
+            "--limit-nofile",
+            "65536",
+            "--slice",
+            "apps",
+            "--dry-run",
+            "--no-interactive",
+            "--no-color",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Slice=apps.slice"))
+        .stdout(predicate::str::contains("MemoryHigh=768M"))
+        .stdout(predicate::str::contains("MemoryMax=1G"))
+        .stdout(predicate::str::contains("CPUQuota=150%"))
+        .stdout(predicate::str::contains("TasksMax=512"))
+        .stdout(predicate::str::contains("LimitNOFILE=65536"));
+}
+
+#[test]
+fn test_service_invalid_resource_value() {
+    mkunit()
+        .args([
+            "service",
+            "limited",
+            "--exec",
+            "/bin/true",
+            "--cpu-weight",
+            "0",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("1 to 10000"));
+}
+
+#[test]
+fn test_slice_dry_run() {
+    mkunit()
+        .args([
+            "slice",
+            "workers",
+            "--memory-max",
+            "4G",
+            "--cpu-weight",
+            "50",
+            "--dry-run",
+            "--no-interactive",
+            "--no-color",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("workers.slice"))
+        .stdout(predicate::str::contains("[Slice]"))
+        .stdout(predicate::str::contains("MemoryMax=4G"))
+        .stdout(predicate::str::contains("CPUWeight=50"))
+        .stdout(predicate::str::contains("[Install]").not());
+}
+
+#[test]
+fn test_service_template() {
+    mkunit()
+        .args([
+            "service",
+            "worker",
+            "--template",
+            "--default-instance",
+            "main",
+            "--exec",
+            "/usr/bin/worker %i",
+            "--install",
+            "--dry-run",
+            "--no-interactive",
+            "--no-color",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("worker@.service"))
+        .stdout(predicate::str::contains(
+            "Description=worker service for %i",
+        ))
+        .stdout(predicate::str::contains("DefaultInstance=main"))
+        .stdout(predicate::str::contains("enable worker@main.service"));
+}
+
+#[test]
+fn test_template_install_requires_instance() {
+    mkunit()
+        .args([
+            "timer",
+            "worker",
+            "--template",
+            "--on-calendar",
+            "daily",
+            "--install",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--default-instance"));
+}
+
+#[test]
+fn test_service_invalid_env() {
+    mkunit()
+        .args([
+            "service",
+            "bad-env",
+            "--exec",
+            "/usr/bin/true",
+            "--env",
+            "NOT VALID=1",
+            "--dry-run",
+            "--no-interactive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid environment variable name",
+        ));
+}
+
+#[test]
+fn test_validate_unknown_specifier() {
+    let temp_dir = TempDir::new().unwrap();
+    let unit_path = temp_dir.path().join("spec.service");
+
+    std::fs::write(
+        &unit_path,
+        "[Unit]\nDescription=Uses 100%z\n\n[Service]\nExecStart=/usr/bin/true\n",
+    )
+    .unwrap();
+
+    mkunit()
+        .args(["validate", unit_path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown specifier '%z'"));
+}